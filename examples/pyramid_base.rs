@@ -206,6 +206,8 @@ impl Acts<Segment> for AddAction {
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
 struct MyData {}
 
+// `SizeMinSummary` counts the positions in the segments, not the segments themselves,
+// so `segment_len` isn't implemented.
 impl Data for MyData {
     type Value = Segment;
 
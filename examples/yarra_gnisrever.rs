@@ -140,6 +140,8 @@ impl Acts<Interval> for RevAction {
 
 struct RevData {}
 
+// `Size` counts the positions in the intervals, not the intervals themselves,
+// so `segment_len` isn't implemented.
 impl Data for RevData {
     type Action = RevAction;
     type Summary = Size;
@@ -29,8 +29,11 @@ impl Action for Unit {
     }
 }
 
+impl ValuePreservingAction for Unit {}
+
 /// Storing the size of a subtree.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Size {
     /// The size of a subtree
     pub size: usize,
@@ -57,9 +60,10 @@ impl SizedSummary for Size {
     }
 }
 
-/// [`Data`] instance for plain values with segment size information, so that they can be accessed.
+/// [`Data`] instance for plain values with segment size information, so that they can be accessed
+/// by index. This is the default [`Data`] instance of [`crate::map::GroveMap`].
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
-struct SizeData<V> {
+pub struct SizeData<V> {
     phantom: PhantomData<V>,
 }
 
@@ -71,8 +75,14 @@ impl<V> Data for SizeData<V> {
     fn to_summary(_val: &Self::Value) -> Self::Summary {
         Size { size: 1 }
     }
+
+    fn segment_len(summary: Self::Summary) -> Option<usize> {
+        Some(summary.size)
+    }
 }
 
+impl<V> SizedData for SizeData<V> {}
+
 /// A trait for summary instances which keep track of the size of segments.
 pub trait SizedSummary {
     /// The size of the segment
@@ -131,9 +141,70 @@ impl Acts<Size> for RevAction {
     fn act_inplace(&self, _val: &mut Size) {}
 }
 
+/// Actions that either reverse the order of a segment or keep it as it is.
+/// Unlike [`RevAction`], these never change the values themselves, so they act on any type.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, Default)]
+pub struct ReverseOrder {
+    /// Whether to reverse the segment
+    pub to_reverse: bool,
+}
+
+impl Add for ReverseOrder {
+    type Output = ReverseOrder;
+    fn add(self, b: ReverseOrder) -> ReverseOrder {
+        ReverseOrder {
+            to_reverse: self.to_reverse != b.to_reverse,
+        }
+    }
+}
+
+impl Action for ReverseOrder {
+    fn is_identity(self) -> bool {
+        self == Default::default()
+    }
+
+    fn to_reverse(self) -> bool {
+        self.to_reverse
+    }
+
+    fn preserves_values(self) -> bool {
+        true
+    }
+}
+
+impl ValuePreservingAction for ReverseOrder {}
+
+impl<V> Acts<V> for ReverseOrder {
+    fn act_inplace(&self, _val: &mut V) {}
+}
+
+/// [`Data`] instance for plain sequences of values, with segment size information, so that they can be
+/// accessed by index, and with reversal actions. This is the [`Data`] instance of [`crate::vec::GroveVec`].
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct SequenceData<V> {
+    phantom: PhantomData<V>,
+}
+
+impl<V> Data for SequenceData<V> {
+    type Action = ReverseOrder;
+    type Summary = Size;
+    type Value = V;
+
+    fn to_summary(_val: &Self::Value) -> Self::Summary {
+        Size { size: 1 }
+    }
+
+    fn segment_len(summary: Self::Summary) -> Option<usize> {
+        Some(summary.size)
+    }
+}
+
+impl<V> SizedData for SequenceData<V> {}
+
 type I = i32;
 /// A standard numerical summary
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NumSummary {
     /// The maximum of all values in the segment. [`None`] is the segment is empty.
     pub max: Option<I>,
@@ -236,6 +307,7 @@ impl Acts<NumSummary> for RevAddAction {
 
 /// Actions of reversals, adding a constant, and multiplying by a constant.
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RevAffineAction {
     /// Whether to reverse the segment.
     pub to_reverse: bool,
@@ -316,8 +388,14 @@ impl Data for StdNum {
             sum: *val,
         }
     }
+
+    fn segment_len(summary: Self::Summary) -> Option<usize> {
+        Some(summary.size())
+    }
 }
 
+impl SizedData for StdNum {}
+
 // TODO: consider retiring this and just requiring Value: Ord instead.
 
 /// A trait for values that are keyed.
@@ -335,6 +413,7 @@ pub trait Keyed {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A wrapper that wraps around values that should be stored in sorted order.
 /// Implements `Keyed` with the key being the value itself.
 pub struct Ordered<T>(pub T);
@@ -345,3 +424,12 @@ impl<T: Ord> Keyed for Ordered<T> {
         &self.0
     }
 }
+
+/// Key-value pairs are ordered by their first element. This is how [`crate::map::GroveMap`]
+/// stores its entries.
+impl<K: Ord, V> Keyed for (K, V) {
+    type Key = K;
+    fn get_key(&self) -> &Self::Key {
+        &self.0
+    }
+}
@@ -5,7 +5,8 @@
 //! In order for a choice of types for `Value`, `Summary` and `Action`, to work
 //! in a segment tree, they must be an instance of the [`Data`] trait.
 //!
-//! In addition, this module provides the [`SizedSummary`] and [`Keyed`] traits,
+//! In addition, this module provides the [`SizedData`], [`ValuePreservingAction`], [`SizedSummary`]
+//! and [`Keyed`] traits,
 //! and some common possible instantiations in the [`example_data`] module.
 
 pub mod example_data;
@@ -120,8 +121,50 @@ pub trait Data {
 
     /// Creates the summary of a single value.
     fn to_summary(val: &Self::Value) -> Self::Summary;
+
+    /// This function should be implemented if the summaries keep track of the number of values
+    /// in the segment, e.g., if they implement [`SizedSummary`].
+    /// Then, iterators over segments of the tree report exact size hints.
+    /// The default implementation always returns [`None`].
+    ///
+    /// This function should return the number of values in a segment with the given summary.
+    /// It should either always return [`Some`], or always return [`None`].
+    fn segment_len(_summary: Self::Summary) -> Option<usize> {
+        None
+    }
 }
 
+/// Marks [`Data`] instances whose [`Data::segment_len`] always returns [`Some`].
+/// Iterators over segments of trees with such data know their exact length up front,
+/// and implement [`ExactSizeIterator`].
+///
+/// This isn't implied by [`SizedSummary`], since the size of a summary doesn't have to be
+/// the number of values, e.g., when every value is an interval of positions.
+/// If the size does count the values, opt in with:
+///```
+/// use grove::data::{Data, SizedData, SizedSummary};
+/// # use grove::example_data::{Size, Unit};
+///
+/// struct Counted {}
+///
+/// impl Data for Counted {
+///     type Value = i32;
+///     type Summary = Size;
+///     type Action = Unit;
+///
+///     fn to_summary(_val: &i32) -> Size {
+///         Size { size: 1 }
+///     }
+///
+///     fn segment_len(summary: Size) -> Option<usize> {
+///         Some(summary.size())
+///     }
+/// }
+///
+/// impl SizedData for Counted {}
+///```
+pub trait SizedData: Data {}
+
 /// Trait representing actions. this entailes having an identity action ([`Default`]), being able to compose actions
 /// ([`Add`]`<Output=Self>`), checking whether an action is the identity action, and checking whether this action
 /// reverses subsegments.
@@ -140,8 +183,25 @@ pub trait Action: Copy + Default + Add<Output = Self> {
     fn to_reverse(self) -> bool {
         false
     }
+
+    /// This function can be implemented if some non-identity actions don't change the values,
+    /// e.g., actions that only reverse segments.
+    /// Then, queries through shared references (see [`crate::QueryableTree`]) can return
+    /// references to values instead of acted-upon copies of them.
+    /// The default implementation returns [`Action::is_identity()`].
+    ///
+    /// This function should return [`true`] only if acting with this action on any value leaves it unchanged.
+    fn preserves_values(self) -> bool {
+        self.is_identity()
+    }
 }
 
+/// Marks actions that never change the values, e.g., actions that only reverse segments.
+/// For such actions, [`Action::preserves_values`] should always return [`true`].
+/// Queries through shared references can then always return references to values,
+/// without requiring the values to be [`Clone`]. See [`crate::QueryableTree::search_ref`].
+pub trait ValuePreservingAction: Action {}
+
 /// Trait representation actions on a type `V`. If `A: Acts<V>` that means that given any `action: A`,
 /// we can apply it to any `val: V`. This trait is used to represent the actions on
 /// values and summaries used by segment trees.
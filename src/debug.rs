@@ -0,0 +1,200 @@
+//! Tools for visualizing the structure of trees, for debugging.
+//!
+//! [`to_dot`] exports a tree in the [Graphviz] DOT format, and [`to_ascii`] renders it
+//! as multi-line ASCII-art, suitable for printing in a terminal.
+//! Both show every node's value, its subtree's summary, its pending action, its
+//! algorithm-specific data (e.g., AVL ranks or treap priorities), and whether it
+//! has a pending reversal.
+//!
+//! Trees are shown as they are stored: pending actions and reversals are shown, not applied.
+//! Therefore, values are shown before applying the pending actions of their ancestors, and
+//! the sons of a node with a pending reversal are shown in their stored order, which is the
+//! reverse of their logical order.
+//!
+//! Any tree that implements [`DebugTree`] can be visualized. This includes all of the trees
+//! that are built on top of [`BasicTree`].
+//!
+//!```
+//! use grove::{*, basic_tree::BasicTree, debug};
+//! use grove::example_data::SizeData;
+//!
+//! let tree: BasicTree<SizeData<char>> = "abc".chars().collect();
+//! assert_eq!(debug::to_ascii(&tree).lines().collect::<Vec<_>>(), vec![
+//!     "'b', summary: Size { size: 3 }",
+//!     "+-- 'a', summary: Size { size: 1 }",
+//!     "`-- 'c', summary: Size { size: 1 }",
+//! ]);
+//!
+//! assert!(debug::to_dot(&tree).starts_with("digraph {"));
+//!```
+//!
+//! [Graphviz]: https://graphviz.org/
+
+use crate::basic_tree::{BasicNode, BasicTree};
+use crate::*;
+use std::fmt::{Debug, Write};
+
+/// Trees whose structure can be visualized.
+pub trait DebugTree<D: Data> {
+    /// The algorithm-specific data stored in the nodes.
+    type AlgData;
+
+    /// Returns the underlying [`BasicTree`].
+    fn basic_tree(&self) -> &BasicTree<D, Self::AlgData>;
+
+    /// Describes the algorithm-specific data stored in a node, e.g., `"rank 3"`.
+    /// Returns [`None`] if there is nothing to show.
+    fn describe_alg_data(alg_data: &Self::AlgData) -> Option<String>;
+}
+
+impl<D: Data> DebugTree<D> for BasicTree<D> {
+    type AlgData = ();
+
+    fn basic_tree(&self) -> &BasicTree<D> {
+        self
+    }
+
+    fn describe_alg_data(_: &()) -> Option<String> {
+        None
+    }
+}
+
+/// Describes everything that is stored in a node, except for its sons.
+fn describe_node<D: Data, DT: DebugTree<D>>(node: &BasicNode<D, DT::AlgData>) -> Vec<String>
+where
+    D::Value: Debug,
+    D::Summary: Debug,
+    D::Action: Debug,
+{
+    let mut parts = vec![
+        format!("{:?}", node.node_value),
+        format!("summary: {:?}", node.subtree_summary()),
+    ];
+    if let Some(alg_data) = DT::describe_alg_data(node.alg_data()) {
+        parts.push(alg_data);
+    }
+    let action = *node.action();
+    if !action.is_identity() {
+        parts.push(format!("action: {:?}", action));
+    }
+    if action.to_reverse() {
+        parts.push("pending reversal".to_string());
+    }
+    parts
+}
+
+/// Escapes a string, so that it can be used inside a quoted DOT string.
+fn escape_dot(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Exports the tree in the Graphviz DOT format.
+/// Every node is labeled with its value, its subtree's summary, its pending action, and its
+/// algorithm-specific data. Nodes with a pending reversal are drawn with dashed lines,
+/// and empty subtrees are drawn as points.
+/// See the [module documentation](self).
+pub fn to_dot<D: Data, DT: DebugTree<D>>(tree: &DT) -> String
+where
+    D::Value: Debug,
+    D::Summary: Debug,
+    D::Action: Debug,
+{
+    let mut res = String::from("digraph {\n    node [shape=box];\n");
+    let mut count = 0;
+    dot_subtree::<D, DT>(tree.basic_tree(), &mut count, &mut res);
+    res.push_str("}\n");
+    res
+}
+
+/// Writes the DOT statements of the given subtree, and returns the name of its root.
+fn dot_subtree<D: Data, DT: DebugTree<D>>(
+    tree: &BasicTree<D, DT::AlgData>,
+    count: &mut usize,
+    res: &mut String,
+) -> String
+where
+    D::Value: Debug,
+    D::Summary: Debug,
+    D::Action: Debug,
+{
+    let name = format!("n{}", count);
+    *count += 1;
+    match tree.node() {
+        None => writeln!(res, "    {} [shape=point];", name).unwrap(),
+        Some(node) => {
+            let label = describe_node::<D, DT>(node)
+                .iter()
+                .map(|part| escape_dot(part))
+                .collect::<Vec<_>>()
+                .join("\\n");
+            let style = if node.action().to_reverse() {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            writeln!(res, "    {} [label=\"{}\"{}];", name, label, style).unwrap();
+            let left = dot_subtree::<D, DT>(&node.left, count, res);
+            let right = dot_subtree::<D, DT>(&node.right, count, res);
+            writeln!(res, "    {} -> {} [label=\"L\"];", name, left).unwrap();
+            writeln!(res, "    {} -> {} [label=\"R\"];", name, right).unwrap();
+        }
+    }
+    name
+}
+
+/// Renders the tree as multi-line ASCII-art.
+/// Every node is printed in its own line, with its value, its subtree's summary, its pending
+/// action, and its algorithm-specific data, followed by its left son and then its right son.
+/// Empty subtrees are printed as `*`, unless both sons of a node are empty.
+/// See the [module documentation](self).
+pub fn to_ascii<D: Data, DT: DebugTree<D>>(tree: &DT) -> String
+where
+    D::Value: Debug,
+    D::Summary: Debug,
+    D::Action: Debug,
+{
+    let mut res = String::new();
+    ascii_subtree::<D, DT>(tree.basic_tree(), "", "", &mut res);
+    res
+}
+
+/// Writes the lines of the given subtree. `first_prefix` is printed before the subtree's root,
+/// and `prefix` is printed before all other lines.
+fn ascii_subtree<D: Data, DT: DebugTree<D>>(
+    tree: &BasicTree<D, DT::AlgData>,
+    first_prefix: &str,
+    prefix: &str,
+    res: &mut String,
+) where
+    D::Value: Debug,
+    D::Summary: Debug,
+    D::Action: Debug,
+{
+    match tree.node() {
+        None => writeln!(res, "{}*", first_prefix).unwrap(),
+        Some(node) => {
+            writeln!(
+                res,
+                "{}{}",
+                first_prefix,
+                describe_node::<D, DT>(node).join(", ")
+            )
+            .unwrap();
+            if node.left.is_empty() && node.right.is_empty() {
+                return;
+            }
+            ascii_subtree::<D, DT>(
+                &node.left,
+                &format!("{}+-- ", prefix),
+                &format!("{}|   ", prefix),
+                res,
+            );
+            ascii_subtree::<D, DT>(
+                &node.right,
+                &format!("{}`-- ", prefix),
+                &format!("{}    ", prefix),
+                res,
+            );
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Checking that [`Data`] instances satisfy the rules listed in the [`Data`] documentation.
+//!
+//! A [`Data`] instance that breaks one of the rules usually doesn't fail right away:
+//! instead, queries on the tree return wrong answers, possibly only after many operations,
+//! and only for some tree shapes. This module checks the rules directly, given samples of
+//! values, summaries and actions, and reports the first counterexample it finds.
+//!
+//! [`check_random`] checks the rules on random samples, drawn from the given generators.
+//! [`check_exhaustive`] checks the rules on every combination of the given samples.
+//!
+//! The summaries should be summaries that can actually occur in a tree, e.g., sums of
+//! summaries of values. Actions are compared by their results on the sample values and
+//! summaries, so actions don't need to implement [`PartialEq`].
+//!
+//! This module is only available with the `testing` feature.
+//!
+//!```
+//! use grove::{*, laws};
+//! use grove::example_data::{StdNum, RevAffineAction};
+//! use rand::Rng;
+//!
+//! let value = || rand::thread_rng().gen_range(-100..100);
+//! let summary = || {
+//!     let len = rand::thread_rng().gen_range(0..5);
+//!     (0..len).map(|_| StdNum::to_summary(&value())).fold(Default::default(), |a, b| a + b)
+//! };
+//! let action = || {
+//!     let mut rng = rand::thread_rng();
+//!     RevAffineAction {
+//!         to_reverse: rng.gen(),
+//!         mul: rng.gen_range(-2..=2),
+//!         add: rng.gen_range(-100..100),
+//!     }
+//! };
+//! laws::check_random::<StdNum, _, _, _>(value, summary, action, 1000).unwrap();
+//!```
+//!
+//! When a rule is broken, the error describes the rule, the samples and both sides of the
+//! equation that doesn't hold:
+//!```
+//! use grove::{*, laws};
+//!
+//! #[derive(Clone, Copy, Default, Debug)]
+//! struct AddAction(i64);
+//!
+//! impl std::ops::Add for AddAction {
+//!     type Output = Self;
+//!     fn add(self, other: Self) -> Self {
+//!         AddAction(self.0 + other.0)
+//!     }
+//! }
+//!
+//! impl Action for AddAction {
+//!     fn is_identity(self) -> bool {
+//!         self.0 == 0
+//!     }
+//! }
+//!
+//! // wrong for summaries: the sum of `n` values should grow by `n * self.0`.
+//! impl Acts<i64> for AddAction {
+//!     fn act_inplace(&self, object: &mut i64) {
+//!         *object += self.0;
+//!     }
+//! }
+//!
+//! struct BrokenSum {}
+//!
+//! impl Data for BrokenSum {
+//!     type Value = i64;
+//!     type Summary = i64;
+//!     type Action = AddAction;
+//!     fn to_summary(val: &i64) -> i64 {
+//!         *val
+//!     }
+//! }
+//!
+//! let violation = laws::check_exhaustive::<BrokenSum>(&[1], &[1, 2], &[AddAction(5)]).unwrap_err();
+//! assert_eq!(violation.law, "actions distribute over summary addition");
+//! assert_eq!(violation.inputs, "action1 = AddAction(5), summary1 = 1, summary2 = 1");
+//! assert_eq!(violation.left, "action1.act(summary1 + summary2) = 7");
+//!```
+
+use crate::*;
+use std::fmt::Debug;
+
+/// A violation of one of the rules of [`Data`], with a counterexample.
+pub struct LawViolation {
+    /// The rule that was violated.
+    pub law: &'static str,
+    /// The samples that violate the rule.
+    pub inputs: String,
+    /// The left side of the equation that doesn't hold, and its value.
+    pub left: String,
+    /// The right side of the equation that doesn't hold, and its value.
+    pub right: String,
+}
+
+impl std::fmt::Display for LawViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "law violated: {}", self.law)?;
+        writeln!(f, "counterexample: {}", self.inputs)?;
+        writeln!(f, "  left: {}", self.left)?;
+        write!(f, " right: {}", self.right)
+    }
+}
+
+// Implemented the same as `Display`, so that unwrapping the results gives readable messages.
+impl Debug for LawViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for LawViolation {}
+
+type LawResult = Result<(), LawViolation>;
+
+/// Describes the given samples, by name.
+macro_rules! inputs {
+    ($($name:ident),*) => {
+        || {
+            let inputs: Vec<String> = vec![$(format!("{} = {:?}", stringify!($name), $name)),*];
+            inputs.join(", ")
+        }
+    };
+}
+
+/// Checks that `left == right`. Otherwise, returns a violation of `law`.
+fn check_eq<T: PartialEq + Debug, F: FnOnce() -> String>(
+    law: &'static str,
+    inputs: F,
+    (left_expr, left): (&str, T),
+    (right_expr, right): (&str, T),
+) -> LawResult {
+    if left == right {
+        Ok(())
+    } else {
+        Err(LawViolation {
+            law,
+            inputs: inputs(),
+            left: format!("{} = {:?}", left_expr, left),
+            right: format!("{} = {:?}", right_expr, right),
+        })
+    }
+}
+
+/// The rules that involve actions and values.
+fn check_value_laws<D: Data>(
+    value: &D::Value,
+    action1: D::Action,
+    action2: D::Action,
+    action3: D::Action,
+) -> LawResult
+where
+    D::Value: Clone + PartialEq + Debug,
+    D::Summary: PartialEq + Debug,
+    D::Action: Debug,
+{
+    let act = |action: D::Action| action.act(value.clone());
+    check_eq(
+        "action composition is associative",
+        inputs!(action1, action2, action3, value),
+        (
+            "((action3 + action2) + action1).act(value)",
+            act((action3 + action2) + action1),
+        ),
+        (
+            "(action3 + (action2 + action1)).act(value)",
+            act(action3 + (action2 + action1)),
+        ),
+    )?;
+    check_eq(
+        "composing actions acts as applying them one after the other",
+        inputs!(action1, action2, value),
+        ("(action2 + action1).act(value)", act(action2 + action1)),
+        (
+            "action2.act(action1.act(value))",
+            action2.act(action1.act(value.clone())),
+        ),
+    )?;
+    let identity = D::Action::default();
+    check_eq(
+        "the default action is the identity of action composition",
+        inputs!(action1, value),
+        ("(default() + action1).act(value)", act(identity + action1)),
+        ("action1.act(value)", act(action1)),
+    )?;
+    check_eq(
+        "the default action is the identity of action composition",
+        inputs!(action1, value),
+        ("(action1 + default()).act(value)", act(action1 + identity)),
+        ("action1.act(value)", act(action1)),
+    )?;
+    check_eq(
+        "the default action doesn't change values",
+        inputs!(value),
+        ("default().act(value)", act(identity)),
+        ("value", value.clone()),
+    )?;
+    if action1.is_identity() {
+        check_eq(
+            "identity actions don't change values",
+            inputs!(action1, value),
+            ("action1.act(value)", act(action1)),
+            ("value", value.clone()),
+        )?;
+    }
+    if action1.preserves_values() {
+        check_eq(
+            "actions that preserve values don't change values",
+            inputs!(action1, value),
+            ("action1.act(value)", act(action1)),
+            ("value", value.clone()),
+        )?;
+    }
+    check_eq(
+        "actions respect to_summary",
+        inputs!(action1, value),
+        (
+            "D::to_summary(&action1.act(value))",
+            D::to_summary(&act(action1)),
+        ),
+        (
+            "action1.act(D::to_summary(&value))",
+            action1.act(D::to_summary(value)),
+        ),
+    )?;
+    if let Some(len) = D::segment_len(D::to_summary(value)) {
+        check_eq(
+            "segment_len counts values",
+            inputs!(value),
+            ("D::segment_len(D::to_summary(&value))", len),
+            ("1", 1),
+        )?;
+    }
+    Ok(())
+}
+
+/// The rules that involve actions and summaries.
+fn check_summary_laws<D: Data>(
+    summary1: D::Summary,
+    summary2: D::Summary,
+    summary3: D::Summary,
+    action1: D::Action,
+    action2: D::Action,
+    action3: D::Action,
+) -> LawResult
+where
+    D::Summary: PartialEq + Debug,
+    D::Action: Debug,
+{
+    check_eq(
+        "summary addition is associative",
+        inputs!(summary1, summary2, summary3),
+        (
+            "(summary1 + summary2) + summary3",
+            (summary1 + summary2) + summary3,
+        ),
+        (
+            "summary1 + (summary2 + summary3)",
+            summary1 + (summary2 + summary3),
+        ),
+    )?;
+    let empty = D::Summary::default();
+    check_eq(
+        "the default summary is the identity of summary addition",
+        inputs!(summary1),
+        ("default() + summary1", empty + summary1),
+        ("summary1", summary1),
+    )?;
+    check_eq(
+        "the default summary is the identity of summary addition",
+        inputs!(summary1),
+        ("summary1 + default()", summary1 + empty),
+        ("summary1", summary1),
+    )?;
+
+    check_eq(
+        "action composition is associative",
+        inputs!(action1, action2, action3, summary1),
+        (
+            "((action3 + action2) + action1).act(summary1)",
+            ((action3 + action2) + action1).act(summary1),
+        ),
+        (
+            "(action3 + (action2 + action1)).act(summary1)",
+            (action3 + (action2 + action1)).act(summary1),
+        ),
+    )?;
+    check_eq(
+        "composing actions acts as applying them one after the other",
+        inputs!(action1, action2, summary1),
+        (
+            "(action2 + action1).act(summary1)",
+            (action2 + action1).act(summary1),
+        ),
+        (
+            "action2.act(action1.act(summary1))",
+            action2.act(action1.act(summary1)),
+        ),
+    )?;
+    let identity = D::Action::default();
+    check_eq(
+        "the default action is the identity of action composition",
+        inputs!(action1, summary1),
+        (
+            "(default() + action1).act(summary1)",
+            (identity + action1).act(summary1),
+        ),
+        ("action1.act(summary1)", action1.act(summary1)),
+    )?;
+    check_eq(
+        "the default action is the identity of action composition",
+        inputs!(action1, summary1),
+        (
+            "(action1 + default()).act(summary1)",
+            (action1 + identity).act(summary1),
+        ),
+        ("action1.act(summary1)", action1.act(summary1)),
+    )?;
+    check_eq(
+        "the default action doesn't change summaries",
+        inputs!(summary1),
+        ("default().act(summary1)", identity.act(summary1)),
+        ("summary1", summary1),
+    )?;
+    if action1.is_identity() {
+        check_eq(
+            "identity actions don't change summaries",
+            inputs!(action1, summary1),
+            ("action1.act(summary1)", action1.act(summary1)),
+            ("summary1", summary1),
+        )?;
+    }
+
+    if action1.to_reverse() {
+        check_eq(
+            "reversing actions cross-distribute over summary addition",
+            inputs!(action1, summary1, summary2),
+            (
+                "action1.act(summary1 + summary2)",
+                action1.act(summary1 + summary2),
+            ),
+            (
+                "action1.act(summary2) + action1.act(summary1)",
+                action1.act(summary2) + action1.act(summary1),
+            ),
+        )?;
+    } else {
+        check_eq(
+            "actions distribute over summary addition",
+            inputs!(action1, summary1, summary2),
+            (
+                "action1.act(summary1 + summary2)",
+                action1.act(summary1 + summary2),
+            ),
+            (
+                "action1.act(summary1) + action1.act(summary2)",
+                action1.act(summary1) + action1.act(summary2),
+            ),
+        )?;
+    }
+
+    check_eq(
+        "segment_len either always or never returns Some",
+        inputs!(summary1),
+        (
+            "D::segment_len(summary1).is_some()",
+            D::segment_len(summary1).is_some(),
+        ),
+        (
+            "D::segment_len(default()).is_some()",
+            D::segment_len(empty).is_some(),
+        ),
+    )?;
+    if let Some(len) = D::segment_len(empty) {
+        check_eq(
+            "segment_len counts values",
+            inputs!(),
+            ("D::segment_len(default())", Some(len)),
+            ("Some(0)", Some(0)),
+        )?;
+    }
+    if let (Some(len1), Some(len2)) = (D::segment_len(summary1), D::segment_len(summary2)) {
+        check_eq(
+            "segment_len counts values",
+            inputs!(summary1, summary2),
+            (
+                "D::segment_len(summary1 + summary2)",
+                D::segment_len(summary1 + summary2),
+            ),
+            (
+                "D::segment_len(summary1) + D::segment_len(summary2)",
+                Some(len1 + len2),
+            ),
+        )?;
+        check_eq(
+            "actions don't change the number of values",
+            inputs!(action1, summary1),
+            (
+                "D::segment_len(action1.act(summary1))",
+                D::segment_len(action1.act(summary1)),
+            ),
+            ("D::segment_len(summary1)", Some(len1)),
+        )?;
+    }
+    Ok(())
+}
+
+/// The rules that involve only actions.
+fn check_action_laws<D: Data>(action1: D::Action, action2: D::Action) -> LawResult
+where
+    D::Action: Debug,
+{
+    check_eq(
+        "the default action is an identity action",
+        inputs!(),
+        (
+            "default().is_identity()",
+            D::Action::default().is_identity(),
+        ),
+        ("true", true),
+    )?;
+    check_eq(
+        "composing actions xors their reversals",
+        inputs!(action1, action2),
+        (
+            "(action2 + action1).to_reverse()",
+            (action2 + action1).to_reverse(),
+        ),
+        (
+            "action2.to_reverse() ^ action1.to_reverse()",
+            action2.to_reverse() ^ action1.to_reverse(),
+        ),
+    )?;
+    if action1.is_identity() {
+        check_eq(
+            "identity actions don't reverse",
+            inputs!(action1),
+            ("action1.to_reverse()", action1.to_reverse()),
+            ("false", false),
+        )?;
+    }
+    Ok(())
+}
+
+/// Checks the rules of [`Data`] on `rounds` random samples, drawn from the given generators.
+/// Returns the first violation found. See the [module documentation](self).
+pub fn check_random<D: Data, GV, GS, GA>(
+    mut values: GV,
+    mut summaries: GS,
+    mut actions: GA,
+    rounds: usize,
+) -> Result<(), LawViolation>
+where
+    D::Value: Clone + PartialEq + Debug,
+    D::Summary: PartialEq + Debug,
+    D::Action: Debug,
+    GV: FnMut() -> D::Value,
+    GS: FnMut() -> D::Summary,
+    GA: FnMut() -> D::Action,
+{
+    for _ in 0..rounds {
+        let (action1, action2, action3) = (actions(), actions(), actions());
+        check_action_laws::<D>(action1, action2)?;
+        check_value_laws::<D>(&values(), action1, action2, action3)?;
+        let (summary1, summary2, summary3) = (summaries(), summaries(), summaries());
+        check_summary_laws::<D>(summary1, summary2, summary3, action1, action2, action3)?;
+    }
+    Ok(())
+}
+
+/// Checks the rules of [`Data`] on every combination of the given samples.
+/// The identity action is always checked as well.
+/// Returns the first violation found. See the [module documentation](self).
+///
+/// Rules that involve three actions or three summaries are checked on every triple,
+/// so the samples should be small. However, rules that involve both actions and several
+/// summaries are only checked with a single action at a time.
+pub fn check_exhaustive<D: Data>(
+    values: &[D::Value],
+    summaries: &[D::Summary],
+    actions: &[D::Action],
+) -> Result<(), LawViolation>
+where
+    D::Value: Clone + PartialEq + Debug,
+    D::Summary: PartialEq + Debug,
+    D::Action: Debug,
+{
+    let mut all_actions = vec![D::Action::default()];
+    all_actions.extend_from_slice(actions);
+    let actions = &all_actions;
+
+    for &action1 in actions {
+        for &action2 in actions {
+            check_action_laws::<D>(action1, action2)?;
+            for &action3 in actions {
+                for value in values {
+                    check_value_laws::<D>(value, action1, action2, action3)?;
+                }
+            }
+        }
+    }
+
+    for &summary in summaries {
+        for &action1 in actions {
+            for &action2 in actions {
+                for &action3 in actions {
+                    check_summary_laws::<D>(summary, summary, summary, action1, action2, action3)?;
+                }
+            }
+        }
+    }
+    for &action in actions {
+        for &summary1 in summaries {
+            for &summary2 in summaries {
+                for &summary3 in summaries {
+                    check_summary_laws::<D>(summary1, summary2, summary3, action, action, action)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
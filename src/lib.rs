@@ -27,15 +27,28 @@
 //! * Reverse segments of trees, split and concatenate segment trees
 //! 
 //! In order to use a certain kind of tree, i.e., red-black, AVL, splay tree, treaps,
-//! scapegoat trees, regular unbalanced trees, or any other, the user has to specify
+//! scapegoat trees, weight-balanced trees, regular unbalanced trees, or any other, the user has to specify
 //! a tree type that implements the trait in the [`trees`] module. (currently
-//! splay/AVL/treaps/unbalanced trees are implemented)
+//! splay/AVL/red-black/scapegoat/weight-balanced/treaps/unbalanced trees are implemented, as well as
+//! persistent treaps)
 //! 
 //! Indeed, the library is generic in both the tree type and the [`Data`] instance: you can use any
 //! setting with any tree type.
 //! 
 //! The [`methods`] module provides some general methods for use on all trees.
 //!
+//! The [`map`] module provides [`map::GroveMap`] and [`map::GroveSet`], ordered maps and sets
+//! with an interface similar to the standard library's, implemented on top of any of the trees.
+//! Similarly, the [`vec`] module provides [`vec::GroveVec`], an indexable sequence with
+//! logarithmic time insertions, removals, splits and concatenations, and the [`rope`] module
+//! provides [`rope::Rope`], a text rope that can be indexed by bytes, chars, or lines and columns.
+//!
+//! With the `testing` feature, the `laws` module can check that your [`Data`] instances
+//! satisfy the rules listed in the [`Data`] documentation.
+//!
+//! The [`debug`] module can export the structure of any tree as Graphviz DOT or ASCII-art,
+//! for debugging.
+//!
 //! # Advanced examples
 //! 
 //! In the examples folder in the library (which is automatically stripped from crates.io), there are two
@@ -59,8 +72,14 @@
 extern crate derive_destructure;
 
 pub mod data;
+pub mod debug;
+#[cfg(feature = "testing")]
+pub mod laws;
 pub mod locators;
+pub mod map;
+pub mod rope;
 pub mod trees;
+pub mod vec;
 
 pub use data::*;
 pub use locators::Locator;
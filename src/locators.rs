@@ -7,6 +7,7 @@
 //! will use any node that is accepted.
 //! Functions like insertions, will expect a locator that doesn't accept any node,
 //! but leads the locator into a space between nodes, where the node will be inserted.
+//! Such locators are represented by the [`Splitter`] trait.
 
 use crate::*;
 
@@ -73,9 +74,6 @@ where
     }
 }
 
-// TODO: Splitter. locators that can't `Accept`. used for splitting trees
-// and for insertions.
-
 /// Locator instance for `usize` representing a single index.
 impl<D: Data> Locator<D> for usize
 where
@@ -447,6 +445,32 @@ where
     }
 }
 
+/// Locator instance for [`ByKey`]`<(Bound<&D::Value::Key>, Bound<&D::Value::Key>)>`, representing
+/// an arbitrary range of keys. This is useful for handling any [`std::ops::RangeBounds`] at once.
+impl<'a, D: Data> Locator<D>
+    for ByKey<(
+        std::ops::Bound<&'a <D::Value as Keyed>::Key>,
+        std::ops::Bound<&'a <D::Value as Keyed>::Key>,
+    )>
+where
+    D::Value: Keyed,
+{
+    fn locate(&self, _left: D::Summary, node: &D::Value, _right: D::Summary) -> LocResult {
+        use std::ops::Bound::*;
+        let key = node.get_key();
+        match self.0 .0 {
+            Included(start) if key < start => return GoRight,
+            Excluded(start) if key <= start => return GoRight,
+            _ => (),
+        }
+        match self.0 .1 {
+            Included(end) if end < key => GoLeft,
+            Excluded(end) if end <= key => GoLeft,
+            _ => Accept,
+        }
+    }
+}
+
 // TODO: finish all the range types
 // TODO: switch to ranges of references to keys
 
@@ -477,6 +501,188 @@ impl<D: Data, L: Locator<D>> Locator<D> for RightEdgeOf<L> {
     }
 }
 
+/// Splitters are locators that never `Accept`. Instead of a segment, they represent
+/// a position between two adjacent values (or at one of the ends of the tree),
+/// i.e., an empty position in the tree.
+///
+/// Insertions and splits only make sense at such positions, so functions like
+/// [`SomeTree::insert_at`] and [`SomeTree::split_at`] receive splitters, and can't
+/// land on a filled node by accident.
+///
+/// Every splitter is also a [`Locator`], whose [`Locator::locate`] never returns `Accept`.
+/// Use [`splitter_locate`] to implement it.
+pub trait Splitter<D: Data>: Locator<D> {
+    /// Looks at a specific node's value, and its context (the summaries to the right and left),
+    /// and decides whether the split position is to the left of the node or to its right.
+    fn split_side(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> Side;
+}
+
+/// Implements [`Locator::locate`] for a [`Splitter`], by converting its answer into a [`LocResult`].
+pub fn splitter_locate<D: Data, S: Splitter<D>>(
+    splitter: &S,
+    left: D::Summary,
+    node: &D::Value,
+    right: D::Summary,
+) -> LocResult {
+    match splitter.split_side(left, node, right) {
+        Side::Left => GoLeft,
+        Side::Right => GoRight,
+    }
+}
+
+impl<D: Data, L: Locator<D>> Splitter<D> for LeftEdgeOf<L> {
+    fn split_side(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> Side {
+        match self.0.locate(left, node, right) {
+            GoRight => Side::Right,
+            _ => Side::Left,
+        }
+    }
+}
+
+impl<D: Data, L: Locator<D>> Splitter<D> for RightEdgeOf<L> {
+    fn split_side(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> Side {
+        match self.0.locate(left, node, right) {
+            GoLeft => Side::Left,
+            _ => Side::Right,
+        }
+    }
+}
+
+/// A splitter for the position just before the value at the given index,
+/// i.e., the position that has exactly that many values to its left.
+/// `BeforeIndex(len)` is the position at the end of the tree.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub struct BeforeIndex(pub usize);
+
+impl<D: Data> Splitter<D> for BeforeIndex
+where
+    D::Summary: SizedSummary,
+{
+    fn split_side(&self, left: D::Summary, _node: &D::Value, _right: D::Summary) -> Side {
+        if left.size() < self.0 {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    }
+}
+
+impl<D: Data> Locator<D> for BeforeIndex
+where
+    D::Summary: SizedSummary,
+{
+    fn locate(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> LocResult {
+        splitter_locate::<D, _>(self, left, node, right)
+    }
+}
+
+/// A splitter for the position just after all the values whose keys are smaller than
+/// or equal to the given key, through the [`data::Keyed`] trait.
+/// This is where a new value with this key should be inserted, after any existing
+/// values with an equal key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub struct AfterKey<T>(pub T);
+
+impl<D: Data> Splitter<D> for AfterKey<&<D::Value as Keyed>::Key>
+where
+    D::Value: Keyed,
+{
+    fn split_side(&self, _left: D::Summary, node: &D::Value, _right: D::Summary) -> Side {
+        if node.get_key() <= self.0 {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    }
+}
+
+impl<D: Data> Locator<D> for AfterKey<&<D::Value as Keyed>::Key>
+where
+    D::Value: Keyed,
+{
+    fn locate(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> LocResult {
+        splitter_locate::<D, _>(self, left, node, right)
+    }
+}
+
+/// A splitter for the position just before the first value that satisfies the predicate.
+/// The predicate should be monotone: it should be false for some prefix of the values,
+/// and true for the rest of them.
+/// This is the same as [`slice::partition_point`], with the predicate negated.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FirstWhere<F>(pub F);
+
+impl<D: Data, F> Splitter<D> for FirstWhere<F>
+where
+    F: Fn(&D::Value) -> bool + Clone,
+{
+    fn split_side(&self, _left: D::Summary, node: &D::Value, _right: D::Summary) -> Side {
+        if (self.0)(node) {
+            Side::Left
+        } else {
+            Side::Right
+        }
+    }
+}
+
+impl<D: Data, F> Locator<D> for FirstWhere<F>
+where
+    F: Fn(&D::Value) -> bool + Clone,
+{
+    fn locate(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> LocResult {
+        splitter_locate::<D, _>(self, left, node, right)
+    }
+}
+
+/// A locator for the first value whose prefix satisfies the predicate, i.e., the first value
+/// such that the summary of all values up to and including it satisfies the predicate.
+/// The predicate should be monotone: once a prefix satisfies it, every longer prefix should too.
+///
+/// For example, `PrefixWhere(|s: NumSummary| s.sum > 10)` finds the first value where
+/// the prefix sum exceeds 10, assuming the values are nonnegative.
+/// If no prefix satisfies the predicate, this locates the empty position at the end of the tree.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PrefixWhere<F>(pub F);
+
+impl<D: Data, F> Locator<D> for PrefixWhere<F>
+where
+    F: Fn(D::Summary) -> bool + Clone,
+{
+    fn locate(&self, left: D::Summary, node: &D::Value, _right: D::Summary) -> LocResult {
+        if (self.0)(left) {
+            GoLeft
+        } else if (self.0)(left + D::to_summary(node)) {
+            Accept
+        } else {
+            GoRight
+        }
+    }
+}
+
+/// A locator for the last value whose suffix satisfies the predicate, i.e., the last value
+/// such that the summary of all values from it to the end satisfies the predicate.
+/// The predicate should be monotone: once a suffix satisfies it, every longer suffix should too.
+///
+/// This is the mirror image of [`PrefixWhere`].
+/// If no suffix satisfies the predicate, this locates the empty position at the start of the tree.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SuffixWhere<F>(pub F);
+
+impl<D: Data, F> Locator<D> for SuffixWhere<F>
+where
+    F: Fn(D::Summary) -> bool + Clone,
+{
+    fn locate(&self, _left: D::Summary, node: &D::Value, right: D::Summary) -> LocResult {
+        if (self.0)(right) {
+            GoRight
+        } else if (self.0)(D::to_summary(node) + right) {
+            Accept
+        } else {
+            GoLeft
+        }
+    }
+}
+
 /// A Wrapper for other locators what will find the segment to the left
 /// of the previous locator. So, `LeftOf(5..8)` is equivalent to `0..5`.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -542,3 +748,167 @@ impl<D: Data, L1: Locator<D>, L2: Locator<D>> Locator<D> for BetweenLocator<L1,
         }
     }
 }
+
+/// A Wrapper for two other locators, that finds the intersection of their segments.
+/// For example, the Intersection of ranges `[3,9)` and `[6,12)` will be `[6,9)`.
+/// If the segments don't intersect, this finds an empty segment.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct IntersectionLocator<L1, L2>(pub L1, pub L2);
+
+impl<D: Data, L1: Locator<D>, L2: Locator<D>> Locator<D> for IntersectionLocator<L1, L2> {
+    fn locate(&self, left: D::Summary, node: &D::Value, right: D::Summary) -> LocResult {
+        let a = self.0.locate(left, node, right);
+        let b = self.1.locate(left, node, right);
+        match (a, b) {
+            (Accept, Accept) => Accept,
+            (GoRight, _) | (_, GoRight) => GoRight,
+            _ => GoLeft,
+        }
+    }
+}
+
+/// A Wrapper that adapts a locator written for one [`Data`] instance, `D1`, into a locator for
+/// another instance `D2`, whose summaries and values contain the summaries and values of `D1`.
+/// This is done by projecting the summaries and the values of `D2` into those of `D1`.
+///```
+/// use grove::{SomeTree, treap::Treap};
+/// use grove::example_data::{SizeData, Size};
+/// use grove::locators::{FirstWhere, MapLocator};
+///
+/// let mut tree: Treap<SizeData<(i32, &str)>> = vec![(1, "a"), (4, "b"), (6, "c"), (9, "d")]
+///     .into_iter()
+///     .collect();
+/// // a locator for trees of numbers
+/// let locator = FirstWhere(|x: &i32| *x >= 5);
+/// let mapped = MapLocator::<SizeData<i32>, _, _, _, _>::new(
+///     locator,
+///     |summary: Size| summary,
+///     |value: &(i32, &str)| &value.0,
+/// );
+///
+/// tree.insert_at(mapped, (5, "e"));
+/// assert_eq!(tree.iter_locator(1..4).map(|v| v.1).collect::<String>(), "bec");
+///```
+pub struct MapLocator<D1, D2, L, FS, FV> {
+    locator: L,
+    map_summary: FS,
+    map_value: FV,
+    phantom: std::marker::PhantomData<fn(D2) -> D1>,
+}
+
+impl<D1: Data, D2: Data, L, FS, FV> MapLocator<D1, D2, L, FS, FV> {
+    /// Creates a locator for `D2` out of a locator for `D1`, and functions that project
+    /// the summaries and the values of `D2` into those of `D1`.
+    pub fn new(locator: L, map_summary: FS, map_value: FV) -> Self
+    where
+        L: Locator<D1>,
+        FS: Fn(D2::Summary) -> D1::Summary + Clone,
+        FV: Fn(&D2::Value) -> &D1::Value + Clone,
+    {
+        MapLocator {
+            locator,
+            map_summary,
+            map_value,
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<D1, D2, L: Clone, FS: Clone, FV: Clone> Clone for MapLocator<D1, D2, L, FS, FV> {
+    fn clone(&self) -> Self {
+        MapLocator {
+            locator: self.locator.clone(),
+            map_summary: self.map_summary.clone(),
+            map_value: self.map_value.clone(),
+            phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<D1: Data, D2: Data, L, FS, FV> Locator<D2> for MapLocator<D1, D2, L, FS, FV>
+where
+    L: Locator<D1>,
+    FS: Fn(D2::Summary) -> D1::Summary + Clone,
+    FV: Fn(&D2::Value) -> &D1::Value + Clone,
+{
+    fn locate(&self, left: D2::Summary, node: &D2::Value, right: D2::Summary) -> LocResult {
+        self.locator.locate(
+            (self.map_summary)(left),
+            (self.map_value)(node),
+            (self.map_summary)(right),
+        )
+    }
+}
+
+impl<D1: Data, D2: Data, L, FS, FV> Splitter<D2> for MapLocator<D1, D2, L, FS, FV>
+where
+    L: Splitter<D1>,
+    FS: Fn(D2::Summary) -> D1::Summary + Clone,
+    FV: Fn(&D2::Value) -> &D1::Value + Clone,
+{
+    fn split_side(&self, left: D2::Summary, node: &D2::Value, right: D2::Summary) -> Side {
+        self.locator.split_side(
+            (self.map_summary)(left),
+            (self.map_value)(node),
+            (self.map_summary)(right),
+        )
+    }
+}
+
+/// A trait for building locators fluently out of other locators, e.g.,
+/// `(3..10).and(5..).or(RightOf(20..))`. It is implemented for the locator types in this module.
+/// Other locators, such as closures, can be wrapped in one of these types first.
+///```
+/// use grove::{SomeTree, treap::Treap};
+/// use grove::example_data::StdNum;
+/// use grove::locators::LocatorBuilder;
+///
+/// let mut tree: Treap<StdNum> = (0..20).collect();
+///
+/// assert_eq!(tree.segment_summary((3..10).and(5..)).size, 5);
+/// assert_eq!(tree.segment_summary((3..5).or(8..10)).size, 7);
+/// assert_eq!(tree.segment_summary((3..5).until(12..14)).size, 9);
+///```
+pub trait LocatorBuilder: Clone {
+    /// Finds the intersection of the two segments. See [`IntersectionLocator`].
+    fn and<L>(self, other: L) -> IntersectionLocator<Self, L> {
+        IntersectionLocator(self, other)
+    }
+
+    /// Finds the smallest segment containing both segments. See [`UnionLocator`].
+    fn or<L>(self, other: L) -> UnionLocator<Self, L> {
+        UnionLocator(self, other)
+    }
+
+    /// Finds the segment that starts where this segment starts, and ends where
+    /// the other segment starts.
+    /// For example, `(3..5).until(12..14)` is equivalent to `3..12`.
+    fn until<L>(
+        self,
+        other: L,
+    ) -> IntersectionLocator<UnionLocator<Self, RightOf<Self>>, LeftOf<L>> {
+        IntersectionLocator(UnionLocator(self.clone(), RightOf(self)), LeftOf(other))
+    }
+}
+
+impl LocatorBuilder for usize {}
+impl LocatorBuilder for std::ops::RangeFull {}
+impl LocatorBuilder for std::ops::Range<usize> {}
+impl LocatorBuilder for std::ops::RangeInclusive<usize> {}
+impl LocatorBuilder for std::ops::RangeFrom<usize> {}
+impl LocatorBuilder for std::ops::RangeTo<usize> {}
+impl LocatorBuilder for std::ops::RangeToInclusive<usize> {}
+impl<T: Clone> LocatorBuilder for ByKey<T> {}
+impl<L: Clone> LocatorBuilder for LeftEdgeOf<L> {}
+impl<L: Clone> LocatorBuilder for RightEdgeOf<L> {}
+impl LocatorBuilder for BeforeIndex {}
+impl<T: Clone> LocatorBuilder for AfterKey<T> {}
+impl<F: Clone> LocatorBuilder for FirstWhere<F> {}
+impl<F: Clone> LocatorBuilder for PrefixWhere<F> {}
+impl<F: Clone> LocatorBuilder for SuffixWhere<F> {}
+impl<L: Clone> LocatorBuilder for LeftOf<L> {}
+impl<L: Clone> LocatorBuilder for RightOf<L> {}
+impl<L1: Clone, L2: Clone> LocatorBuilder for UnionLocator<L1, L2> {}
+impl<L1: Clone, L2: Clone> LocatorBuilder for BetweenLocator<L1, L2> {}
+impl<L1: Clone, L2: Clone> LocatorBuilder for IntersectionLocator<L1, L2> {}
+impl<D1, D2, L: Clone, FS: Clone, FV: Clone> LocatorBuilder for MapLocator<D1, D2, L, FS, FV> {}
@@ -0,0 +1,636 @@
+//! Ordered maps and sets, implemented on top of any of the trees in this library.
+//!
+//! [`GroveMap`] and [`GroveSet`] have an interface similar to [`std::collections::BTreeMap`]
+//! and [`std::collections::BTreeSet`]. In addition, since they are backed by segment trees,
+//! they can compute summaries of key ranges, apply actions on key ranges,
+//! and find entries by their rank, all in logarithmic time.
+//!
+//! The entries are stored in the tree as `(K, V)` pairs, ordered by their keys
+//! (see the [`Keyed`] instance for pairs). Therefore, the [`Data`] instance `D` should have
+//! `D::Value = (K, V)`, and its summaries have to keep track of sizes, i.e., `D::Summary: `[`SizedSummary`].
+//! By default, [`SizeData`] is used, and the tree type is a [`Treap`].
+//!
+//! Like the trees themselves, most queries receive `&mut self`, since some trees (e.g, splay trees)
+//! restructure themselves on every access.
+//!
+//! Actions applied using [`GroveMap::act_range`] must not change the keys, and must not reverse
+//! segments. Otherwise, the entries would stop being ordered by their keys.
+//!
+//!```
+//! use grove::map::GroveMap;
+//!
+//! let mut map: GroveMap<&str, i32> = GroveMap::new();
+//! map.insert("b", 2);
+//! map.insert("a", 1);
+//! map.insert("c", 3);
+//!
+//! assert_eq!(map.get(&"b"), Some(&2));
+//! assert_eq!(map.range("b"..).collect::<Vec<_>>(), vec![(&"b", &2), (&"c", &3)]);
+//! assert_eq!(map.rank(&"c"), 2);
+//! assert_eq!(map.select(0), Some((&"a", &1)));
+//! assert_eq!(map.pop_first(), Some(("a", 1)));
+//! assert_eq!(map.len(), 2);
+//!```
+
+use crate::example_data::SizeData;
+use crate::locators::ByKey;
+use crate::treap::Treap;
+use crate::*;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+
+/// A locator for all the keys in the given range.
+fn key_bounds<K, R: RangeBounds<K>>(range: &R) -> ByKey<(Bound<&K>, Bound<&K>)> {
+    ByKey((range.start_bound(), range.end_bound()))
+}
+
+/// An ordered map, implemented using a tree of type `T`, which stores `(K, V)` pairs
+/// with the [`Data`] instance `D`. See the [module documentation](self).
+pub struct GroveMap<K, V, D: Data = SizeData<(K, V)>, T = Treap<D>> {
+    tree: T,
+    phantom: PhantomData<(K, V, D)>,
+}
+
+impl<K, V, D, T> GroveMap<K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        GroveMap {
+            tree: T::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns [`true`] if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entries from the map.
+    pub fn clear(&mut self) {
+        self.tree = T::default();
+    }
+
+    /// Returns a reference to the underlying tree.
+    pub fn as_tree(&self) -> &T {
+        &self.tree
+    }
+
+    /// Returns the underlying tree.
+    pub fn into_tree(self) -> T {
+        self.tree
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Returns the key-value pair corresponding to the key.
+    pub fn get_key_value(&mut self, key: &K) -> Option<(&K, &V)> {
+        let mut iter = self.tree.iter_locator(ByKey((key,)));
+        iter.next().map(|(key, value)| (key, value))
+    }
+
+    /// Returns [`true`] if the map contains the key.
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        !self.tree.search(ByKey((key,))).is_empty()
+    }
+
+    /// Inserts a key-value pair into the map.
+    /// If the key was already present, its value is replaced, and the old value is returned.
+    /// In that case, the key itself isn't updated.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut walker = self.tree.search(ByKey((&key,)));
+        if walker.is_empty() {
+            walker.insert((key, value));
+            None
+        } else {
+            walker.with_value(|(_, old_value)| std::mem::replace(old_value, value))
+        }
+    }
+
+    /// Removes a key from the map, and returns its value, if the key was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key from the map, and returns the stored key-value pair, if the key was present.
+    pub fn remove_entry(&mut self, key: &K) -> Option<(K, V)> {
+        self.tree.search(ByKey((key,))).delete()
+    }
+
+    /// Gets the entry of the given key, for in-place manipulation.
+    ///```
+    /// use grove::map::GroveMap;
+    ///
+    /// let mut counts: GroveMap<char, usize> = GroveMap::new();
+    /// for c in "abracadabra".chars() {
+    ///     counts.entry(c).and_modify(|count| *count += 1).or_insert(1);
+    /// }
+    ///
+    /// assert_eq!(counts.get(&'a'), Some(&5));
+    /// assert_eq!(counts.get(&'c'), Some(&1));
+    /// assert_eq!(counts.len(), 5);
+    ///```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, D, T> {
+        if self.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry { map: self, key })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, key })
+        }
+    }
+
+    /// Returns the entry with the smallest key.
+    pub fn first_key_value(&mut self) -> Option<(&K, &V)> {
+        self.select(0)
+    }
+
+    /// Returns the entry with the largest key.
+    pub fn last_key_value(&mut self) -> Option<(&K, &V)> {
+        let index = self.len().checked_sub(1)?;
+        self.select(index)
+    }
+
+    /// Removes the entry with the smallest key, and returns it.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        self.tree.search(0).delete()
+    }
+
+    /// Removes the entry with the largest key, and returns it.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let index = self.len().checked_sub(1)?;
+        self.tree.search(index).delete()
+    }
+
+    /// Iterates over the entries of the map, in order.
+    pub fn iter(&mut self) -> impl Iterator<Item = (&K, &V)> {
+        self.tree.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Iterates over the keys of the map, in order.
+    pub fn keys(&mut self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Iterates over the values of the map, in order of their keys.
+    pub fn values(&mut self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Iterates over the entries whose keys are in the given range, in order.
+    pub fn range<R: RangeBounds<K>>(&mut self, range: R) -> impl Iterator<Item = (&K, &V)> {
+        // The iterator can't keep a locator that borrows the range's keys,
+        // so the range is converted to a range of indices first.
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.rank(key),
+            Bound::Excluded(key) => self.range_summary(..=key).size(),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Unbounded => self.len(),
+            bound => self.range_summary((Bound::Unbounded, bound)).size(),
+        };
+        let end = std::cmp::max(start, end);
+        self.tree
+            .iter_locator(start..end)
+            .map(|(key, value)| (key, value))
+    }
+
+    /// Computes the summary of all entries whose keys are in the given range.
+    pub fn range_summary<R: RangeBounds<K>>(&mut self, range: R) -> D::Summary {
+        self.tree.segment_summary(key_bounds(&range))
+    }
+
+    /// Applies an action on all entries whose keys are in the given range.
+    /// The action must not change the keys or reverse the segment.
+    pub fn act_range<R: RangeBounds<K>>(&mut self, range: R, action: D::Action) {
+        self.tree.act_segment(action, key_bounds(&range))
+    }
+
+    /// Returns the number of keys in the map that are smaller than the given key.
+    /// If the key is in the map, this is its index.
+    pub fn rank(&mut self, key: &K) -> usize {
+        self.tree.rank_of_key(key)
+    }
+
+    /// Returns the entry with the given index, i.e., the entry with exactly
+    /// `index` smaller keys in the map.
+    pub fn select(&mut self, index: usize) -> Option<(&K, &V)> {
+        self.tree.select(index).map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V, D, T> Default for GroveMap<K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, D, T> Extend<(K, V)> for GroveMap<K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, D, T> std::iter::FromIterator<(K, V)> for GroveMap<K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    /// Like in [`std::collections::BTreeMap`], if a key appears more than once,
+    /// the last value is kept.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, D, T> IntoIterator for GroveMap<K, V, D, T>
+where
+    D: Data<Value = (K, V)>,
+    T: IntoIterator<Item = (K, V)>,
+{
+    type Item = (K, V);
+    type IntoIter = T::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tree.into_iter()
+    }
+}
+
+/// An entry of a [`GroveMap`], which is either vacant or occupied. See [`GroveMap::entry`].
+///
+/// Since changing the values requires updating the summaries in the tree, the entries don't give out
+/// mutable references to the values. Instead, use [`Entry::and_modify`] or [`OccupiedEntry::with_value`].
+pub enum Entry<'a, K, V, D: Data, T> {
+    /// The key is in the map.
+    Occupied(OccupiedEntry<'a, K, V, D, T>),
+    /// The key isn't in the map.
+    Vacant(VacantEntry<'a, K, V, D, T>),
+}
+
+/// An entry of a key that is in a [`GroveMap`]. See [`GroveMap::entry`].
+pub struct OccupiedEntry<'a, K, V, D: Data, T> {
+    map: &'a mut GroveMap<K, V, D, T>,
+    key: K,
+}
+
+/// An entry of a key that isn't in a [`GroveMap`]. See [`GroveMap::entry`].
+pub struct VacantEntry<'a, K, V, D: Data, T> {
+    map: &'a mut GroveMap<K, V, D, T>,
+    key: K,
+}
+
+impl<'a, K, V, D, T> Entry<'a, K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'b> &'b mut T: ModifiableTreeRef<D>,
+{
+    /// Returns the entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts the default value if the entry is vacant.
+    /// Returns a reference to the value in the entry.
+    pub fn or_insert(self, default: V) -> &'a V {
+        self.or_insert_with(|| default)
+    }
+
+    /// Inserts the result of the function if the entry is vacant.
+    /// Returns a reference to the value in the entry.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a V {
+        match self {
+            Entry::Occupied(entry) => entry.into_ref(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Inserts [`Default::default()`] if the entry is vacant.
+    /// Returns a reference to the value in the entry.
+    pub fn or_default(self) -> &'a V
+    where
+        V: Default,
+    {
+        self.or_insert_with(Default::default)
+    }
+
+    /// Modifies the value if the entry is occupied.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.with_value(f);
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K, V, D, T> OccupiedEntry<'a, K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'b> &'b mut T: ModifiableTreeRef<D>,
+{
+    /// Returns the entry's key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a reference to the value in the entry.
+    pub fn get(&mut self) -> &V {
+        self.map.get(&self.key).unwrap()
+    }
+
+    /// Converts the entry into a reference to its value.
+    pub fn into_ref(self) -> &'a V {
+        let OccupiedEntry { map, key } = self;
+        map.get(&key).unwrap()
+    }
+
+    /// Lets you modify the value, and after you modified it, updates the summaries.
+    pub fn with_value<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut V) -> R,
+    {
+        let mut walker = self.map.tree.search(ByKey((&self.key,)));
+        walker.with_value(|(_, value)| f(value)).unwrap()
+    }
+
+    /// Replaces the value in the entry, and returns the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        self.with_value(|old_value| std::mem::replace(old_value, value))
+    }
+
+    /// Removes the entry from the map, and returns its value.
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    /// Removes the entry from the map, and returns the stored key-value pair.
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.remove_entry(&self.key).unwrap()
+    }
+}
+
+impl<'a, K, V, D, T> VacantEntry<'a, K, V, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, V)>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'b> &'b mut T: ModifiableTreeRef<D>,
+{
+    /// Returns the entry's key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the value with the entry's key, and returns a reference to it.
+    pub fn insert(self, value: V) -> &'a V {
+        let VacantEntry { map, key } = self;
+        let index = map.rank(&key);
+        map.tree.search(index..index).insert((key, value));
+        map.select(index).unwrap().1
+    }
+}
+
+/// An ordered set, implemented using a [`GroveMap`] with `()` values.
+/// See the [module documentation](self).
+///```
+/// use grove::map::GroveSet;
+///
+/// let mut set: GroveSet<i32> = (0..10).map(|x| x * 3).collect();
+///
+/// assert!(set.contains(&9));
+/// assert!(!set.contains(&10));
+/// assert_eq!(set.range(4..=12).cloned().collect::<Vec<_>>(), vec![6, 9, 12]);
+/// assert_eq!(set.rank(&10), 4);
+/// assert_eq!(set.select(4), Some(&12));
+/// assert_eq!(set.range_summary(..10).size, 4);
+///```
+pub struct GroveSet<K, D: Data = SizeData<(K, ())>, T = Treap<D>> {
+    map: GroveMap<K, (), D, T>,
+}
+
+impl<K, D, T> GroveSet<K, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, ())>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        GroveSet {
+            map: GroveMap::new(),
+        }
+    }
+
+    /// Returns the number of keys in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns [`true`] if the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes all keys from the set.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns a reference to the underlying tree.
+    pub fn as_tree(&self) -> &T {
+        self.map.as_tree()
+    }
+
+    /// Returns the underlying tree.
+    pub fn into_tree(self) -> T {
+        self.map.into_tree()
+    }
+
+    /// Returns [`true`] if the set contains the key.
+    pub fn contains(&mut self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Returns a reference to the key in the set that is equal to the given key.
+    pub fn get(&mut self, key: &K) -> Option<&K> {
+        self.map.get_key_value(key).map(|(key, _)| key)
+    }
+
+    /// Adds a key to the set. Returns whether the key was newly inserted.
+    pub fn insert(&mut self, key: K) -> bool {
+        self.map.insert(key, ()).is_none()
+    }
+
+    /// Removes a key from the set. Returns whether the key was present.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.map.remove(key).is_some()
+    }
+
+    /// Removes a key from the set, and returns the stored key, if it was present.
+    pub fn take(&mut self, key: &K) -> Option<K> {
+        self.map.remove_entry(key).map(|(key, _)| key)
+    }
+
+    /// Returns the smallest key in the set.
+    pub fn first(&mut self) -> Option<&K> {
+        self.map.first_key_value().map(|(key, _)| key)
+    }
+
+    /// Returns the largest key in the set.
+    pub fn last(&mut self) -> Option<&K> {
+        self.map.last_key_value().map(|(key, _)| key)
+    }
+
+    /// Removes the smallest key in the set, and returns it.
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.map.pop_first().map(|(key, _)| key)
+    }
+
+    /// Removes the largest key in the set, and returns it.
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.map.pop_last().map(|(key, _)| key)
+    }
+
+    /// Iterates over the keys of the set, in order.
+    pub fn iter(&mut self) -> impl Iterator<Item = &K> {
+        self.map.keys()
+    }
+
+    /// Iterates over the keys in the given range, in order.
+    pub fn range<R: RangeBounds<K>>(&mut self, range: R) -> impl Iterator<Item = &K> {
+        self.map.range(range).map(|(key, _)| key)
+    }
+
+    /// Computes the summary of all keys in the given range.
+    pub fn range_summary<R: RangeBounds<K>>(&mut self, range: R) -> D::Summary {
+        self.map.range_summary(range)
+    }
+
+    /// Applies an action on all keys in the given range.
+    /// The action must not change the keys' order or reverse the segment.
+    pub fn act_range<R: RangeBounds<K>>(&mut self, range: R, action: D::Action) {
+        self.map.act_range(range, action)
+    }
+
+    /// Returns the number of keys in the set that are smaller than the given key.
+    /// If the key is in the set, this is its index.
+    pub fn rank(&mut self, key: &K) -> usize {
+        self.map.rank(key)
+    }
+
+    /// Returns the key with the given index, i.e., the key with exactly
+    /// `index` smaller keys in the set.
+    pub fn select(&mut self, index: usize) -> Option<&K> {
+        self.map.select(index).map(|(key, _)| key)
+    }
+}
+
+impl<K, D, T> Default for GroveSet<K, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, ())>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, D, T> Extend<K> for GroveSet<K, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, ())>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|key| (key, ())))
+    }
+}
+
+impl<K, D, T> std::iter::FromIterator<K> for GroveSet<K, D, T>
+where
+    K: Ord,
+    D: Data<Value = (K, ())>,
+    D::Summary: SizedSummary,
+    T: SomeTree<D>,
+    for<'a> &'a mut T: ModifiableTreeRef<D>,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<K, D, T> IntoIterator for GroveSet<K, D, T>
+where
+    D: Data<Value = (K, ())>,
+    T: IntoIterator<Item = (K, ())>,
+{
+    type Item = K;
+    type IntoIter = std::iter::Map<T::IntoIter, fn((K, ())) -> K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter().map(|(key, _)| key)
+    }
+}
@@ -0,0 +1,630 @@
+//! A text rope, implemented on top of any of the splittable trees in this library.
+//!
+//! A [`Rope`] stores its text as a sequence of string chunks, in a tree with the [`RopeData`] instance.
+//! The summaries ([`TextSummary`]) keep track of the number of bytes, chars, UTF-16 code units
+//! and newlines, so that positions in the text can be addressed by byte offset ([`ByteOffset`]),
+//! char index ([`CharIndex`]), UTF-16 code unit index ([`Utf16Index`]),
+//! or by line and column ([`LineCol`]). All of these are locators, so they can also
+//! be used directly on the underlying tree.
+//!
+//! Insertions and removals take logarithmic time (plus the length of the inserted text):
+//! they split the chunks and the tree at the edges of the edited range, and concatenate the pieces back,
+//! merging the chunks at the edges when they fit in one chunk together.
+//!
+//! Like the trees themselves, most queries receive `&mut self`, since some trees (e.g, splay trees)
+//! restructure themselves on every access.
+//!
+//!```
+//! use grove::rope::{Rope, ByteOffset, CharIndex, LineCol};
+//!
+//! let mut rope: Rope = Rope::from("hello world\nsecond line");
+//! rope.insert_str(ByteOffset(5), ",");
+//! rope.insert_str(LineCol(1, 0), "the ");
+//! rope.remove(CharIndex(0)..CharIndex(1));
+//!
+//! assert_eq!(rope.chunks().collect::<String>(), "ello, world\nthe second line");
+//! assert_eq!(rope.slice(LineCol(1, 4)..LineCol(1, 10)), "second");
+//! assert_eq!(rope.lines().collect::<Vec<_>>(), vec!["ello, world", "the second line"]);
+//! assert_eq!(rope.len_lines(), 2);
+//!```
+
+use crate::example_data::Unit;
+use crate::locators::{LeftEdgeOf, LocResult, Locator};
+use crate::treap::Treap;
+use crate::*;
+use std::cmp::Ordering;
+use std::ops::{Add, Bound, RangeBounds};
+
+/// Texts are split into chunks of at most this many bytes.
+/// Edits merge the chunks around the edited positions when they fit in one chunk together,
+/// so that small edits don't leave many small chunks behind.
+const MAX_CHUNK_BYTES: usize = 512;
+
+/// Splits the text into chunks of at most [`MAX_CHUNK_BYTES`] bytes, at char boundaries.
+fn make_chunks(mut text: &str) -> impl Iterator<Item = String> + '_ {
+    std::iter::from_fn(move || {
+        if text.is_empty() {
+            return None;
+        }
+        let mut end = std::cmp::min(MAX_CHUNK_BYTES, text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, rest) = text.split_at(end);
+        text = rest;
+        Some(chunk.to_string())
+    })
+}
+
+/// The summary of a segment of text.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, Default)]
+pub struct TextSummary {
+    /// The number of bytes in the segment.
+    pub bytes: usize,
+    /// The number of chars in the segment.
+    pub chars: usize,
+    /// The number of UTF-16 code units in the segment.
+    pub utf16: usize,
+    /// The number of newlines (`'\n'`) in the segment.
+    pub newlines: usize,
+    /// The number of chars after the last newline of the segment.
+    /// If there are no newlines in the segment, this is the number of chars in the segment.
+    pub last_line_chars: usize,
+}
+
+impl TextSummary {
+    /// Computes the summary of a string.
+    pub fn of(text: &str) -> TextSummary {
+        let chars = text.chars().count();
+        TextSummary {
+            bytes: text.len(),
+            chars,
+            utf16: text.chars().map(char::len_utf16).sum(),
+            newlines: text.matches('\n').count(),
+            last_line_chars: match text.rfind('\n') {
+                Some(index) => text[index + 1..].chars().count(),
+                None => chars,
+            },
+        }
+    }
+
+    /// The line and column of the position right after this segment,
+    /// assuming that the segment starts at the beginning of the text.
+    /// The column is counted in chars.
+    pub fn line_col(self) -> (usize, usize) {
+        (self.newlines, self.last_line_chars)
+    }
+}
+
+impl Add for TextSummary {
+    type Output = TextSummary;
+    fn add(self, other: TextSummary) -> TextSummary {
+        TextSummary {
+            bytes: self.bytes + other.bytes,
+            chars: self.chars + other.chars,
+            utf16: self.utf16 + other.utf16,
+            newlines: self.newlines + other.newlines,
+            last_line_chars: if other.newlines > 0 {
+                other.last_line_chars
+            } else {
+                self.last_line_chars + other.chars
+            },
+        }
+    }
+}
+
+/// The [`Data`] instance of [`Rope`]s. The values are chunks of text.
+pub struct RopeData {}
+
+impl Data for RopeData {
+    type Value = String;
+    type Summary = TextSummary;
+    type Action = Unit;
+
+    fn to_summary(chunk: &String) -> TextSummary {
+        TextSummary::of(chunk)
+    }
+}
+
+/// Positions in a text. Each position is a locator that accepts the chunk containing the position,
+/// i.e., the chunk that contains the char right after the position.
+/// A position at the end of the text isn't accepted by any chunk.
+pub trait TextPosition: Locator<RopeData> + Copy {
+    /// Compares this position to the position right after a text whose summary is `summary`.
+    fn cmp_summary(&self, summary: TextSummary) -> Ordering;
+
+    /// Returns the byte offset of this position inside the chunk, given the summary
+    /// of the text before the chunk. Should be called only if the chunk contains the position.
+    /// Panics if the position isn't a valid position in the chunk.
+    fn offset_in_chunk(&self, before: TextSummary, chunk: &str) -> usize;
+}
+
+/// The common implementation of [`Locator`] for [`TextPosition`]s.
+fn locate_position<P: TextPosition>(position: &P, left: TextSummary, chunk: &str) -> LocResult {
+    if position.cmp_summary(left) == Ordering::Less {
+        LocResult::GoLeft
+    } else if position.cmp_summary(left + TextSummary::of(chunk)) == Ordering::Less {
+        LocResult::Accept
+    } else {
+        LocResult::GoRight
+    }
+}
+
+/// A position in a text, given by its byte offset.
+/// The offset has to be on a char boundary.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, PartialOrd, Ord)]
+pub struct ByteOffset(pub usize);
+
+impl TextPosition for ByteOffset {
+    fn cmp_summary(&self, summary: TextSummary) -> Ordering {
+        self.0.cmp(&summary.bytes)
+    }
+
+    fn offset_in_chunk(&self, before: TextSummary, chunk: &str) -> usize {
+        let offset = self.0 - before.bytes;
+        assert!(
+            chunk.is_char_boundary(offset),
+            "byte offset {} is not a char boundary",
+            self.0
+        );
+        offset
+    }
+}
+
+impl Locator<RopeData> for ByteOffset {
+    fn locate(&self, left: TextSummary, chunk: &String, _right: TextSummary) -> LocResult {
+        locate_position(self, left, chunk)
+    }
+}
+
+/// A position in a text, given by the number of chars before it.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, PartialOrd, Ord)]
+pub struct CharIndex(pub usize);
+
+impl TextPosition for CharIndex {
+    fn cmp_summary(&self, summary: TextSummary) -> Ordering {
+        self.0.cmp(&summary.chars)
+    }
+
+    fn offset_in_chunk(&self, before: TextSummary, chunk: &str) -> usize {
+        chunk.char_indices().nth(self.0 - before.chars).unwrap().0
+    }
+}
+
+impl Locator<RopeData> for CharIndex {
+    fn locate(&self, left: TextSummary, chunk: &String, _right: TextSummary) -> LocResult {
+        locate_position(self, left, chunk)
+    }
+}
+
+/// A position in a text, given by the number of UTF-16 code units before it.
+/// The position can't be in the middle of a surrogate pair.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, PartialOrd, Ord)]
+pub struct Utf16Index(pub usize);
+
+impl TextPosition for Utf16Index {
+    fn cmp_summary(&self, summary: TextSummary) -> Ordering {
+        self.0.cmp(&summary.utf16)
+    }
+
+    fn offset_in_chunk(&self, before: TextSummary, chunk: &str) -> usize {
+        let mut utf16 = before.utf16;
+        for (offset, c) in chunk.char_indices() {
+            match utf16.cmp(&self.0) {
+                Ordering::Less => utf16 += c.len_utf16(),
+                Ordering::Equal => return offset,
+                Ordering::Greater => break,
+            }
+        }
+        panic!("UTF-16 index {} is inside a surrogate pair", self.0);
+    }
+}
+
+impl Locator<RopeData> for Utf16Index {
+    fn locate(&self, left: TextSummary, chunk: &String, _right: TextSummary) -> LocResult {
+        locate_position(self, left, chunk)
+    }
+}
+
+/// A position in a text, given by its line and its column, both starting from zero.
+/// The column is counted in chars, and can be at most the length of the line.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, PartialOrd, Ord)]
+pub struct LineCol(pub usize, pub usize);
+
+impl TextPosition for LineCol {
+    fn cmp_summary(&self, summary: TextSummary) -> Ordering {
+        (self.0, self.1).cmp(&summary.line_col())
+    }
+
+    fn offset_in_chunk(&self, before: TextSummary, chunk: &str) -> usize {
+        let (mut line, mut col) = before.line_col();
+        for (offset, c) in chunk.char_indices() {
+            if (line, col) == (self.0, self.1) {
+                return offset;
+            }
+            if c == '\n' {
+                assert!(
+                    line != self.0,
+                    "column {} is past the end of line {}",
+                    self.1,
+                    self.0
+                );
+                line += 1;
+                col = 0;
+            } else {
+                col += 1;
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Locator<RopeData> for LineCol {
+    fn locate(&self, left: TextSummary, chunk: &String, _right: TextSummary) -> LocResult {
+        locate_position(self, left, chunk)
+    }
+}
+
+/// A text rope, implemented using a tree of type `T`. See the [module documentation](self).
+pub struct Rope<T = Treap<RopeData>> {
+    tree: T,
+}
+
+impl<T> Rope<T>
+where
+    T: ConcatenableTree<RopeData>,
+    for<'a> &'a mut T: SplittableTreeRef<RopeData, T = T>,
+{
+    /// Creates an empty rope.
+    pub fn new() -> Self {
+        Rope { tree: T::default() }
+    }
+
+    /// Returns the summary of the whole text.
+    pub fn summary(&self) -> TextSummary {
+        self.tree.subtree_summary()
+    }
+
+    /// Returns the length of the text in bytes.
+    pub fn len_bytes(&self) -> usize {
+        self.summary().bytes
+    }
+
+    /// Returns the length of the text in chars.
+    pub fn len_chars(&self) -> usize {
+        self.summary().chars
+    }
+
+    /// Returns the length of the text in UTF-16 code units.
+    pub fn len_utf16(&self) -> usize {
+        self.summary().utf16
+    }
+
+    /// Returns the number of lines in the text, i.e., the number of newlines plus one.
+    /// Note that this differs from the number of lines returned by [`Rope::lines`], if the
+    /// text is empty or ends with a newline.
+    pub fn len_lines(&self) -> usize {
+        self.summary().newlines + 1
+    }
+
+    /// Returns [`true`] if the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len_bytes() == 0
+    }
+
+    /// Returns a reference to the underlying tree.
+    pub fn as_tree(&self) -> &T {
+        &self.tree
+    }
+
+    /// Returns the underlying tree.
+    pub fn into_tree(self) -> T {
+        self.tree
+    }
+
+    /// Returns the summary of the text before the given position.
+    /// This can be used to convert between different kinds of positions.
+    /// Panics if the position is out of bounds.
+    ///```
+    /// use grove::rope::{Rope, ByteOffset, CharIndex, LineCol};
+    ///
+    /// let mut rope: Rope = Rope::from("αβγ\nδε");
+    ///
+    /// assert_eq!(rope.summary_before(CharIndex(5)).bytes, 9);
+    /// assert_eq!(rope.summary_before(ByteOffset(9)).line_col(), (1, 1));
+    /// assert_eq!(rope.summary_before(LineCol(1, 0)).chars, 4);
+    ///```
+    pub fn summary_before<P: TextPosition>(&mut self, position: P) -> TextSummary {
+        let walker = self.tree.search(position);
+        let before = walker.left_summary();
+        match walker.value() {
+            Some(chunk) => {
+                let offset = position.offset_in_chunk(before, chunk);
+                before + TextSummary::of(&chunk[..offset])
+            }
+            None => {
+                assert!(
+                    position.cmp_summary(before) == Ordering::Equal,
+                    "position is out of bounds"
+                );
+                before
+            }
+        }
+    }
+
+    /// Returns the byte offset of the given position.
+    /// Panics if the position is out of bounds.
+    pub fn byte_offset<P: TextPosition>(&mut self, position: P) -> usize {
+        self.summary_before(position).bytes
+    }
+
+    /// Returns the byte offset right after the char at the given position.
+    fn byte_offset_after<P: TextPosition>(&mut self, position: P) -> usize {
+        let walker = self.tree.search(position);
+        let before = walker.left_summary();
+        let chunk = walker.value().expect("position is out of bounds");
+        let offset = position.offset_in_chunk(before, chunk);
+        let c = chunk[offset..].chars().next().unwrap();
+        before.bytes + offset + c.len_utf8()
+    }
+
+    /// Converts a range of positions to a range of byte offsets.
+    fn byte_range<P: TextPosition, R: RangeBounds<P>>(&mut self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&position) => self.byte_offset(position),
+            Bound::Excluded(&position) => self.byte_offset_after(position),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&position) => self.byte_offset_after(position),
+            Bound::Excluded(&position) => self.byte_offset(position),
+            Bound::Unbounded => self.len_bytes(),
+        };
+        assert!(start <= end, "range starts after it ends");
+        (start, end)
+    }
+
+    /// Makes sure that there is a chunk boundary at the given byte offset.
+    fn split_chunk_at(&mut self, at: usize) {
+        let mut walker = self.tree.search(ByteOffset(at));
+        let before = walker.left_summary();
+        let offset = match walker.value() {
+            Some(chunk) => ByteOffset(at).offset_in_chunk(before, chunk),
+            None => return,
+        };
+        if offset == 0 {
+            return;
+        }
+        let second = walker.with_value(|chunk| chunk.split_off(offset)).unwrap();
+        walker.next_empty().unwrap();
+        walker.insert(second).unwrap();
+    }
+
+    /// Merges the chunks on both sides of the given byte offset, if they fit in one chunk together.
+    /// Should only be called on a chunk boundary.
+    fn merge_chunks_at(&mut self, at: usize) {
+        if at == 0 || at >= self.len_bytes() {
+            return;
+        }
+        let left_len = self.tree.search(ByteOffset(at - 1)).value().unwrap().len();
+        let right_len = self.tree.search(ByteOffset(at)).value().unwrap().len();
+        if left_len + right_len > MAX_CHUNK_BYTES {
+            return;
+        }
+        let right = self.tree.search(ByteOffset(at)).delete().unwrap();
+        self.tree
+            .search(ByteOffset(at - 1))
+            .with_value(|chunk| chunk.push_str(&right));
+    }
+
+    /// Merges the chunk containing the given byte offset with the chunks next to it,
+    /// if they fit in one chunk together.
+    fn merge_chunks_around(&mut self, at: usize) {
+        if at >= self.len_bytes() {
+            return;
+        }
+        let walker = self.tree.search(ByteOffset(at));
+        let start = walker.left_summary().bytes;
+        let end = start + walker.value().unwrap().len();
+        drop(walker);
+        self.merge_chunks_at(end);
+        self.merge_chunks_at(start);
+    }
+
+    /// Splits the rope at the given byte offset. `self` keeps the text before the offset,
+    /// and the text after it is returned.
+    fn split_off_bytes(&mut self, at: usize) -> Self {
+        self.split_chunk_at(at);
+        Rope {
+            tree: self
+                .tree
+                .slice(LeftEdgeOf(ByteOffset(at)))
+                .split_right()
+                .unwrap(),
+        }
+    }
+
+    /// Splits the rope at the given position. `self` keeps the text before the position,
+    /// and the text after it is returned.
+    /// Panics if the position is out of bounds.
+    pub fn split_off<P: TextPosition>(&mut self, position: P) -> Self {
+        let at = self.byte_offset(position);
+        self.split_off_bytes(at)
+    }
+
+    /// Appends the text of `other` to the end of `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        let at = self.len_bytes();
+        self.tree.concatenate_right(std::mem::take(&mut other.tree));
+        self.merge_chunks_at(at);
+    }
+
+    /// Inserts the text at the given position.
+    /// Panics if the position is out of bounds.
+    pub fn insert_str<P: TextPosition>(&mut self, position: P, text: &str) {
+        let at = self.byte_offset(position);
+        if text.is_empty() {
+            return;
+        }
+
+        // If the chunk containing the position, or the chunk ending at the position,
+        // has enough room, insert the text into it directly.
+        let mut walker = self.tree.search(ByteOffset(at));
+        if walker.is_empty() {
+            let _ = walker.previous_filled();
+        }
+        let start = walker.left_summary().bytes;
+        let fits = match walker.value() {
+            Some(chunk) => chunk.len() + text.len() <= MAX_CHUNK_BYTES,
+            None => false,
+        };
+        if fits {
+            walker.with_value(|chunk| chunk.insert_str(at - start, text));
+            return;
+        }
+        drop(walker);
+
+        let right = self.split_off_bytes(at);
+        self.tree.concatenate_right(make_chunks(text).collect());
+        self.tree.concatenate_right(right.tree);
+        // the chunks around the edges of the new text might have been split.
+        self.merge_chunks_around(at + text.len());
+        if at > 0 {
+            self.merge_chunks_around(at - 1);
+        }
+    }
+
+    /// Removes the text in the given range of positions.
+    /// Panics if the range is out of bounds.
+    pub fn remove<P: TextPosition, R: RangeBounds<P>>(&mut self, range: R) {
+        let (start, end) = self.byte_range(range);
+        let right = self.split_off_bytes(end);
+        self.split_off_bytes(start);
+        self.tree.concatenate_right(right.tree);
+        // the chunks around the removed text might have been split.
+        self.merge_chunks_around(start);
+        if start > 0 {
+            self.merge_chunks_around(start - 1);
+        }
+    }
+
+    /// Returns the text in the given range of positions.
+    /// Panics if the range is out of bounds.
+    pub fn slice<P: TextPosition, R: RangeBounds<P>>(&mut self, range: R) -> String {
+        let (start, end) = self.byte_range(range);
+        let mut result = String::with_capacity(end - start);
+        if start == end {
+            return result;
+        }
+        let mut chunk_start = self.tree.search(ByteOffset(start)).left_summary().bytes;
+        let overlapping = |left: TextSummary, chunk: &String, _right: TextSummary| {
+            if left.bytes + chunk.len() <= start {
+                LocResult::GoRight
+            } else if end <= left.bytes {
+                LocResult::GoLeft
+            } else {
+                LocResult::Accept
+            }
+        };
+        for chunk in self.tree.iter_locator(&overlapping) {
+            let from = start.saturating_sub(chunk_start);
+            let to = std::cmp::min(end - chunk_start, chunk.len());
+            result.push_str(&chunk[from..to]);
+            chunk_start += chunk.len();
+        }
+        result
+    }
+
+    /// Iterates over the chunks of the text.
+    pub fn chunks(&mut self) -> impl Iterator<Item = &str> {
+        self.tree.iter().map(String::as_str)
+    }
+
+    /// Iterates over the chars of the text.
+    pub fn chars(&mut self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
+    }
+
+    /// Iterates over the lines of the text, like [`str::lines`]:
+    /// Lines end with either a newline (`\n`) or a carriage return followed by a newline (`\r\n`),
+    /// which aren't included in the returned lines. The final line ending is optional.
+    pub fn lines(
+        &mut self,
+    ) -> Lines<'_, basic_tree::iterators::IterLocator<'_, RopeData, std::ops::RangeFull, T::TreeData>>
+    {
+        Lines {
+            chunks: self.tree.iter(),
+            rest: "",
+        }
+    }
+}
+
+impl<T> Default for Rope<T>
+where
+    T: ConcatenableTree<RopeData>,
+    for<'a> &'a mut T: SplittableTreeRef<RopeData, T = T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<&str> for Rope<T>
+where
+    T: ConcatenableTree<RopeData>,
+    for<'a> &'a mut T: SplittableTreeRef<RopeData, T = T>,
+{
+    fn from(text: &str) -> Self {
+        Rope {
+            tree: make_chunks(text).collect(),
+        }
+    }
+}
+
+impl<T: IntoIterator<Item = String>> From<Rope<T>> for String {
+    fn from(rope: Rope<T>) -> String {
+        rope.tree.into_iter().collect()
+    }
+}
+
+/// An iterator over the lines of a [`Rope`]. See [`Rope::lines`].
+pub struct Lines<'a, I> {
+    chunks: I,
+    /// The rest of the current chunk.
+    rest: &'a str,
+}
+
+impl<'a, I: Iterator<Item = &'a String>> Iterator for Lines<'a, I> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let mut line = String::new();
+        let mut started = false;
+        loop {
+            if self.rest.is_empty() {
+                match self.chunks.next() {
+                    Some(chunk) => {
+                        self.rest = chunk;
+                        continue;
+                    }
+                    None => return if started { Some(line) } else { None },
+                }
+            }
+            started = true;
+            match self.rest.find('\n') {
+                Some(index) => {
+                    line.push_str(&self.rest[..index]);
+                    self.rest = &self.rest[index + 1..];
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                    return Some(line);
+                }
+                None => {
+                    line.push_str(self.rest);
+                    self.rest = "";
+                }
+            }
+        }
+    }
+}
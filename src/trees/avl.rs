@@ -13,30 +13,33 @@ type T = u8;
 /// Used for rank differences
 type TD = i8;
 
+/// A tree taken apart into its root's left subtree, its root, and its root's right subtree.
+type Exposed<D> = (AVLTree<D>, Box<BasicNode<D, T>>, AVLTree<D>);
+
 /// An AVL tree. Balanced by keeping track of node ranks, this is a worst-case balancing
 /// Algorithm that has a small memory overhead per node.
 pub struct AVLTree<D: Data> {
     tree: BasicTree<D, T>,
 }
 
-/// For implementing `rank`, `rank_diff` and `rebuild_ranks` for
+/// For implementing `avl_rank`, `rank_diff` and `rebuild_ranks` for
 /// trees, nodes and walkers alike.
 trait Rankable {
-    fn rank(&self) -> T;
+    fn avl_rank(&self) -> T;
 
     /// Returns `true` if the rank of the current node had to be updated,
     /// `false` if it was correct.
     fn rebuild_ranks(&mut self) -> bool;
 
-    /// Returns `right.rank() - left.rank()`
+    /// Returns `right.avl_rank() - left.avl_rank()`
     fn rank_diff(&self) -> TD;
 }
 
 impl<D: Data> Rankable for BasicTree<D, T> {
-    fn rank(&self) -> T {
+    fn avl_rank(&self) -> T {
         match self.node() {
             None => 0,
-            Some(node) => node.rank(),
+            Some(node) => node.avl_rank(),
         }
     }
 
@@ -48,7 +51,7 @@ impl<D: Data> Rankable for BasicTree<D, T> {
         }
     }
 
-    /// Returns `right.rank() - left.rank()`
+    /// Returns `right.avl_rank() - left.avl_rank()`
     fn rank_diff(&self) -> TD {
         match self.node() {
             None => 0,
@@ -58,13 +61,13 @@ impl<D: Data> Rankable for BasicTree<D, T> {
 }
 
 impl<D: Data> Rankable for BasicNode<D, T> {
-    fn rank(&self) -> T {
+    fn avl_rank(&self) -> T {
         *self.alg_data()
     }
 
-    /// Returns `right.rank() - left.rank()`
+    /// Returns `right.avl_rank() - left.avl_rank()`
     fn rank_diff(&self) -> TD {
-        let diff = self.right.rank() as TD - self.left.rank() as TD;
+        let diff = self.right.avl_rank() as TD - self.left.avl_rank() as TD;
         if self.action().to_reverse() {
             -diff
         } else {
@@ -73,8 +76,8 @@ impl<D: Data> Rankable for BasicNode<D, T> {
     }
 
     fn rebuild_ranks(&mut self) -> bool {
-        let new_rank = std::cmp::max(self.left.rank(), self.right.rank()) + 1;
-        let changed = self.rank() != new_rank;
+        let new_rank = std::cmp::max(self.left.avl_rank(), self.right.avl_rank()) + 1;
+        let changed = self.avl_rank() != new_rank;
         self.alg_data = new_rank;
         changed
     }
@@ -97,9 +100,15 @@ impl<D: Data> AVLTree<D> {
     }
 
     fn assert_ranks_locally_internal(node: &BasicNode<D, T>) {
-        assert!(node.rank() == node.left.rank() + 1 || node.rank() == node.right.rank() + 1);
-        assert!(node.left.rank() == node.rank() - 1 || node.left.rank() == node.rank() - 2);
-        assert!(node.right.rank() == node.rank() - 1 || node.right.rank() == node.rank() - 2);
+        assert!(Self::ranks_correct_locally(node));
+    }
+
+    /// Returns `true` if the node's rank is one more than the bigger of its sons' ranks,
+    /// and its sons' ranks differ by at most one.
+    fn ranks_correct_locally(node: &BasicNode<D, T>) -> bool {
+        let (left, right) = (node.left.avl_rank(), node.right.avl_rank());
+        Some(node.avl_rank()) == std::cmp::max(left, right).checked_add(1)
+            && (left as TD - right as TD).abs() <= 1
     }
 
     /// Asserts that the tree's ranks are correct.
@@ -108,14 +117,24 @@ impl<D: Data> AVLTree<D> {
         self.tree
             .assert_correctness_with(Self::assert_ranks_locally_internal);
     }
+
+    /// Takes the tree apart into its root's left subtree, its root, and its root's right subtree.
+    /// Returns [`None`] if the tree is empty.
+    fn expose(self) -> Option<Exposed<D>> {
+        let mut node = self.tree.into_node_boxed()?;
+        node.access();
+        let left = std::mem::replace(&mut node.left, BasicTree::Empty);
+        let right = std::mem::replace(&mut node.right, BasicTree::Empty);
+        Some((AVLTree { tree: left }, node, AVLTree { tree: right }))
+    }
 }
 
 impl<D: Data> Rankable for AVLTree<D> {
-    fn rank(&self) -> T {
-        self.tree.rank()
+    fn avl_rank(&self) -> T {
+        self.tree.avl_rank()
     }
 
-    /// Returns `right.rank() - left.rank()`
+    /// Returns `right.avl_rank() - left.avl_rank()`
     fn rank_diff(&self) -> TD {
         self.tree.rank_diff()
     }
@@ -125,6 +144,45 @@ impl<D: Data> Rankable for AVLTree<D> {
     }
 }
 
+impl<D: Data> QueryableTree<D> for AVLTree<D> {
+    type ImmTreeData = T;
+
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.segment_summary_imm(locator)
+    }
+
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.search_imm(locator)
+    }
+
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: locators::Locator<D>,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.search_ref(locator)
+    }
+
+    fn iter_locator_imm<L>(
+        &self,
+        locator: L,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, L, T>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.iter_locator_imm(locator)
+    }
+}
+
 impl<D: Data> Default for AVLTree<D> {
     fn default() -> Self {
         AVLTree::new()
@@ -180,6 +238,13 @@ impl<D: Data> SomeTree<D> for AVLTree<D> {
         iterators::IterLocator::new(&mut self.tree, locator)
     }
 
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L, u8> {
+        iterators::CursorMut::new(&mut self.tree, locator)
+    }
+
     fn assert_correctness(&self)
     where
         D::Summary: Eq,
@@ -225,24 +290,129 @@ derive_SomeEntry! {tree,
     }
 }
 
+impl<D: Data> crate::debug::DebugTree<D> for AVLTree<D> {
+    type AlgData = T;
+
+    fn basic_tree(&self) -> &BasicTree<D, T> {
+        &self.tree
+    }
+
+    fn describe_alg_data(rank: &T) -> Option<String> {
+        Some(format!("rank {}", rank))
+    }
+}
+
 impl<D: Data> std::iter::FromIterator<D::Value> for AVLTree<D> {
+    /// Builds a perfectly balanced tree.
     /// This takes `O(n)` worst-case time.
-    fn from_iter<T: IntoIterator<Item = D::Value>>(iter: T) -> Self {
-        // TODO: check if inserting is O(1) amortized. if it is, we can do this by
-        // just calling insert.
-        // if not, than this is `O(n log n)` worst-case time.
-
-        let mut tree: AVLTree<D> = Default::default();
-        let mut walker = tree.walker();
-        for val in iter.into_iter() {
-            // note: this relies on the assumption, that after we insert a node, the new position of the locator
-            // will be an ancestor of the location where the value was inserted.
-            // TODO: check.
-            while walker.go_right().is_ok() {}
-            walker.insert(val);
+    fn from_iter<I: IntoIterator<Item = D::Value>>(iter: I) -> Self {
+        let nodes: Vec<_> = iter
+            .into_iter()
+            .map(|val| Box::new(BasicNode::new_alg(val, 0 /* dummy value */)))
+            .collect();
+        let count = nodes.len();
+        AVLTree {
+            // the heights of the two subtrees of every node differ by at most one,
+            // so the ranks are valid.
+            tree: basic_tree::build_balanced(&mut nodes.into_iter(), count, |node| {
+                node.rebuild_ranks();
+            }),
         }
-        drop(walker);
-        tree
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<D: Data> rayon::iter::FromParallelIterator<D::Value> for AVLTree<D>
+where
+    D::Value: Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    /// Builds a perfectly balanced tree from the values, in the same order as the iterator.
+    /// The values are first collected, and then the tree is built in parallel.
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut tree: AVLTree<StdNum> = (0..10_000).into_par_iter().collect();
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (0..10_000).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: rayon::iter::IntoParallelIterator<Item = D::Value>,
+    {
+        use rayon::prelude::*;
+        let mut values: Vec<_> = par_iter.into_par_iter().map(Some).collect();
+        AVLTree {
+            tree: basic_tree::par_build_balanced(&mut values, |node| {
+                node.rebuild_ranks();
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "snapshot")]
+impl<D: Data> AVLTree<D> {
+    /// Writes a snapshot of the tree, that preserves its exact shape, including the ranks.
+    /// See [`basic_tree::snapshot`].
+    pub fn to_snapshot(&self) -> Vec<u8>
+    where
+        D::Value: serde::Serialize,
+        D::Summary: serde::Serialize,
+        D::Action: serde::Serialize,
+    {
+        snapshot::write(&self.tree, snapshot::TreeKind::AVL)
+    }
+
+    /// Loads a snapshot written by [`AVLTree::to_snapshot`], in `O(n)` time.
+    /// Checks the summaries and the ranks of all nodes.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, snapshot::SnapshotError>
+    where
+        D::Value: serde::de::DeserializeOwned,
+        D::Summary: serde::de::DeserializeOwned + Eq,
+        D::Action: serde::de::DeserializeOwned,
+    {
+        let tree = snapshot::read(bytes, snapshot::TreeKind::AVL, Self::ranks_correct_locally)?;
+        Ok(AVLTree { tree })
+    }
+}
+
+/// Serializes the tree as the sequence of its values, in order, with all pending actions applied.
+#[cfg(feature = "serde")]
+impl<D: Data> serde::Serialize for AVLTree<D>
+where
+    D::Value: serde::Serialize + Clone,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.tree.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<D: Data> AVLTree<D> {
+    /// Serializes the tree by reference, without requiring the values to be [`Clone`].
+    /// See [`BasicTree::serialize_ref`].
+    pub fn serialize_ref<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        D::Value: serde::Serialize,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.serialize_ref(serializer)
+    }
+}
+
+/// Deserializes a sequence of values, and builds a balanced tree out of them in `O(n)` time.
+#[cfg(feature = "serde")]
+impl<'de, D: Data> serde::Deserialize<'de> for AVLTree<D>
+where
+    D::Value: serde::Deserialize<'de>,
+{
+    fn deserialize<De: serde::Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let values = Vec::<D::Value>::deserialize(deserializer)?;
+        Ok(values.into_iter().collect())
     }
 }
 
@@ -294,14 +464,14 @@ derive_SomeEntry!{walker,
 impl<'a, D: Data> Rankable for AVLWalker<'a, D> {
     /// Returns the priority of the current node. Lower numbers means
     /// The node is closer to the root.
-    fn rank(&self) -> T {
+    fn avl_rank(&self) -> T {
         match self.walker.node() {
             None => 0,
             Some(node) => *node.alg_data(),
         }
     }
 
-    /// Returns `right.rank() - left.rank()`
+    /// Returns `right.avl_rank() - left.avl_rank()`
     fn rank_diff(&self) -> TD {
         self.walker.inner().rank_diff()
     }
@@ -567,13 +737,13 @@ impl<D: Data> AVLTree<D> {
         mut mid: Box<BasicNode<D, T>>,
         mut right: AVLTree<D>,
     ) {
-        if self.rank() < right.rank() {
+        if self.avl_rank() < right.avl_rank() {
             std::mem::swap(self, &mut right);
             self.concatenate_boxed_middle_left(right, mid);
             return;
         }
         let mut walker = self.walker();
-        while walker.rank() > right.rank() {
+        while walker.avl_rank() > right.avl_rank() {
             walker.go_right().unwrap();
         }
         mid.alg_data = 0;
@@ -607,13 +777,13 @@ impl<D: Data> AVLTree<D> {
         mut left: AVLTree<D>,
         mut mid: Box<BasicNode<D, T>>,
     ) {
-        if self.rank() < left.rank() {
+        if self.avl_rank() < left.avl_rank() {
             std::mem::swap(self, &mut left);
             self.concatenate_boxed_middle_right(mid, left);
             return;
         }
         let mut walker = self.walker();
-        while walker.rank() > left.rank() {
+        while walker.avl_rank() > left.avl_rank() {
             walker.go_left().unwrap();
         }
         mid.alg_data = 0;
@@ -625,6 +795,194 @@ impl<D: Data> AVLTree<D> {
     }
 }
 
+impl<D: Data> AVLTree<D> {
+    /// Computes the union of two trees, ordered by keys.
+    /// We order the resulting tree based on the `D::Value: Keyed` instance, assuming that
+    /// the values in the existing trees are also in the correct order.
+    ///
+    /// Values with equal keys are all kept, and the values from `tree2` are placed after
+    /// the values of `self` with the same key.
+    ///
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = AVLTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.union(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3,4,4,5,5,6,6,7,8].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn union(&mut self, tree2: AVLTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = union_internal(tree1, tree2);
+    }
+
+    /// Computes the intersection of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys appear in `tree2`. Values with equal keys in `self` are all kept.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = AVLTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.intersection(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn intersection(&mut self, tree2: AVLTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = intersection_internal(tree1, tree2);
+    }
+
+    /// Computes the difference of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys don't appear in `tree2`.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = AVLTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.difference(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn difference(&mut self, tree2: AVLTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = difference_internal(tree1, tree2);
+    }
+}
+
+/// Splits the tree into the values with keys smaller than `key`, the values with keys equal
+/// to `key`, and the values with keys bigger than `key`.
+fn split_by_key<D: Data>(
+    mut tree: AVLTree<D>,
+    key: &<D::Value as Keyed>::Key,
+) -> (AVLTree<D>, AVLTree<D>, AVLTree<D>)
+where
+    D::Value: Keyed,
+{
+    let mut mid = tree
+        .slice(locators::LeftEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    let right = mid
+        .slice(locators::RightEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    (tree, mid, right)
+}
+
+fn union_internal<D: Data>(tree1: AVLTree<D>, tree2: AVLTree<D>) -> AVLTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree2;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let mut left1 = tree1;
+    let right1 = left1
+        .slice(locators::RightEdgeOf(locators::ByKey((mid.node_value.get_key(),))))
+        .split_right()
+        .unwrap();
+
+    let mut res = union_internal(left1, left2);
+    res.concatenate_boxed_middle_right(mid, union_internal(right1, right2));
+    res
+}
+
+fn intersection_internal<D: Data>(tree1: AVLTree<D>, tree2: AVLTree<D>) -> AVLTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return AVLTree::new(),
+        Some(parts) => parts,
+    };
+    let (left1, equal1, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = intersection_internal(left1, left2);
+    res.concatenate_right(equal1);
+    res.concatenate_right(intersection_internal(right1, right2));
+    res
+}
+
+fn difference_internal<D: Data>(tree1: AVLTree<D>, tree2: AVLTree<D>) -> AVLTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let (left1, _, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = difference_internal(left1, left2);
+    res.concatenate_right(difference_internal(right1, right2));
+    res
+}
+
+impl<D: Data> SetOperations<D> for AVLTree<D> {
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        AVLTree::union(self, other);
+    }
+
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        AVLTree::intersection(self, other);
+    }
+
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        AVLTree::difference(self, other);
+    }
+}
+
 impl<D: Data> ConcatenableTree<D> for AVLTree<D> {
     /// Concatenates the trees together, in place.
     /// Complexity: `O(log n)`.
@@ -4,6 +4,7 @@
 
 use super::super::*; // crate::trees::*
 use super::*;
+use locators::LocResult;
 use recursive_reference::RecRef;
 
 const NO_VALUE_ERROR: &str = "invariant violated: RecRef can't be empty";
@@ -31,6 +32,13 @@ impl<D: Data> SomeTree<D> for BasicTree<D> {
         iterators::IterLocator::new(self, locator)
     }
 
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L> {
+        iterators::CursorMut::new(self, locator)
+    }
+
     /// Checks that invariants remain correct. i.e., that every node's summary
     /// is the sum of the summaries of its children.
     /// If it is not, panics.
@@ -46,6 +54,204 @@ impl<D: Data> SomeTree<D> for BasicTree<D> {
     }
 }
 
+impl<D: Data, T> QueryableTree<D> for BasicTree<D, T> {
+    type ImmTreeData = T;
+
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: Locator<D>,
+        D::Value: Clone,
+    {
+        let mut tree = self;
+        let mut action: D::Action = Default::default();
+        let mut left: D::Summary = Default::default();
+        let mut right: D::Summary = Default::default();
+        while let Some(node) = tree.node() {
+            let view = node.view(action);
+            let value_summary = view.value_summary();
+            let near_left_summary = left + view.left_summary();
+            let near_right_summary = view.right_summary() + right;
+            match locator.locate(near_left_summary, &view.value, near_right_summary) {
+                LocResult::GoRight => {
+                    left = near_left_summary + value_summary;
+                    tree = view.right;
+                }
+                LocResult::GoLeft => {
+                    right = value_summary + near_right_summary;
+                    tree = view.left;
+                }
+                // at this point, we split into the two sides
+                LocResult::Accept => {
+                    let first_half = segment_summary_on_suffix_imm(
+                        view.left,
+                        view.action,
+                        &locator,
+                        left,
+                        value_summary + near_right_summary,
+                    );
+                    let second_half = segment_summary_on_prefix_imm(
+                        view.right,
+                        view.action,
+                        &locator,
+                        near_left_summary + value_summary,
+                        right,
+                    );
+                    return first_half + value_summary + second_half;
+                }
+            }
+            action = view.action;
+        }
+
+        // empty segment case
+        Default::default()
+    }
+
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: Locator<D>,
+        D::Value: Clone,
+    {
+        let mut tree = self;
+        let mut action: D::Action = Default::default();
+        let mut left: D::Summary = Default::default();
+        let mut right: D::Summary = Default::default();
+        while let Some(node) = tree.node() {
+            let view = node.view(action);
+            let value_summary = view.value_summary();
+            let near_left_summary = left + view.left_summary();
+            let near_right_summary = view.right_summary() + right;
+            match locator.locate(near_left_summary, &view.value, near_right_summary) {
+                LocResult::GoRight => {
+                    left = near_left_summary + value_summary;
+                    tree = view.right;
+                }
+                LocResult::GoLeft => {
+                    right = value_summary + near_right_summary;
+                    tree = view.left;
+                }
+                LocResult::Accept => return Some(view.value),
+            }
+            action = view.action;
+        }
+        None
+    }
+
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: Locator<D>,
+        D::Action: ValuePreservingAction,
+    {
+        let mut tree = self;
+        let mut action: D::Action = Default::default();
+        let mut left: D::Summary = Default::default();
+        let mut right: D::Summary = Default::default();
+        while let Some(node) = tree.node() {
+            // like `node.view(action)`, except that the value is never changed by the action
+            action = action + node.action;
+            let (node_left, node_right) = if action.to_reverse() {
+                (&node.right, &node.left)
+            } else {
+                (&node.left, &node.right)
+            };
+            let value_summary = D::to_summary(&node.node_value);
+            let near_left_summary = left + action.act(node_left.subtree_summary());
+            let near_right_summary = action.act(node_right.subtree_summary()) + right;
+            match locator.locate(near_left_summary, &node.node_value, near_right_summary) {
+                LocResult::GoRight => {
+                    left = near_left_summary + value_summary;
+                    tree = node_right;
+                }
+                LocResult::GoLeft => {
+                    right = value_summary + near_right_summary;
+                    tree = node_left;
+                }
+                LocResult::Accept => return Some(&node.node_value),
+            }
+        }
+        None
+    }
+
+    fn iter_locator_imm<L>(&self, locator: L) -> iterators::ImmIterLocator<'_, D, L, T>
+    where
+        L: Locator<D>,
+        D::Value: Clone,
+    {
+        iterators::ImmIterLocator::new(self, locator)
+    }
+}
+
+/// Computes the summary of the part of the segment inside `tree`, assuming that the segment
+/// continues to the right of `tree`. `action` is the total action of the tree's ancestors, and
+/// `left` and `right` are the summaries of the values to the left and right of the tree.
+fn segment_summary_on_suffix_imm<D: Data, L: Locator<D>, T>(
+    mut tree: &BasicTree<D, T>,
+    mut action: D::Action,
+    locator: &L,
+    mut left: D::Summary,
+    mut right: D::Summary,
+) -> D::Summary
+where
+    D::Value: Clone,
+{
+    let mut res: D::Summary = Default::default();
+    while let Some(node) = tree.node() {
+        let view = node.view(action);
+        let value_summary = view.value_summary();
+        let right_summary = view.right_summary();
+        let near_left_summary = left + view.left_summary();
+        match locator.locate(near_left_summary, &view.value, right_summary + right) {
+            LocResult::Accept => {
+                res = value_summary + right_summary + res;
+                right = value_summary + right_summary + right;
+                tree = view.left;
+            }
+            LocResult::GoRight => {
+                left = near_left_summary + value_summary;
+                tree = view.right;
+            }
+            LocResult::GoLeft => panic!("inconsistent locator"),
+        }
+        action = view.action;
+    }
+    res
+}
+
+/// Computes the summary of the part of the segment inside `tree`, assuming that the segment
+/// continues to the left of `tree`. `action` is the total action of the tree's ancestors, and
+/// `left` and `right` are the summaries of the values to the left and right of the tree.
+fn segment_summary_on_prefix_imm<D: Data, L: Locator<D>, T>(
+    mut tree: &BasicTree<D, T>,
+    mut action: D::Action,
+    locator: &L,
+    mut left: D::Summary,
+    mut right: D::Summary,
+) -> D::Summary
+where
+    D::Value: Clone,
+{
+    let mut res: D::Summary = Default::default();
+    while let Some(node) = tree.node() {
+        let view = node.view(action);
+        let value_summary = view.value_summary();
+        let left_summary = view.left_summary();
+        let near_right_summary = view.right_summary() + right;
+        match locator.locate(left + left_summary, &view.value, near_right_summary) {
+            LocResult::Accept => {
+                res = res + left_summary + value_summary;
+                left = left + left_summary + value_summary;
+                tree = view.right;
+            }
+            LocResult::GoLeft => {
+                right = value_summary + near_right_summary;
+                tree = view.left;
+            }
+            LocResult::GoRight => panic!("inconsistent locator"),
+        }
+        action = view.action;
+    }
+    res
+}
+
 impl<D: Data> Default for BasicTree<D> {
     fn default() -> Self {
         Empty
@@ -90,6 +296,99 @@ impl<D: Data> std::iter::FromIterator<D::Value> for BasicTree<D> {
     }
 }
 
+/// Serializes the values of the tree in order, by reference, with all pending actions applied.
+/// `act_value` is only called for values whose accumulated pending action changes them.
+#[cfg(feature = "serde")]
+fn serialize_values<D: Data, T, S, F>(
+    tree: &BasicTree<D, T>,
+    serializer: S,
+    act_value: F,
+) -> Result<S::Ok, S::Error>
+where
+    D::Value: serde::Serialize,
+    S: serde::Serializer,
+    F: Fn(D::Action, &D::Value) -> D::Value,
+{
+    use serde::ser::SerializeSeq;
+    let mut seq = serializer.serialize_seq(D::segment_len(tree.subtree_summary()))?;
+    // the ancestors whose values haven't been serialized yet, with their accumulated actions.
+    // this is a loop instead of a recursion, since splay trees may be very deep.
+    let mut stack = vec![];
+    let mut tree = tree;
+    let mut action: D::Action = Default::default();
+    loop {
+        while let Some(node) = tree.node() {
+            action = action + node.action;
+            stack.push((node, action));
+            tree = if action.to_reverse() {
+                &node.right
+            } else {
+                &node.left
+            };
+        }
+        let (node, node_action) = match stack.pop() {
+            Some(entry) => entry,
+            None => break,
+        };
+        if node_action.preserves_values() {
+            seq.serialize_element(&node.node_value)?;
+        } else {
+            seq.serialize_element(&act_value(node_action, &node.node_value))?;
+        }
+        action = node_action;
+        tree = if action.to_reverse() {
+            &node.left
+        } else {
+            &node.right
+        };
+    }
+    seq.end()
+}
+
+/// Serializes the tree as the sequence of its values, in order, with all pending actions
+/// applied. Since this doesn't modify the tree, values under a pending action that changes them
+/// are cloned. Values under other pending actions, e.g. reversals, are serialized by reference.
+///
+/// If the action never changes values, [`BasicTree::serialize_ref`] doesn't require
+/// the values to be [`Clone`].
+#[cfg(feature = "serde")]
+impl<D: Data, T> serde::Serialize for BasicTree<D, T>
+where
+    D::Value: serde::Serialize + Clone,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_values(self, serializer, |action, value| action.act(value.clone()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<D: Data, T> BasicTree<D, T> {
+    /// Serializes the tree in the same format as its [`serde::Serialize`] implementation,
+    /// by reference, without requiring the values to be [`Clone`].
+    /// Can be used with `#[serde(serialize_with = "BasicTree::serialize_ref")]`.
+    pub fn serialize_ref<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        D::Value: serde::Serialize,
+        D::Action: ValuePreservingAction,
+    {
+        serialize_values(self, serializer, |_, _| {
+            unreachable!("the action should preserve the values")
+        })
+    }
+}
+
+/// Deserializes a sequence of values, and builds a balanced tree out of them in `O(n)` time.
+#[cfg(feature = "serde")]
+impl<'de, D: Data> serde::Deserialize<'de> for BasicTree<D>
+where
+    D::Value: serde::Deserialize<'de>,
+{
+    fn deserialize<De: serde::Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let values = Vec::<D::Value>::deserialize(deserializer)?;
+        Ok(values.into_iter().collect())
+    }
+}
+
 impl<D: Data> IntoIterator for BasicTree<D> {
     type Item = D::Value;
     type IntoIter = iterators::IntoIter<D, std::ops::RangeFull>;
@@ -122,6 +421,7 @@ impl<'a, D: Data, T> SomeWalker<D> for BasicWalker<'a, D, T> {
         // push side information
         if res.is_ok() {
             self.is_left.push(Side::Left); // went left
+            self.left_steps += 1;
             self.vals.push(frame);
         }
         res
@@ -152,6 +452,9 @@ impl<'a, D: Data, T> SomeWalker<D> for BasicWalker<'a, D, T> {
         match self.is_left.pop() {
             None => Err(()),
             Some(b) => {
+                if b == Side::Left {
+                    self.left_steps -= 1;
+                }
                 RecRef::pop(&mut self.rec_ref).expect(NO_VALUE_ERROR);
                 self.vals.pop().expect(NO_VALUE_ERROR);
                 self.rec_ref.rebuild();
@@ -171,6 +474,15 @@ impl<'a, D: Data, T> SomeWalker<D> for BasicWalker<'a, D, T> {
         self.vals.last().expect(NO_VALUE_ERROR).right
     }
 
+    fn touches_left_edge(&self) -> bool {
+        // the walker never went right
+        self.left_steps == self.is_left.len()
+    }
+    fn touches_right_edge(&self) -> bool {
+        // the walker never went left
+        self.left_steps == 0
+    }
+
     // fn inner(&self) -> &BasicTree<A> {
     //     &*self.rec_ref
     // }
@@ -1,10 +1,13 @@
 use crate::*;
 use basic_tree::*;
 use locators::LocResult;
+use std::borrow::Cow;
+use std::collections::VecDeque;
 
 enum Fragment<'a, D: Data, T = ()> {
     Value(&'a mut D::Value),
-    Node(&'a mut BasicNode<D, T>),
+    /// A node, with the summaries of everything to its left and everything to its right.
+    Node(&'a mut BasicNode<D, T>, D::Summary, D::Summary),
 }
 
 /// Mutable iterator iterating over a segment of the tree. Since it is a mutable
@@ -35,65 +38,115 @@ enum Fragment<'a, D: Data, T = ()> {
 ///
 /// Therefore, this type isn't exposed - it can't be used productively.
 /// Instead, this type is wrapped inside the `IterLocator` type, which is exported.
+/// For modifying values in a segment, use [`CursorMut`], which is a streaming iterator,
+/// and rebuilds the nodes as it goes.
 struct IterLocatorMut<'a, D: Data, L, T = ()> {
-    left: D::Summary,
-    // a stack of the fragments, and for every fragment,
-    // the summary of everything to its right
-    stack: Vec<(Fragment<'a, D, T>, D::Summary)>,
+    // the fragments of the rest of the segment, in order.
+    fragments: VecDeque<Fragment<'a, D, T>>,
     locator: L,
+    // the number of values in the whole segment, if the data counts values,
+    // and the number of values already returned.
+    segment_len: Option<usize>,
+    returned: usize,
 }
 
-impl<'a, D: Data, L, T> IterLocatorMut<'a, D, L, T> {
+impl<'a, D: Data, L: Locator<D>, T> IterLocatorMut<'a, D, L, T> {
     pub fn new(tree: &'a mut BasicTree<D, T>, locator: L) -> Self {
+        let segment_len = segment_len(&mut *tree, locator.clone());
         let mut res = IterLocatorMut {
-            left: Default::default(),
-            stack: vec![],
+            fragments: VecDeque::new(),
             locator,
+            segment_len,
+            returned: 0,
         };
-        res.push(tree, Default::default());
+        res.push(Side::Left, tree, Default::default(), Default::default());
         res
     }
 
-    /// Internal method: same as stack.push(...), but deals with the [`Empty`] case.
+    /// Internal method: pushes the tree to the given side of the fragments,
+    /// with the summaries to its left and to its right. But deals with the [`Empty`] case.
     /// If empty, do nothing.
-    fn push(&mut self, tree: &'a mut BasicTree<D, T>, summary: D::Summary) {
+    fn push(
+        &mut self,
+        side: Side,
+        tree: &'a mut BasicTree<D, T>,
+        left: D::Summary,
+        right: D::Summary,
+    ) {
         if let Some(node) = tree.node_mut() {
-            self.stack.push((Fragment::Node(node), summary));
+            push_fragment(&mut self.fragments, side, Fragment::Node(node, left, right));
         }
     }
 }
 
-impl<'a, D: Data, L: Locator<D>, T> Iterator for IterLocatorMut<'a, D, L, T> {
-    type Item = &'a mut D::Value;
+/// Pushes the fragment to the given side of the deque.
+fn push_fragment<F>(fragments: &mut VecDeque<F>, side: Side, fragment: F) {
+    match side {
+        Side::Left => fragments.push_front(fragment),
+        Side::Right => fragments.push_back(fragment),
+    }
+}
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        // Iterator is empty
-        if self.stack.is_empty() {
-            (0, Some(0))
-        } else {
-            // We know that every stack fragment contains at least one element.
-            // We don't know any upper bound.
-            // If we could specialize for `D: SizedData`, we could know the exact size,
-            // but we can't.
-            (self.stack.len(), None)
+/// Pops a fragment from the given side of the deque.
+fn pop_fragment<F>(fragments: &mut VecDeque<F>, side: Side) -> Option<F> {
+    match side {
+        Side::Left => fragments.pop_front(),
+        Side::Right => fragments.pop_back(),
+    }
+}
+
+/// Checks that the locator didn't send us away from the segment, after some of the segment
+/// has already been found.
+fn check_direction(dir: &LocResult, side: Side, is_middle: bool) {
+    match (dir, side) {
+        (LocResult::GoLeft, Side::Left) if is_middle => {
+            panic!("GoLeft received in the middle of a segment")
+        }
+        (LocResult::GoRight, Side::Right) if is_middle => {
+            panic!("GoRight received in the middle of a segment")
         }
+        _ => (),
     }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let (frag, summary) = match self.stack.pop() {
-                None => return None,
-                Some(x) => x,
-            };
+/// The number of values in the segment, if the data counts values.
+/// Doesn't traverse the tree otherwise.
+fn segment_len<D: Data, L: Locator<D>, T>(tree: &mut BasicTree<D, T>, locator: L) -> Option<usize> {
+    D::segment_len(Default::default())?;
+    D::segment_len(methods::segment_summary(tree, locator))
+}
 
-            let node = match frag {
-                // if value has been inserted to the stack, the locator has already been called
+/// The size hint for iterators that keep a deque of fragments.
+fn fragments_size_hint<F>(
+    fragments: &VecDeque<F>,
+    segment_len: Option<usize>,
+    returned: usize,
+) -> (usize, Option<usize>) {
+    if let Some(len) = segment_len {
+        // The summary keeps track of the size, so we know the exact size.
+        (len - returned, Some(len - returned))
+    } else if fragments.is_empty() {
+        // Iterator is empty
+        (0, Some(0))
+    } else {
+        // We know that every fragment contains at least one element.
+        // We don't know any upper bound.
+        (fragments.len(), None)
+    }
+}
+
+impl<'a, D: Data, L: Locator<D>, T> IterLocatorMut<'a, D, L, T> {
+    /// Returns the next value from the given side of the segment.
+    fn next_from(&mut self, side: Side) -> Option<&'a mut D::Value> {
+        loop {
+            let (node, left, right) = match pop_fragment(&mut self.fragments, side)? {
+                // if value has been inserted to the deque, the locator has already been called
                 // on it and returned `Accept`.
                 Fragment::Value(val) => {
-                    self.left = self.left + D::to_summary(val);
+                    self.returned += 1;
                     return Some(val);
                 }
-                Fragment::Node(node) => node,
+                Fragment::Node(node, left, right) => (node, left, right),
             };
 
             node.access();
@@ -102,34 +155,53 @@ impl<'a, D: Data, L: Locator<D>, T> Iterator for IterLocatorMut<'a, D, L, T> {
             let left_node = &mut node.left;
 
             let value_summary = D::to_summary(value);
-            let near_left_summary: D::Summary = self.left + left_node.subtree_summary();
-            let near_right_summary: D::Summary = right_node.subtree_summary() + summary;
+            let near_left_summary: D::Summary = left + left_node.subtree_summary();
+            let near_right_summary: D::Summary = right_node.subtree_summary() + right;
 
             let dir = self
                 .locator
                 .locate(near_left_summary, value, near_right_summary);
+            check_direction(&dir, side, !self.fragments.is_empty());
+            let left_context = (left, value_summary + near_right_summary);
+            let right_context = (near_left_summary + value_summary, right);
             match dir {
-                LocResult::GoLeft => {
-                    if !self.stack.is_empty() {
-                        panic!("GoLeft received in the middle of a segment");
-                    }
-                    self.push(left_node, value_summary + near_right_summary);
-                }
-                LocResult::GoRight => {
-                    self.push(right_node, summary);
-                    self.left = near_left_summary + value_summary;
-                }
+                LocResult::GoLeft => self.push(side, left_node, left_context.0, left_context.1),
+                LocResult::GoRight => self.push(side, right_node, right_context.0, right_context.1),
                 LocResult::Accept => {
-                    self.push(right_node, summary);
-                    self.stack
-                        .push((Fragment::Value(value), near_right_summary));
-                    self.push(left_node, value_summary + near_right_summary);
+                    // push the far side first, so that the fragments stay in order.
+                    let (far_node, far_context, near_node, near_context) = match side {
+                        Side::Left => (right_node, right_context, left_node, left_context),
+                        Side::Right => (left_node, left_context, right_node, right_context),
+                    };
+                    self.push(side, far_node, far_context.0, far_context.1);
+                    push_fragment(&mut self.fragments, side, Fragment::Value(value));
+                    self.push(side, near_node, near_context.0, near_context.1);
                 }
             }
         }
     }
 }
 
+impl<'a, D: Data, L: Locator<D>, T> Iterator for IterLocatorMut<'a, D, L, T> {
+    type Item = &'a mut D::Value;
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        fragments_size_hint(&self.fragments, self.segment_len, self.returned)
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_from(Side::Left)
+    }
+}
+
+impl<'a, D: Data, L: Locator<D>, T> DoubleEndedIterator for IterLocatorMut<'a, D, L, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_from(Side::Right)
+    }
+}
+
+impl<'a, D: SizedData, L: Locator<D>, T> ExactSizeIterator for IterLocatorMut<'a, D, L, T> {}
+
 /// Immutable iterator.
 /// The iterator receives a `&mut self` argument instead of a `&self` argument.
 /// Because of the way the trees work, immutable iterators can't be written without either mutable access
@@ -140,6 +212,8 @@ impl<'a, D: Data, L: Locator<D>, T> Iterator for IterLocatorMut<'a, D, L, T> {
 ///
 /// If you use interior mutability to update the values inside the tree, and these changes affect the summaries,
 /// the tree may behave incorrectly.
+///
+/// If the values are `Clone`, [`ImmIterLocator`] only requires a shared reference to the tree.
 pub struct IterLocator<'a, D: Data, L, T = ()> {
     mut_iter: IterLocatorMut<'a, D, L, T>,
 }
@@ -166,43 +240,168 @@ impl<'a, D: Data, L: Locator<D>, T> Iterator for IterLocator<'a, D, L, T> {
     }
 }
 
-/// Owning fragment
-enum OFragment<D: Data, T = ()> {
-    Value(D::Value),
-    Node(Box<BasicNode<D, T>>),
+/// Iterating in reverse order. Reversals that haven't been pushed down the tree yet are respected,
+/// so the values are returned in the reverse of their logical order.
+impl<'a, D: Data, L: Locator<D>, T> DoubleEndedIterator for IterLocator<'a, D, L, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some(&*self.mut_iter.next_back()?)
+    }
 }
-/// Owning iterator iterating over a segment of the tree.
-pub struct IntoIter<D: Data, L, T = ()> {
+
+/// When the data counts the values of segments, the number of values is known up front.
+impl<'a, D: SizedData, L: Locator<D>, T> ExactSizeIterator for IterLocator<'a, D, L, T> {}
+
+/// A mutable cursor iterating over a segment of the tree, that rebuilds the nodes as it goes.
+/// See [`SomeTree::cursor_mut`] and [`SomeTree::for_each_mut`].
+///
+/// This is a streaming (lending) iterator: [`CursorMut::next`] returns a mutable reference to
+/// the next value, which is valid until the next call. Before the cursor moves on (or is dropped),
+/// it rebuilds the node it has just given out, and it rebuilds the nodes above it when
+/// it walks up the tree. Therefore, iterating over `k` values takes `O(k + log n)` time in total.
+///
+/// The locator is consulted while iterating, so changing the values shouldn't change
+/// whether the locator accepts them. For example, keys shouldn't be changed
+/// when iterating over a [`locators::ByKey`] segment.
+pub struct CursorMut<'a, D: Data, L, T = ()> {
+    walker: BasicWalker<'a, D, T>,
+    locator: L,
+    /// Whether the iteration has started, i.e., whether the walker has already
+    /// been moved to the left edge of the segment.
+    started: bool,
+    /// Whether the iteration has ended.
+    finished: bool,
+    /// Whether the current node's value has been given out, so it has to be rebuilt.
+    dirty: bool,
+}
+
+impl<'a, D: Data, L: Locator<D>, T> CursorMut<'a, D, L, T> {
+    /// Creates a new mutable cursor for a segment of the given tree.
+    pub fn new(tree: &'a mut BasicTree<D, T>, locator: L) -> Self {
+        CursorMut {
+            walker: BasicWalker::new(tree),
+            locator,
+            started: false,
+            finished: false,
+            dirty: false,
+        }
+    }
+
+    /// Rebuilds the current node, if its value has been given out.
+    fn clean(&mut self) {
+        if self.dirty {
+            self.walker.rebuild();
+            self.dirty = false;
+        }
+    }
+
+    /// Advances the cursor, and returns a mutable reference to the next value in the segment.
+    /// Returns [`None`] when the segment ends.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<&mut D::Value> {
+        if self.finished {
+            return None;
+        }
+        self.clean();
+        if !self.started {
+            self.started = true;
+            self.walker
+                .search_subtree(locators::LeftEdgeOf(self.locator.clone()));
+        }
+        let accepted = self.walker.next_filled().is_ok()
+            && locators::walker_locate(&mut self.walker, &self.locator) == Some(LocResult::Accept);
+        if !accepted {
+            self.finished = true;
+            return None;
+        }
+        self.dirty = true;
+        Some(self.walker.node_mut()?.node_value_mut())
+    }
+
+    /// Returns a reference to the value that was last returned by [`CursorMut::next`].
+    /// Returns [`None`] if the iteration hasn't started yet, or has ended.
+    pub fn current(&self) -> Option<&D::Value> {
+        if !self.started || self.finished {
+            return None;
+        }
+        self.walker.value()
+    }
+
+    /// Returns a mutable reference to the value that was last returned by [`CursorMut::next`].
+    /// Returns [`None`] if the iteration hasn't started yet, or has ended.
+    pub fn current_mut(&mut self) -> Option<&mut D::Value> {
+        if !self.started || self.finished {
+            return None;
+        }
+        self.dirty = true;
+        Some(self.walker.node_mut()?.node_value_mut())
+    }
+}
+
+impl<'a, D: Data, L, T> Drop for CursorMut<'a, D, L, T> {
+    fn drop(&mut self) {
+        // the walker itself rebuilds the rest of the path when it's dropped.
+        if self.dirty {
+            self.walker.rebuild();
+        }
+    }
+}
+
+/// Fragment of a tree, seen through a shared reference.
+enum ImmFragment<'a, D: Data, T = ()>
+where
+    D::Value: Clone,
+{
+    Value(Cow<'a, D::Value>),
+    /// A node, and the total action of its ancestors.
+    Node(&'a BasicNode<D, T>, D::Action),
+}
+
+/// Immutable iterator iterating over a segment of the tree, that only requires a shared reference
+/// to the tree. See [`QueryableTree::iter_locator_imm`].
+///
+/// Instead of pushing the pending actions down the tree, the iterator composes them on the way down.
+/// Values that still have a pending action on them are cloned and then acted on.
+/// Values without any pending action are borrowed from the tree.
+pub struct ImmIterLocator<'a, D: Data, L, T = ()>
+where
+    D::Value: Clone,
+{
     left: D::Summary,
     // a stack of the fragments, and for every fragment,
     // the summary of everything to its right
-    stack: Vec<(OFragment<D, T>, D::Summary)>,
+    stack: Vec<(ImmFragment<'a, D, T>, D::Summary)>,
     locator: L,
 }
 
-impl<D: Data, L, T> IntoIter<D, L, T> {
-    /// Creates a new owning iterator for a segment of the given tree.
-    pub fn new(tree: BasicTree<D, T>, locator: L) -> Self {
-        let mut res = IntoIter {
+impl<'a, D: Data, L, T> ImmIterLocator<'a, D, L, T>
+where
+    D::Value: Clone,
+{
+    /// Creates a new immutable iterator for a segment of the given tree.
+    pub fn new(tree: &'a BasicTree<D, T>, locator: L) -> Self {
+        let mut res = ImmIterLocator {
             left: Default::default(),
             stack: vec![],
             locator,
         };
-        res.push(tree, Default::default());
+        res.push(tree, Default::default(), Default::default());
         res
     }
 
     /// Internal method: same as stack.push(...), but deals with the [`Empty`] case.
     /// If empty, do nothing.
-    fn push(&mut self, tree: BasicTree<D, T>, summary: D::Summary) {
-        if let Some(boxed_node) = tree.into_node_boxed() {
-            self.stack.push((OFragment::Node(boxed_node), summary));
+    fn push(&mut self, tree: &'a BasicTree<D, T>, action: D::Action, summary: D::Summary) {
+        if let Some(node) = tree.node() {
+            self.stack.push((ImmFragment::Node(node, action), summary));
         }
     }
 }
 
-impl<D: Data, L: Locator<D>, T> Iterator for IntoIter<D, L, T> {
-    type Item = D::Value;
+impl<'a, D: Data, L: Locator<D>, T> Iterator for ImmIterLocator<'a, D, L, T>
+where
+    D::Value: Clone,
+{
+    type Item = Cow<'a, D::Value>;
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         // Iterator is empty
@@ -211,59 +410,163 @@ impl<D: Data, L: Locator<D>, T> Iterator for IntoIter<D, L, T> {
         } else {
             // We know that every stack fragment contains at least one element.
             // We don't know any upper bound.
-            // If we could specialize for `D: SizedData`, we could know the exact size,
-            // but we can't.
             (self.stack.len(), None)
         }
     }
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let (frag, summary) = match self.stack.pop() {
-                None => return None,
-                Some(x) => x,
-            };
+            let (frag, summary) = self.stack.pop()?;
 
-            let mut node = match frag {
+            let (node, action) = match frag {
                 // if value has been inserted to the stack, the locator has already been called
                 // on it and returned `Accept`.
-                OFragment::Value(val) => {
+                ImmFragment::Value(val) => {
                     self.left = self.left + D::to_summary(&val);
                     return Some(val);
                 }
-                OFragment::Node(node) => node,
+                ImmFragment::Node(node, action) => (node, action),
             };
 
-            node.access();
-            let value = node.node_value;
-            let right_node = node.right;
-            let left_node = node.left;
-
-            let value_summary = D::to_summary(&value);
-            let near_left_summary: D::Summary = self.left + left_node.subtree_summary();
-            let near_right_summary: D::Summary = right_node.subtree_summary() + summary;
+            let view = node.view(action);
+            let value_summary = view.value_summary();
+            let near_left_summary: D::Summary = self.left + view.left_summary();
+            let near_right_summary: D::Summary = view.right_summary() + summary;
 
             let dir = self
                 .locator
-                .locate(near_left_summary, &value, near_right_summary);
+                .locate(near_left_summary, &view.value, near_right_summary);
             match dir {
                 LocResult::GoLeft => {
                     if !self.stack.is_empty() {
                         panic!("GoLeft received in the middle of a segment");
                     }
-                    self.push(left_node, value_summary + near_right_summary);
+                    self.push(view.left, view.action, value_summary + near_right_summary);
                 }
                 LocResult::GoRight => {
-                    self.push(right_node, summary);
+                    self.push(view.right, view.action, summary);
                     self.left = near_left_summary + value_summary;
                 }
                 LocResult::Accept => {
-                    self.push(right_node, summary);
+                    self.push(view.right, view.action, summary);
                     self.stack
-                        .push((OFragment::Value(value), near_right_summary));
-                    self.push(left_node, value_summary + near_right_summary);
+                        .push((ImmFragment::Value(view.value), near_right_summary));
+                    self.push(view.left, view.action, value_summary + near_right_summary);
+                }
+            }
+        }
+    }
+}
+
+/// Owning fragment
+enum OFragment<D: Data, T = ()> {
+    Value(D::Value),
+    /// A node, with the summaries of everything to its left and everything to its right.
+    Node(Box<BasicNode<D, T>>, D::Summary, D::Summary),
+}
+/// Owning iterator iterating over a segment of the tree.
+pub struct IntoIter<D: Data, L, T = ()> {
+    // the fragments of the rest of the segment, in order.
+    fragments: VecDeque<OFragment<D, T>>,
+    locator: L,
+    // the number of values in the whole segment, if the data counts values,
+    // and the number of values already returned.
+    segment_len: Option<usize>,
+    returned: usize,
+}
+
+impl<D: Data, L: Locator<D>, T> IntoIter<D, L, T> {
+    /// Creates a new owning iterator for a segment of the given tree.
+    pub fn new(mut tree: BasicTree<D, T>, locator: L) -> Self {
+        let segment_len = segment_len(&mut tree, locator.clone());
+        let mut res = IntoIter {
+            fragments: VecDeque::new(),
+            locator,
+            segment_len,
+            returned: 0,
+        };
+        res.push(Side::Left, tree, Default::default(), Default::default());
+        res
+    }
+
+    /// Internal method: pushes the tree to the given side of the fragments,
+    /// with the summaries to its left and to its right. But deals with the [`Empty`] case.
+    /// If empty, do nothing.
+    fn push(&mut self, side: Side, tree: BasicTree<D, T>, left: D::Summary, right: D::Summary) {
+        if let Some(boxed_node) = tree.into_node_boxed() {
+            push_fragment(
+                &mut self.fragments,
+                side,
+                OFragment::Node(boxed_node, left, right),
+            );
+        }
+    }
+
+    /// Returns the next value from the given side of the segment.
+    fn next_from(&mut self, side: Side) -> Option<D::Value> {
+        loop {
+            let (mut node, left, right) = match pop_fragment(&mut self.fragments, side)? {
+                // if value has been inserted to the deque, the locator has already been called
+                // on it and returned `Accept`.
+                OFragment::Value(val) => {
+                    self.returned += 1;
+                    return Some(val);
+                }
+                OFragment::Node(node, left, right) => (node, left, right),
+            };
+
+            node.access();
+            let value = node.node_value;
+            let right_node = node.right;
+            let left_node = node.left;
+
+            let value_summary = D::to_summary(&value);
+            let near_left_summary: D::Summary = left + left_node.subtree_summary();
+            let near_right_summary: D::Summary = right_node.subtree_summary() + right;
+
+            let dir = self
+                .locator
+                .locate(near_left_summary, &value, near_right_summary);
+            check_direction(&dir, side, !self.fragments.is_empty());
+            let left_context = (left, value_summary + near_right_summary);
+            let right_context = (near_left_summary + value_summary, right);
+            match dir {
+                LocResult::GoLeft => self.push(side, left_node, left_context.0, left_context.1),
+                LocResult::GoRight => self.push(side, right_node, right_context.0, right_context.1),
+                LocResult::Accept => {
+                    // push the far side first, so that the fragments stay in order.
+                    let (far_node, far_context, near_node, near_context) = match side {
+                        Side::Left => (right_node, right_context, left_node, left_context),
+                        Side::Right => (left_node, left_context, right_node, right_context),
+                    };
+                    self.push(side, far_node, far_context.0, far_context.1);
+                    push_fragment(&mut self.fragments, side, OFragment::Value(value));
+                    self.push(side, near_node, near_context.0, near_context.1);
                 }
             }
         }
     }
 }
+
+impl<D: Data, L: Locator<D>, T> Iterator for IntoIter<D, L, T> {
+    type Item = D::Value;
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        fragments_size_hint(&self.fragments, self.segment_len, self.returned)
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_from(Side::Left)
+    }
+}
+
+/// Iterating in reverse order. Reversals that haven't been pushed down the tree yet are respected,
+/// so the values are returned in the reverse of their logical order.
+impl<D: Data, L: Locator<D>, T> DoubleEndedIterator for IntoIter<D, L, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_from(Side::Right)
+    }
+}
+
+/// When the data counts the values of segments, the number of values is known up front.
+impl<D: SizedData, L: Locator<D>, T> ExactSizeIterator for IntoIter<D, L, T> {}
@@ -17,13 +17,22 @@ pub use walker::*;
 mod implementations;
 pub use implementations::*;
 
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "rayon")]
+pub(crate) use parallel::{par_build_balanced, SEQUENTIAL_THRESHOLD};
+
 /// Iterators for [`BasicTree`]
 pub mod iterators;
 
 mod iterative_deallocator;
 pub use iterative_deallocator::deallocate_iteratively;
 
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+
 use crate::*;
+use std::borrow::Cow;
 
 /// A basic tree. might be empty.
 /// The `T` parameter is for algorithm-specific bookeeping data.
@@ -228,6 +237,32 @@ impl<D: Data, T> BasicNode<D, T> {
         &mut self.node_value
     }
 
+    /// Returns a view of this node, as it should be after applying the node's action
+    /// and the given `action`, which is the total action of its ancestors.
+    /// This doesn't modify the node. See [`QueryableTree`].
+    pub(crate) fn view(&self, action: D::Action) -> NodeView<'_, D, T>
+    where
+        D::Value: Clone,
+    {
+        let action = action + self.action;
+        let value = if action.preserves_values() {
+            Cow::Borrowed(&self.node_value)
+        } else {
+            Cow::Owned(action.act(self.node_value.clone()))
+        };
+        let (left, right) = if action.to_reverse() {
+            (&self.right, &self.left)
+        } else {
+            (&self.left, &self.right)
+        };
+        NodeView {
+            left,
+            value,
+            right,
+            action,
+        }
+    }
+
     /// Returns the value stored in this node specifically.
     /// Assumes that the node has been accessed. Panics otherwise.
     pub(crate) fn node_value_clean(&self) -> &D::Value {
@@ -332,10 +367,82 @@ impl<D: Data, T> BasicNode<D, T> {
     where
         D::Summary: Eq,
     {
-        let ns = self.subtree_summary;
+        assert!(
+            self.summaries_correct_locally(),
+            "Incorrect summaries found."
+        );
+    }
+
+    /// Returns `true` if the summaries were calculated correctly at the current node.
+    pub(crate) fn summaries_correct_locally(&self) -> bool
+    where
+        D::Summary: Eq,
+    {
         let os: D::Summary = self.left.subtree_summary()
             + D::to_summary(&self.node_value)
             + self.right.subtree_summary();
-        assert!(ns == os, "Incorrect summaries found.");
+        self.subtree_summary == os
+    }
+}
+
+/// Builds a tree of minimal depth out of `count` nodes with no sons, in the same order.
+///
+/// `rebuild_alg_data` is called on every node after its sons have been built,
+/// in order to compute the node's algorithm-specific data.
+pub(crate) fn build_balanced<D: Data, T, I>(
+    nodes: &mut I,
+    count: usize,
+    rebuild_alg_data: fn(&mut BasicNode<D, T>),
+) -> BasicTree<D, T>
+where
+    I: Iterator<Item = Box<BasicNode<D, T>>>,
+{
+    if count == 0 {
+        return BasicTree::Empty;
+    }
+    let left_count = count / 2;
+    let left = build_balanced(nodes, left_count, rebuild_alg_data);
+    let mut node = nodes.next().unwrap();
+    node.left = left;
+    node.right = build_balanced(nodes, count - left_count - 1, rebuild_alg_data);
+    node.rebuild();
+    rebuild_alg_data(&mut node);
+    BasicTree::Root(node)
+}
+
+/// A node as it should be after applying the pending actions above it,
+/// computed without modifying the tree. Used for the immutable queries of [`QueryableTree`].
+pub(crate) struct NodeView<'a, D: Data, T>
+where
+    D::Value: Clone,
+{
+    /// The left son, in the order after the actions are applied.
+    pub(crate) left: &'a BasicTree<D, T>,
+    /// The value, after the actions are applied.
+    /// It is only cloned if there is an action that actually applies to it.
+    pub(crate) value: Cow<'a, D::Value>,
+    /// The right son, in the order after the actions are applied.
+    pub(crate) right: &'a BasicTree<D, T>,
+    /// The total action that still applies to the sons' subtrees.
+    pub(crate) action: D::Action,
+}
+
+impl<'a, D: Data, T> NodeView<'a, D, T>
+where
+    D::Value: Clone,
+{
+    /// Returns the summary of the left son's subtree.
+    pub(crate) fn left_summary(&self) -> D::Summary {
+        self.action.act(self.left.subtree_summary())
+    }
+
+    /// Returns the summary of the right son's subtree.
+    pub(crate) fn right_summary(&self) -> D::Summary {
+        self.action.act(self.right.subtree_summary())
+    }
+
+    /// Returns the summary of the value.
+    pub(crate) fn value_summary(&self) -> D::Summary {
+        D::to_summary(self.value.as_ref())
     }
 }
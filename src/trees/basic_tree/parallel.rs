@@ -0,0 +1,77 @@
+//! This module implements parallel construction of trees, using [`rayon`].
+//! It is a separate file from the main module file, since it's a private module, and its
+//! contents are re-exported.
+
+use super::*;
+use rayon::prelude::*;
+
+/// Below this amount of values, trees are built sequentially.
+pub(crate) const SEQUENTIAL_THRESHOLD: usize = 1 << 10;
+
+/// Builds a tree of minimal depth from the values, in parallel, and in the same order.
+/// Takes the values out of the slice, which should contain only [`Some`] values.
+/// Small trees are built sequentially by [`build_balanced`].
+///
+/// `rebuild_alg_data` is called on every node after its sons have been built,
+/// in order to compute the node's algorithm-specific data.
+pub(crate) fn par_build_balanced<D: Data, T: Default + Send>(
+    values: &mut [Option<D::Value>],
+    rebuild_alg_data: fn(&mut BasicNode<D, T>),
+) -> BasicTree<D, T>
+where
+    D::Value: Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    let len = values.len();
+    if len < SEQUENTIAL_THRESHOLD {
+        let mut nodes = values.iter_mut().map(|value| {
+            Box::new(BasicNode::new_alg(
+                value.take().unwrap(),
+                Default::default(),
+            ))
+        });
+        return build_balanced(&mut nodes, len, rebuild_alg_data);
+    }
+    let (left_values, rest) = values.split_at_mut(len / 2);
+    let (value, right_values) = rest.split_first_mut().unwrap();
+
+    let (left, right) = rayon::join(
+        || par_build_balanced(left_values, rebuild_alg_data),
+        || par_build_balanced(right_values, rebuild_alg_data),
+    );
+
+    let mut node = BasicNode::new_alg(value.take().unwrap(), Default::default());
+    node.left = left;
+    node.right = right;
+    node.rebuild();
+    rebuild_alg_data(&mut node);
+    BasicTree::from_node(node)
+}
+
+impl<D: Data> FromParallelIterator<D::Value> for BasicTree<D>
+where
+    D::Value: Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    /// Builds a tree of minimal depth from the values, in the same order as the iterator.
+    /// The values are first collected, and then the tree is built in parallel.
+    ///```
+    /// use grove::{SomeTree, basic_tree::BasicTree};
+    /// use grove::example_data::StdNum;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut tree: BasicTree<StdNum> = (0..10_000).into_par_iter().collect();
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (0..10_000).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = D::Value>,
+    {
+        let mut values: Vec<_> = par_iter.into_par_iter().map(Some).collect();
+        par_build_balanced(&mut values, |_| ())
+    }
+}
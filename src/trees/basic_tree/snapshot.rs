@@ -0,0 +1,297 @@
+//! Binary snapshots that preserve the exact shape of a tree.
+//!
+//! Unlike serializing a tree as its sequence of values, a snapshot stores every node as it is:
+//! its value, its pending action, its cached subtree summary and its algorithm-specific data
+//! (e.g., AVL ranks or treap priorities). Loading a snapshot takes `O(n)` time, and restores
+//! the exact same tree, without rebalancing it or drawing new priorities.
+//!
+//! Since snapshots may come from untrusted sources, loading a snapshot recomputes the summaries
+//! of all nodes and checks the balancing invariants of the tree, and returns a
+//! [`SnapshotError`] if anything is wrong.
+//!
+//! The format is a header, containing a magic number, a format version, the kind of the tree and
+//! the number of nodes, followed by the nodes in post-order, encoded with [`bincode`].
+//! Every node record holds a byte that says which of its sons are present, followed by its
+//! value, its action, its subtree summary and its algorithm-specific data.
+//!
+//!```
+//! use grove::{*, treap::Treap};
+//! use grove::example_data::{StdNum, RevAffineAction};
+//!
+//! let mut tree: Treap<StdNum> = (1..=20).collect();
+//! tree.act_segment(RevAffineAction {to_reverse: true, mul: -1, add: 0}, 5..15);
+//!
+//! let bytes = tree.to_snapshot();
+//! let mut copy = Treap::<StdNum>::from_snapshot(&bytes).unwrap();
+//! assert_eq!(copy.to_snapshot(), bytes);
+//! assert!(copy.iter().eq(tree.iter()));
+//!
+//! assert!(Treap::<StdNum>::from_snapshot(&bytes[..bytes.len() - 1]).is_err());
+//!```
+
+use super::*;
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+
+const MAGIC: [u8; 4] = *b"GRVS";
+const VERSION: u16 = 1;
+
+const HAS_LEFT: u8 = 1;
+const HAS_RIGHT: u8 = 2;
+
+/// The kinds of trees that snapshots can be taken of.
+/// Stored in the snapshot, so that a snapshot of one kind of tree isn't loaded as another.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TreeKind {
+    /// A [`BasicTree`].
+    Basic,
+    /// A [`crate::splay::SplayTree`].
+    Splay,
+    /// A [`crate::treap::Treap`].
+    Treap,
+    /// An [`crate::avl::AVLTree`].
+    AVL,
+}
+
+impl TreeKind {
+    fn tag(self) -> u8 {
+        match self {
+            TreeKind::Basic => 0,
+            TreeKind::Splay => 1,
+            TreeKind::Treap => 2,
+            TreeKind::AVL => 3,
+        }
+    }
+}
+
+/// The errors that can occur while loading a snapshot.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The snapshot doesn't start with the snapshot magic number.
+    BadMagic,
+    /// The snapshot was written in an unsupported version of the format.
+    UnsupportedVersion(u16),
+    /// The snapshot was taken of a different kind of tree.
+    WrongTreeKind {
+        /// The kind of tree that was being loaded.
+        expected: TreeKind,
+        /// The tag of the kind of tree stored in the snapshot.
+        found: u8,
+    },
+    /// The bytes couldn't be decoded, e.g., because the snapshot was truncated.
+    Decode(bincode::Error),
+    /// The node records don't form a single tree.
+    MalformedShape,
+    /// There are extra bytes after the last node.
+    TrailingBytes,
+    /// The cached summary of a node is incorrect.
+    /// Nodes are numbered by their order in the snapshot, i.e., in post-order.
+    IncorrectSummary {
+        /// The number of the node.
+        node: usize,
+    },
+    /// The algorithm-specific data of a node violates the tree's balancing invariants.
+    /// Nodes are numbered by their order in the snapshot, i.e., in post-order.
+    InvalidAlgData {
+        /// The number of the node.
+        node: usize,
+    },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "not a tree snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::WrongTreeKind { expected, found } => write!(
+                f,
+                "expected a snapshot of a {:?} tree, found tree kind {}",
+                expected, found
+            ),
+            SnapshotError::Decode(error) => write!(f, "failed decoding snapshot: {}", error),
+            SnapshotError::MalformedShape => write!(f, "the snapshot's nodes don't form a tree"),
+            SnapshotError::TrailingBytes => write!(f, "trailing bytes after the snapshot"),
+            SnapshotError::IncorrectSummary { node } => {
+                write!(f, "incorrect summary at node {}", node)
+            }
+            SnapshotError::InvalidAlgData { node } => {
+                write!(f, "invalid balancing data at node {}", node)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::Decode(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(error: bincode::Error) -> Self {
+        SnapshotError::Decode(error)
+    }
+}
+
+/// Writes a snapshot of the tree.
+/// Doesn't modify the tree: pending actions are stored as they are.
+pub fn write<D: Data, T>(tree: &BasicTree<D, T>, kind: TreeKind) -> Vec<u8>
+where
+    D::Value: Serialize,
+    D::Summary: Serialize,
+    D::Action: Serialize,
+    T: Serialize,
+{
+    let options = bincode::DefaultOptions::new();
+    let mut nodes = vec![];
+    // an iterative post-order traversal, since the tree might be deep.
+    // the flag marks whether the node's sons were already pushed.
+    let mut stack: Vec<(&BasicNode<D, T>, bool)> =
+        tree.node().into_iter().map(|node| (node, false)).collect();
+    while let Some((node, expanded)) = stack.pop() {
+        if expanded {
+            nodes.push(node);
+            continue;
+        }
+        stack.push((node, true));
+        if let Some(right) = node.right.node() {
+            stack.push((right, false));
+        }
+        if let Some(left) = node.left.node() {
+            stack.push((left, false));
+        }
+    }
+
+    let mut bytes = vec![];
+    let header = (MAGIC, VERSION, kind.tag(), nodes.len() as u64);
+    options.serialize_into(&mut bytes, &header).unwrap();
+    for node in nodes {
+        let mut sons = 0;
+        if !node.left.is_empty() {
+            sons |= HAS_LEFT;
+        }
+        if !node.right.is_empty() {
+            sons |= HAS_RIGHT;
+        }
+        let record = (
+            sons,
+            &node.node_value,
+            &node.action,
+            &node.subtree_summary,
+            &node.alg_data,
+        );
+        options.serialize_into(&mut bytes, &record).unwrap();
+    }
+    bytes
+}
+
+/// Loads a snapshot of a tree, that was written by [`write()`].
+/// Checks the summaries of all nodes, and checks their algorithm-specific data using
+/// `alg_data_correct`, which should return whether the balancing invariants hold at a node,
+/// given that they hold at its sons.
+pub fn read<D: Data, T, F>(
+    mut bytes: &[u8],
+    kind: TreeKind,
+    alg_data_correct: F,
+) -> Result<BasicTree<D, T>, SnapshotError>
+where
+    D::Value: DeserializeOwned,
+    D::Summary: DeserializeOwned + Eq,
+    D::Action: DeserializeOwned,
+    T: DeserializeOwned,
+    F: Fn(&BasicNode<D, T>) -> bool,
+{
+    // limit the size of every allocation, so that corrupted lengths can't exhaust the memory.
+    let options = bincode::DefaultOptions::new().with_limit(bytes.len() as u64);
+    let (magic, version, tag, count): ([u8; 4], u16, u8, u64) =
+        options.deserialize_from(&mut bytes)?;
+    if magic != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    if tag != kind.tag() {
+        return Err(SnapshotError::WrongTreeKind {
+            expected: kind,
+            found: tag,
+        });
+    }
+
+    // the subtrees that were already built, and don't have a father yet.
+    let mut stack: Vec<BasicTree<D, T>> = vec![];
+    for index in 0..count as usize {
+        let (sons, node_value, action, subtree_summary, alg_data): (
+            u8,
+            D::Value,
+            D::Action,
+            D::Summary,
+            T,
+        ) = options.deserialize_from(&mut bytes)?;
+        if sons & !(HAS_LEFT | HAS_RIGHT) != 0 {
+            return Err(SnapshotError::MalformedShape);
+        }
+        let mut pop_son = |flag| {
+            if sons & flag == 0 {
+                Ok(BasicTree::Empty)
+            } else {
+                stack.pop().ok_or(SnapshotError::MalformedShape)
+            }
+        };
+        let right = pop_son(HAS_RIGHT)?;
+        let left = pop_son(HAS_LEFT)?;
+        let node = BasicNode {
+            action,
+            subtree_summary,
+            node_value,
+            left,
+            right,
+            alg_data,
+        };
+        if !node.summaries_correct_locally() {
+            return Err(SnapshotError::IncorrectSummary { node: index });
+        }
+        if !alg_data_correct(&node) {
+            return Err(SnapshotError::InvalidAlgData { node: index });
+        }
+        stack.push(BasicTree::from_node(node));
+    }
+
+    if !bytes.is_empty() {
+        return Err(SnapshotError::TrailingBytes);
+    }
+    let tree = stack.pop().unwrap_or(BasicTree::Empty);
+    if !stack.is_empty() {
+        return Err(SnapshotError::MalformedShape);
+    }
+    Ok(tree)
+}
+
+impl<D: Data> BasicTree<D> {
+    /// Writes a snapshot of the tree, that preserves its exact shape.
+    /// See the [module documentation](self).
+    pub fn to_snapshot(&self) -> Vec<u8>
+    where
+        D::Value: Serialize,
+        D::Summary: Serialize,
+        D::Action: Serialize,
+    {
+        write(self, TreeKind::Basic)
+    }
+
+    /// Loads a snapshot written by [`BasicTree::to_snapshot`], in `O(n)` time.
+    /// See the [module documentation](self).
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError>
+    where
+        D::Value: DeserializeOwned,
+        D::Summary: DeserializeOwned + Eq,
+        D::Action: DeserializeOwned,
+    {
+        read(bytes, TreeKind::Basic, |_| true)
+    }
+}
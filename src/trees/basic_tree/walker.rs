@@ -66,6 +66,10 @@ pub struct BasicWalker<'a, D: Data, T = ()> {
     /// This array is always one shorter than [`BasicWalker::rec_ref`] and [`BasicWalker::vals`],
     /// because the last node has no son in the walker.
     pub(super) is_left: Vec<Side>,
+
+    /// The number of times [`Side::Left`] appears in [`BasicWalker::is_left`].
+    /// Used for checking in `O(1)` time whether the current subtree touches the edges of the tree.
+    pub(super) left_steps: usize,
 }
 
 impl<'a, D: Data, T> BasicWalker<'a, D, T> {
@@ -76,6 +80,7 @@ impl<'a, D: Data, T> BasicWalker<'a, D, T> {
             rec_ref: RecRef::new(tree),
             vals: vec![Frame::empty()],
             is_left: vec![],
+            left_steps: 0,
         }
     }
 
@@ -95,6 +100,7 @@ impl<'a, D: Data, T> BasicWalker<'a, D, T> {
                 right: right_summary,
             }],
             is_left: vec![],
+            left_steps: 0,
         }
     }
 
@@ -271,7 +277,7 @@ impl<'a, D: Data, T> BasicWalker<'a, D, T> {
     pub fn root_into_ref(mut self) -> &'a mut BasicTree<D, T> {
         // go to the root
         self.go_to_root();
-        let (tel, _, _) = self.destructure();
+        let (tel, _, _, _) = self.destructure();
         RecRef::into_ref(tel)
     }
 
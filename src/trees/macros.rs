@@ -34,7 +34,15 @@ macro_rules! derive_SomeWalker {
             }
         
             fn far_right_summary(&self) -> $data::Summary {
-                self.$accessor.far_left_summary()
+                self.$accessor.far_right_summary()
+            }
+
+            fn touches_left_edge(&self) -> bool {
+                self.$accessor.touches_left_edge()
+            }
+
+            fn touches_right_edge(&self) -> bool {
+                self.$accessor.touches_right_edge()
             }
         
             fn value(&self) -> Option<& $data::Value> {
@@ -12,8 +12,6 @@
 use crate::*;
 use locators::*;
 
-// TODO: finger searching.
-
 /// Returns the accumulated values on the locator's segment
 /// Do not use with splay trees - it might mess up the complexity,
 /// because it uses go_up().
@@ -178,3 +176,375 @@ where
         }
     }
 }
+
+/// The parts of a node that [`visit_segments`] reports as being inside a segment.
+enum SegmentPart {
+    LeftSubtree,
+    Node,
+    RightSubtree,
+}
+
+/// Goes over several sorted, disjoint segments in one shared traversal, and reports
+/// every part of the tree inside a segment, in order, together with that segment's index.
+/// Subtrees that are entirely inside a segment are reported without descending into them.
+///
+/// The walker should be at the root of the subtree that contains the segments of `locators`.
+/// `open_left` says whether the first segment continues to the left of this subtree, and
+/// `open_right` says whether the last segment continues to the right of this subtree.
+/// The walker ends up at the same position it started from.
+fn visit_segments<W, L, D: Data, F>(
+    walker: &mut W,
+    locators: &[L],
+    first_index: usize,
+    open_left: bool,
+    open_right: bool,
+    f: &mut F,
+) where
+    W: SomeWalker<D>,
+    L: Locator<D>,
+    F: FnMut(&mut W, SegmentPart, usize),
+{
+    use LocResult::*;
+
+    if locators.is_empty() || walker.is_empty() {
+        return;
+    }
+    let left = walker.left_summary();
+    let right = walker.right_summary();
+    let value = walker.value().unwrap();
+    // the segments are sorted, so the segments to the left of this node come first.
+    let start = locators.partition_point(|locator| locator.locate(left, value, right) == GoLeft);
+    let accepted = start < locators.len() && locators[start].locate(left, value, right) == Accept;
+    let end = if accepted { start + 1 } else { start };
+    if end < locators.len() && locators[end].locate(left, value, right) != GoRight {
+        panic!("locators aren't sorted and disjoint");
+    }
+
+    if accepted && start == 0 && open_left {
+        // the left subtree is entirely inside the accepted segment
+        f(walker, SegmentPart::LeftSubtree, first_index);
+    } else if end > 0 {
+        walker.go_left().unwrap();
+        visit_segments(
+            walker,
+            &locators[..end],
+            first_index,
+            open_left,
+            accepted,
+            f,
+        );
+        walker.go_up().unwrap();
+    }
+
+    if accepted {
+        f(walker, SegmentPart::Node, first_index + start);
+    }
+
+    if accepted && end == locators.len() && open_right {
+        // the right subtree is entirely inside the accepted segment
+        f(walker, SegmentPart::RightSubtree, first_index + start);
+    } else if start < locators.len() {
+        walker.go_right().unwrap();
+        visit_segments(
+            walker,
+            &locators[start..],
+            first_index + start,
+            accepted,
+            open_right,
+            f,
+        );
+        walker.go_up().unwrap();
+    }
+}
+
+/// Returns the accumulated values on each of the locators' segments.
+/// The segments must be sorted from left to right, and disjoint.
+/// Panics if they aren't (not all such cases are detected).
+///
+/// This does one shared traversal of the tree, so for `k` segments it takes
+/// `O(k*log(n/k))` time for balanced trees, instead of `O(k*log(n))` for `k` separate queries.
+/// Do not use with splay trees - it might mess up the complexity,
+/// because it uses go_up().
+///
+/// Instead, use the specific [`SomeTree::segment_summaries`]
+pub fn segment_summaries<TR, L, D: Data>(tree: TR, locators: &[L]) -> Vec<D::Summary>
+where
+    TR: SomeTreeRef<D>,
+    L: Locator<D>,
+{
+    let mut res: Vec<D::Summary> = vec![Default::default(); locators.len()];
+    let mut walker = tree.walker();
+    visit_segments(
+        &mut walker,
+        locators,
+        0,
+        false,
+        false,
+        &mut |walker: &mut TR::Walker, part, index| {
+            let summary = match part {
+                SegmentPart::LeftSubtree => walker.left_subtree_summary().unwrap(),
+                SegmentPart::Node => walker.node_summary(),
+                SegmentPart::RightSubtree => walker.right_subtree_summary().unwrap(),
+            };
+            res[index] = res[index] + summary;
+        },
+    );
+    res
+}
+
+/// Applies an action on each of the locators' segments.
+/// The segments must be sorted from left to right, and disjoint.
+/// Panics if they aren't (not all such cases are detected).
+///
+/// This does one shared traversal of the tree, so for `k` segments it takes
+/// `O(k*log(n/k))` time for balanced trees, instead of `O(k*log(n))` for `k` separate actions.
+/// Do not use with splay trees - it might mess up the complexity,
+/// because it uses go_up().
+///
+/// Don't use with actions that reverse segments. Panics otherwise.
+///
+/// Instead, use [`SomeTree::act_segments`]
+pub fn act_segments<TR, L, D: Data>(tree: TR, action: D::Action, locators: &[L])
+where
+    TR: SomeTreeRef<D>,
+    L: Locator<D>,
+{
+    assert!(!action.to_reverse());
+    let mut walker = tree.walker();
+    visit_segments(
+        &mut walker,
+        locators,
+        0,
+        false,
+        false,
+        &mut |walker: &mut TR::Walker, part, _| match part {
+            SegmentPart::LeftSubtree => walker.act_left_subtree(action).unwrap(),
+            SegmentPart::Node => walker.act_node(action).unwrap(),
+            SegmentPart::RightSubtree => walker.act_right_subtree(action).unwrap(),
+        },
+    );
+}
+
+/// Splits out the values of the tree whose keys are smaller than `key`,
+/// and returns them. The rest of the values stay in the tree.
+fn split_off_smaller<T, D: Data>(tree: &mut T, key: &<D::Value as Keyed>::Key) -> T
+where
+    T: SomeTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let right = tree
+        .slice(LeftEdgeOf(ByKey((key,))))
+        .split_right()
+        .unwrap();
+    std::mem::replace(tree, right)
+}
+
+/// Splits out the values of the tree whose keys are smaller than or equal to `key`,
+/// and returns them. The rest of the values stay in the tree.
+fn split_off_smaller_or_equal<T, D: Data>(tree: &mut T, key: &<D::Value as Keyed>::Key) -> T
+where
+    T: SomeTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let right = tree
+        .slice(RightEdgeOf(ByKey((key,))))
+        .split_right()
+        .unwrap();
+    std::mem::replace(tree, right)
+}
+
+/// Computes the union of two trees, ordered by keys, by going over the values of `tree2` in order,
+/// and splitting `tree1` at each of them.
+/// Values with equal keys are all kept, and the values from `tree2` are placed after
+/// the values of `tree1` with the same key.
+///
+/// This works for any tree that can be split and concatenated. However, trees that can be
+/// taken apart at their roots have faster specific implementations.
+/// See [`SetOperations`].
+///
+///```
+/// use grove::{SomeTree, methods, splay::SplayTree};
+/// use grove::example_data::{NoAction, Ordered};
+///
+/// type T = SplayTree<NoAction<Ordered<i32>>>;
+/// let tree1: T = (0..7).map(|x| Ordered(x)).collect();
+/// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+/// let tree = methods::union(tree1, tree2);
+/// # tree.assert_correctness();
+/// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3,4,4,5,5,6,6,7,8].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+///
+/// # Complexity
+/// If the sizes of the trees are `n,k`, this takes `O(k)` splits and concatenations,
+/// i.e., `O(k*log(n+k))` time for balanced trees.
+/// For splay trees, because of the dynamic finger property, this takes `O(k*log(1+n/k))`
+/// amortized time.
+pub fn union<T, D: Data>(tree1: T, tree2: T) -> T
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    union_ordered(tree1, tree2, true)
+}
+
+/// Computes the union of two trees, ordered by keys, by going over the values of `tree2` in order,
+/// and splitting `tree1` at each of them. Therefore, `tree2` should be the smaller tree.
+/// Values with equal keys are all kept, and the values from `tree2` are placed after
+/// the values of `tree1` with the same key if `tree1_first` is true, and before them otherwise.
+pub(super) fn union_ordered<T, D: Data>(mut tree1: T, tree2: T, tree1_first: bool) -> T
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let mut res = T::default();
+    for value in tree2 {
+        let left = if tree1_first {
+            split_off_smaller_or_equal(&mut tree1, value.get_key())
+        } else {
+            split_off_smaller(&mut tree1, value.get_key())
+        };
+        res.concatenate_right(left);
+        res.concatenate_right(std::iter::once(value).collect());
+    }
+    res.concatenate_right(tree1);
+    res
+}
+
+/// Returns whether the tree contains a value with the given key, assuming that it has no
+/// values with smaller keys. Works by splitting and concatenating the tree.
+fn starts_with_key<T, D: Data>(tree: &mut T, key: &<D::Value as Keyed>::Key) -> bool
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let mut equal = split_off_smaller_or_equal(tree, key);
+    let res = !equal.is_empty();
+    equal.concatenate_right(std::mem::take(tree));
+    *tree = equal;
+    res
+}
+
+/// Computes the intersection of two trees, ordered by keys: keeps only the values of `tree1`
+/// whose keys appear in `tree2`. Values with equal keys in `tree1` are all kept.
+/// Works by going over the values of `tree2` in order, and splitting `tree1` at each of them.
+/// See [`union`].
+///
+///```
+/// use grove::{SomeTree, methods, splay::SplayTree};
+/// use grove::example_data::{NoAction, Ordered};
+///
+/// type T = SplayTree<NoAction<Ordered<i32>>>;
+/// let tree1: T = (0..7).map(|x| Ordered(x)).collect();
+/// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+/// let tree = methods::intersection(tree1, tree2);
+/// # tree.assert_correctness();
+/// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+///
+/// # Complexity
+/// The same as [`union`].
+pub fn intersection<T, D: Data>(mut tree1: T, tree2: T) -> T
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let mut res = T::default();
+    for value in tree2 {
+        if tree1.is_empty() {
+            break;
+        }
+        // discard the values with smaller keys
+        split_off_smaller(&mut tree1, value.get_key());
+        let equal = split_off_smaller_or_equal(&mut tree1, value.get_key());
+        res.concatenate_right(equal);
+    }
+    res
+}
+
+/// The same as [`intersection`], but goes over the values of `tree1` in order,
+/// and splits `tree2` at each of them. Therefore, `tree1` should be the smaller tree.
+pub(super) fn intersection_by_first<T, D: Data>(tree1: T, mut tree2: T) -> T
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let mut res = T::default();
+    for value in tree1 {
+        if tree2.is_empty() {
+            break;
+        }
+        // discard the values with smaller keys
+        split_off_smaller(&mut tree2, value.get_key());
+        // the values with equal keys stay, for the next values of `tree1` with the same key.
+        if starts_with_key(&mut tree2, value.get_key()) {
+            res.concatenate_right(std::iter::once(value).collect());
+        }
+    }
+    res
+}
+
+/// Computes the difference of two trees, ordered by keys: keeps only the values of `tree1`
+/// whose keys don't appear in `tree2`.
+/// Works by going over the values of `tree2` in order, and splitting `tree1` at each of them.
+/// See [`union`].
+///
+///```
+/// use grove::{SomeTree, methods, splay::SplayTree};
+/// use grove::example_data::{NoAction, Ordered};
+///
+/// type T = SplayTree<NoAction<Ordered<i32>>>;
+/// let tree1: T = (0..7).map(|x| Ordered(x)).collect();
+/// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+/// let tree = methods::difference(tree1, tree2);
+/// # tree.assert_correctness();
+/// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+///
+/// # Complexity
+/// The same as [`union`].
+pub fn difference<T, D: Data>(mut tree1: T, tree2: T) -> T
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let mut res = T::default();
+    for value in tree2 {
+        if tree1.is_empty() {
+            break;
+        }
+        let smaller = split_off_smaller(&mut tree1, value.get_key());
+        res.concatenate_right(smaller);
+        // discard the values with equal keys
+        split_off_smaller_or_equal(&mut tree1, value.get_key());
+    }
+    res.concatenate_right(tree1);
+    res
+}
+
+/// The same as [`difference`], but goes over the values of `tree1` in order,
+/// and splits `tree2` at each of them. Therefore, `tree1` should be the smaller tree.
+pub(super) fn difference_by_first<T, D: Data>(tree1: T, mut tree2: T) -> T
+where
+    T: ConcatenableTree<D>,
+    for<'a> &'a mut T: SplittableTreeRef<D, T = T>,
+    D::Value: Keyed,
+{
+    let mut res = T::default();
+    for value in tree1 {
+        // discard the values with smaller keys
+        split_off_smaller(&mut tree2, value.get_key());
+        // the values with equal keys stay, for the next values of `tree1` with the same key.
+        if !starts_with_key(&mut tree2, value.get_key()) {
+            res.concatenate_right(std::iter::once(value).collect());
+        }
+    }
+    res
+}
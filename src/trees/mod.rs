@@ -12,9 +12,13 @@
 pub mod avl;
 pub mod basic_tree;
 pub mod methods;
+pub mod persistent_treap;
+pub mod red_black;
+pub mod scapegoat;
 pub mod slice;
 pub mod splay;
 pub mod treap;
+pub mod weight_balanced;
 
 
 use crate::data::*;
@@ -47,8 +51,10 @@ pub trait SomeTree<D: Data>:
 where
     for<'a> &'a mut Self: SomeTreeRef<D>,
 {
-    // TODO: make immutable
     /// Compute the summary of a subsegment.
+    ///
+    /// This may restructure the tree, so it requires `&mut self`. In order to query
+    /// through a shared reference, see [`QueryableTree::segment_summary_imm`].
     fn segment_summary<L>(&mut self, locator: L) -> D::Summary
     where
         L: locators::Locator<D>;
@@ -58,6 +64,247 @@ where
     where
         L: locators::Locator<D>;
 
+    /// Compute the summaries of several subsegments.
+    /// The segments must be sorted from left to right, and disjoint.
+    ///
+    /// For most trees, this does one shared traversal of the tree, so for `k` segments
+    /// it takes `O(k*log(n/k))` time, instead of `k` separate queries from the root.
+    /// See [`methods::segment_summaries`].
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: AVLTree<StdNum> = (0..100).collect();
+    /// let buckets: Vec<_> = (0..10).map(|i| i * 10..i * 10 + 10).collect();
+    /// let sums: Vec<i32> = tree.segment_summaries(&buckets).iter().map(|s| s.sum).collect();
+    ///
+    /// assert_eq!(sums, (0..10).map(|i| 100 * i + 45).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn segment_summaries<L>(&mut self, locators: &[L]) -> Vec<D::Summary>
+    where
+        L: locators::Locator<D>,
+    {
+        methods::segment_summaries(self, locators)
+    }
+
+    /// Apply an action on several subsegments.
+    /// The segments must be sorted from left to right, and disjoint.
+    ///
+    /// For most trees, this does one shared traversal of the tree, so for `k` segments
+    /// it takes `O(k*log(n/k))` time, instead of `k` separate actions from the root.
+    /// Actions that reverse segments are applied to each segment separately.
+    /// See [`methods::act_segments`].
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::{StdNum, RevAffineAction};
+    ///
+    /// let mut tree: AVLTree<StdNum> = (0..10).collect();
+    /// tree.act_segments(RevAffineAction {to_reverse: false, mul: -1, add: 0}, &[1..3, 5..6, 8..10]);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![0, -1, -2, 3, 4, -5, 6, 7, -8, -9]);
+    /// # tree.assert_correctness();
+    ///```
+    fn act_segments<L>(&mut self, action: D::Action, locators: &[L])
+    where
+        L: locators::Locator<D>,
+    {
+        if action.to_reverse() {
+            for locator in locators {
+                self.act_segment(action, locator.clone());
+            }
+        } else {
+            methods::act_segments(self, action, locators);
+        }
+    }
+
+    /// Returns the number of values in the tree.
+    /// Available when the summaries keep track of the size of segments.
+    ///
+    /// The size of a [`SizedSummary`] doesn't have to count values, so iterators only report
+    /// exact sizes and implement [`ExactSizeIterator`] if the data opts in, by implementing
+    /// [`Data::segment_len`] and [`crate::data::SizedData`].
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: Treap<StdNum> = (20..80).collect();
+    /// assert_eq!(tree.len(), 60);
+    /// tree.slice(3..3).insert(7);
+    /// assert_eq!(tree.len(), 61);
+    /// assert_eq!(tree.iter_locator(10..20).len(), 10);
+    ///```
+    fn len(&self) -> usize
+    where
+        D::Summary: SizedSummary,
+    {
+        self.subtree_summary().size()
+    }
+
+    /// Binary search on the prefixes of the tree, like [`slice::partition_point`].
+    /// The predicate should be monotone: it should be true for the summaries of all
+    /// prefixes up to some length, and false for all longer prefixes.
+    ///
+    /// Returns the index of the first value whose prefix (including itself) doesn't satisfy
+    /// the predicate, or the length of the tree if there isn't any.
+    /// In order to get a walker at that value, use `tree.search(PrefixWhere(|s| !f(s)))`,
+    /// see [`locators::PrefixWhere`].
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::{StdNum, NumSummary};
+    ///
+    /// let mut tree: Treap<StdNum> = vec![3, 1, 4, 1, 5, 9, 2, 6].into_iter().collect();
+    ///
+    /// // the first index where the prefix sum exceeds 10
+    /// assert_eq!(tree.partition_point(|s: NumSummary| s.sum <= 10), 4);
+    /// // the first index where the running maximum is at least 6
+    /// assert_eq!(tree.partition_point(|s: NumSummary| s.max < Some(6)), 5);
+    /// assert_eq!(tree.partition_point(|_| true), 8);
+    ///```
+    fn partition_point<F>(&mut self, f: F) -> usize
+    where
+        F: Fn(D::Summary) -> bool + Clone,
+        D::Summary: SizedSummary,
+    {
+        let walker = self.search(locators::PrefixWhere(move |summary| !f(summary)));
+        walker.left_summary().size()
+    }
+
+    /// Returns the value at the given index, or [`None`] if the index is out of bounds.
+    ///```
+    /// use grove::{SomeTree, splay::SplayTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: SplayTree<StdNum> = (20..80).collect();
+    ///
+    /// assert_eq!(tree.select(13), Some(&33));
+    /// assert_eq!(tree.select(60), None);
+    ///```
+    fn select<'a>(&'a mut self, index: usize) -> Option<&'a D::Value>
+    where
+        D: 'a,
+        Self::TreeData: 'a,
+        D::Summary: SizedSummary,
+    {
+        self.iter_locator(index).next()
+    }
+
+    /// Returns the number of values to the left of the locator's segment,
+    /// i.e., the index of its first value, or of its position if the segment is empty.
+    ///```
+    /// use grove::{SomeTree, splay::SplayTree};
+    /// use grove::example_data::StdNum;
+    /// use grove::locators::FirstWhere;
+    ///
+    /// let mut tree: SplayTree<StdNum> = (20..80).collect();
+    ///
+    /// assert_eq!(tree.rank(13..17), 13);
+    /// assert_eq!(tree.rank(FirstWhere(|x: &i32| *x >= 50)), 30);
+    ///```
+    fn rank<L>(&mut self, locator: L) -> usize
+    where
+        L: locators::Locator<D>,
+        D::Summary: SizedSummary,
+    {
+        self.segment_summary(locators::LeftOf(locator)).size()
+    }
+
+    /// Returns the number of values whose keys are smaller than the given key.
+    /// Assumes that the values are sorted by their keys.
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::SizeData;
+    ///
+    /// let mut tree: Treap<SizeData<(i32, char)>> = vec![(1, 'a'), (3, 'b'), (3, 'c'), (7, 'd')]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(tree.rank_of_key(&3), 1);
+    /// assert_eq!(tree.rank_of_key(&5), 3);
+    ///```
+    fn rank_of_key(&mut self, key: &<D::Value as Keyed>::Key) -> usize
+    where
+        D::Value: Keyed,
+        D::Summary: SizedSummary,
+    {
+        self.rank(locators::ByKey((key,)))
+    }
+
+    /// Returns the number of values whose keys are in the given range.
+    /// Assumes that the values are sorted by their keys.
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::SizeData;
+    ///
+    /// let mut tree: Treap<SizeData<(i32, char)>> = vec![(1, 'a'), (3, 'b'), (3, 'c'), (7, 'd')]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert_eq!(tree.count_in_key_range(2..7), 2);
+    /// assert_eq!(tree.count_in_key_range(..=7), 4);
+    ///```
+    fn count_in_key_range<R>(&mut self, range: R) -> usize
+    where
+        R: std::ops::RangeBounds<<D::Value as Keyed>::Key>,
+        D::Value: Keyed,
+        D::Summary: SizedSummary,
+    {
+        let bounds = (range.start_bound(), range.end_bound());
+        self.segment_summary(locators::ByKey(bounds)).size()
+    }
+
+    /// Inserts the value at the splitter's position.
+    /// Since splitters never accept a node, the insertion can't fail.
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::StdNum;
+    /// use grove::locators::{BeforeIndex, FirstWhere};
+    ///
+    /// let mut tree: Treap<StdNum> = (20..80).collect();
+    /// tree.insert_at(BeforeIndex(3), 100);
+    /// tree.insert_at(FirstWhere(|&x: &i32| x >= 50), 40);
+    ///
+    /// assert_eq!(tree.iter_locator(0..5).cloned().collect::<Vec<_>>(), vec![20, 21, 22, 100, 23]);
+    /// assert_eq!(tree.iter_locator(30..33).cloned().collect::<Vec<_>>(), vec![49, 40, 50]);
+    /// # tree.assert_correctness();
+    ///```
+    fn insert_at<S>(&mut self, splitter: S, value: D::Value)
+    where
+        S: locators::Splitter<D>,
+        for<'a> &'a mut Self: ModifiableTreeRef<D>,
+    {
+        self.search(splitter)
+            .insert(value)
+            .expect("Splitters shouldn't accept any node");
+    }
+
+    /// Splits the tree at the splitter's position.
+    /// Returns the part to the left of the position and the part to the right of it.
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    /// use grove::locators::BeforeIndex;
+    ///
+    /// let tree: AVLTree<StdNum> = (20..80).collect();
+    /// let (mut left, mut right) = tree.split_at(BeforeIndex(10));
+    ///
+    /// assert_eq!(left.iter().cloned().collect::<Vec<_>>(), (20..30).collect::<Vec<_>>());
+    /// assert_eq!(right.iter().cloned().collect::<Vec<_>>(), (30..80).collect::<Vec<_>>());
+    /// # left.assert_correctness();
+    /// # right.assert_correctness();
+    ///```
+    fn split_at<S>(mut self, splitter: S) -> (Self, Self)
+    where
+        S: locators::Splitter<D>,
+        for<'a> &'a mut Self: SplittableTreeRef<D, T = Self>,
+    {
+        let right = self
+            .search(splitter)
+            .split_right()
+            .expect("Splitters shouldn't accept any node");
+        (self, right)
+    }
+
     /// Returns a value representing a specific subsegment of the tree. This gives a nicer
     /// Interface for tree operations: `tree.slice(3..50).act(action)` instead of
     /// `tree.act_segment(3..50, action)`. see [`slice::Slice`].
@@ -74,6 +321,7 @@ where
     ///
     /// See documentation in [`basic_tree::iterators`] as to why this function receives a `&mut self`
     /// instead of `&Self` input, and why there isn't a mutable iterator.
+    /// For an iterator that only requires `&self`, see [`QueryableTree::iter_locator_imm`].
     ///```
     /// use grove::{SomeTree, basic_tree::BasicTree};
     /// use grove::example_data::StdNum;
@@ -105,6 +353,78 @@ where
         self.iter_locator(..)
     }
 
+    /// Iterating on the values of a segment, in reverse order.
+    /// Same as `tree.iter_locator(locator).rev()`.
+    /// Reversals that were applied to the tree are respected: values come out in the reverse
+    /// of the order [`SomeTree::iter_locator`] would return them.
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::{StdNum, RevAffineAction};
+    ///
+    /// let mut tree: Treap<StdNum> = (20..80).collect();
+    /// tree.act_segment(RevAffineAction {to_reverse: true, mul: 1, add: 0}, 3..8);
+    /// let segment_iter = tree.iter_rev_locator(1..6);
+    ///
+    /// assert_eq!(segment_iter.cloned().collect::<Vec<_>>(), vec![25, 26, 27, 22, 21]);
+    /// # tree.assert_correctness();
+    ///```
+    fn iter_rev_locator<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> std::iter::Rev<basic_tree::iterators::IterLocator<'_, D, L, Self::TreeData>> {
+        self.iter_locator(locator).rev()
+    }
+
+    /// Returns a mutable cursor over a subsegment, which rebuilds the nodes as it goes.
+    /// See [`basic_tree::iterators::CursorMut`].
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: Treap<StdNum> = (0..10).collect();
+    /// let mut cursor = tree.cursor_mut(2..5);
+    /// while let Some(value) = cursor.next() {
+    ///     *value *= *value;
+    /// }
+    /// drop(cursor);
+    ///
+    /// assert_eq!(tree.segment_summary(..).sum, 45 - 9 + 29);
+    /// # tree.assert_correctness();
+    ///```
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L, Self::TreeData>;
+
+    /// Applies the function on every value in the subsegment, in order, and rebuilds the nodes
+    /// as it goes. This takes `O(k + log n)` time, where `k` is the length of the subsegment,
+    /// and is useful for modifying values non-uniformly, which can't be done with an action.
+    ///```
+    /// use grove::{SomeTree, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: AVLTree<StdNum> = (0..10).collect();
+    /// let mut index = 0;
+    /// tree.for_each_mut(5.., |value| {
+    ///     *value += index;
+    ///     index += 1;
+    /// });
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5, 7, 9, 11, 13]);
+    /// assert_eq!(tree.segment_summary(5..).sum, 45);
+    /// # tree.assert_correctness();
+    ///```
+    fn for_each_mut<L, F>(&mut self, locator: L, mut f: F)
+    where
+        L: locators::Locator<D>,
+        F: FnMut(&mut D::Value),
+    {
+        let mut cursor = self.cursor_mut(locator);
+        while let Some(value) = cursor.next() {
+            f(value);
+        }
+    }
+
     /// Used for testing purposes.
     /// Should panic if the invariants aren't satisfied.
     fn assert_correctness(&self)
@@ -112,6 +432,182 @@ where
         D::Summary: Eq;
 }
 
+/// Trees that can be queried through a shared reference, without restructuring the tree.
+/// For example, this allows querying a tree that is behind an [`std::sync::Arc`]
+/// or a [`std::sync::RwLock`] read guard.
+///
+/// Instead of pushing the pending actions down into the nodes, these queries compose the actions
+/// on the way down. Therefore, values that still have a pending action on them are cloned and
+/// then acted on, and values without any pending action are returned by reference.
+/// This is why the methods require `D::Value: Clone`.
+///
+/// [`splay::SplayTree`] doesn't implement this trait: its queries have to splay the tree
+/// in order to keep its amortized complexity guarantees.
+///
+/// The trees are [`Sync`] whenever `D::Value`, `D::Summary` and `D::Action` are, so a tree
+/// can be queried from several threads at once. See [`QueryableTree::par_segment_summaries`].
+pub trait QueryableTree<D: Data> {
+    /// This is here just so that the signature for iter_locator_imm can be written out. Don't use this.
+    type ImmTreeData;
+
+    /// Compute the summary of a subsegment.
+    /// Same as [`SomeTree::segment_summary`], but only requires a shared reference.
+    ///```
+    /// use grove::{SomeTree, QueryableTree, treap::Treap};
+    /// use grove::example_data::{StdNum, RevAffineAction};
+    ///
+    /// let mut tree: Treap<StdNum> = (1..=20).collect();
+    /// tree.act_segment(RevAffineAction {to_reverse: true, mul: 2, add: 0}, 5..10);
+    /// let tree = std::sync::Arc::new(tree);
+    ///
+    /// assert_eq!(tree.segment_summary_imm(3..7).sum, 4 + 5 + 20 + 18);
+    ///```
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone;
+
+    /// Finds any value that the locator `Accept`s.
+    /// If there isn't any, returns [`None`].
+    ///
+    /// The value is borrowed from the tree, unless there is a pending action that applies to it.
+    ///```
+    /// use grove::{QueryableTree, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: AVLTree<StdNum> = (20..80).collect();
+    ///
+    /// assert_eq!(tree.search_imm(13).as_deref(), Some(&33));
+    /// assert_eq!(tree.search_imm(13..13), None);
+    ///```
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone;
+
+    /// Finds any value that the locator `Accept`s, like [`QueryableTree::search_imm`].
+    /// If there isn't any, returns [`None`].
+    ///
+    /// Since the actions never change the values, the value is always borrowed from the tree,
+    /// and the values don't have to be [`Clone`].
+    ///```
+    /// use grove::{SomeTree, QueryableTree, avl::AVLTree};
+    /// use grove::example_data::{ReverseOrder, SequenceData};
+    ///
+    /// let mut tree: AVLTree<SequenceData<String>> = (20..80).map(|x| x.to_string()).collect();
+    /// tree.act_segment(ReverseOrder { to_reverse: true }, 10..20);
+    ///
+    /// assert_eq!(tree.search_ref(13).map(|x| x.as_str()), Some("36"));
+    /// assert_eq!(tree.search_ref(13..13), None);
+    ///```
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: locators::Locator<D>,
+        D::Action: ValuePreservingAction;
+
+    /// Iterating on the values of a segment.
+    /// Same as [`SomeTree::iter_locator`], but only requires a shared reference.
+    ///```
+    /// use grove::{QueryableTree, basic_tree::BasicTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: BasicTree<StdNum> = (20..80).collect();
+    /// let segment_iter = tree.iter_locator_imm(3..13);
+    ///
+    /// assert_eq!(segment_iter.map(|x| *x).collect::<Vec<_>>(), (23..33).collect::<Vec<_>>());
+    ///```
+    fn iter_locator_imm<L>(
+        &self,
+        locator: L,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, L, Self::ImmTreeData>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone;
+
+    /// Iterates over the whole tree.
+    /// Same as [`SomeTree::iter`], but only requires a shared reference.
+    fn iter_imm(
+        &self,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, std::ops::RangeFull, Self::ImmTreeData>
+    where
+        D::Value: Clone,
+    {
+        self.iter_locator_imm(..)
+    }
+
+    /// Computes the summaries of a batch of subsegments, splitting the queries between threads.
+    /// The summaries are returned in the same order as the locators.
+    ///
+    /// With the `rayon` feature, the queries run on the `rayon` thread pool. Otherwise, at most
+    /// [`std::thread::available_parallelism`] threads are spawned for the call.
+    /// In both cases, every thread gets at least 64 queries, so small batches are computed
+    /// on the current thread.
+    ///```
+    /// use grove::{SomeTree, QueryableTree, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: AVLTree<StdNum> = (0..1000).collect();
+    /// let locators: Vec<_> = (0..100).map(|i| i..(i + 10)).collect();
+    /// let summaries = tree.par_segment_summaries(&locators);
+    ///
+    /// for (i, summary) in summaries.iter().enumerate() {
+    ///     assert_eq!(summary.sum, (i as i32..(i as i32 + 10)).sum());
+    /// }
+    /// # tree.assert_correctness();
+    ///```
+    fn par_segment_summaries<L>(&self, locators: &[L]) -> Vec<D::Summary>
+    where
+        L: locators::Locator<D> + Sync,
+        D::Value: Clone,
+        D::Summary: Send,
+        Self: Sync,
+    {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            locators
+                .par_iter()
+                .with_min_len(PAR_MIN_QUERIES)
+                .map(|locator| self.segment_summary_imm(locator.clone()))
+                .collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            let num_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            let chunk_size = std::cmp::max(PAR_MIN_QUERIES, locators.len().div_ceil(num_threads));
+            if locators.len() <= chunk_size {
+                return locators
+                    .iter()
+                    .map(|locator| self.segment_summary_imm(locator.clone()))
+                    .collect();
+            }
+
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = locators
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .map(|locator| self.segment_summary_imm(locator.clone()))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect()
+            })
+        }
+    }
+}
+
+/// The minimal number of queries that [`QueryableTree::par_segment_summaries`] gives a thread.
+/// Smaller batches aren't worth the overhead of running on another thread.
+const PAR_MIN_QUERIES: usize = 64;
+
 /// This is a workaround for not having Generic Associated Types in Rust yet.
 /// Really, the type [`Self::Walker`] should have been defined in [`SomeTree`] and
 /// should have been generic in a lifetime parameter.
@@ -252,6 +748,63 @@ pub trait SomeWalker<D: Data>: SomeEntry<D> {
         }
     }
 
+    /// Finds any node that the locator `Accept`s, like [`SomeTreeRef::search`], but starting
+    /// from the walker's current position instead of from the root.
+    /// If there isn't any, it finds the empty location where that node would be instead.
+    ///
+    /// The walker only climbs up until it has passed values on both sides of the wanted position,
+    /// or the current subtree touches the edge of the tree on the remaining side.
+    /// Then the current subtree must contain the wanted position, and the walker descends.
+    /// When the wanted position is at distance `d` from the current position,
+    /// this takes `O(log d)` expected time for treaps.
+    ///
+    /// For the other balanced trees, including AVL trees, it takes `O(log d)` time on average over
+    /// the current position, but `O(log n)` time in the worst case. Walkers only have the path
+    /// from the root, and even neighboring positions can be on different sides of the root, so
+    /// getting from one to the other has to climb up to the root. Searching for all positions
+    /// in order takes `O(1)` amortized time per search.
+    /// This is useful for many nearby searches in sequence.
+    ///```
+    /// use grove::{SomeEntry, SomeTreeRef, SomeWalker, treap::Treap};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: Treap<StdNum> = (20..80).collect();
+    /// let mut walker = (&mut tree).search(10);
+    /// for index in 11..20 {
+    ///     walker.finger_search(index);
+    ///     assert_eq!(walker.value(), Some(&(20 + index as i32)));
+    /// }
+    /// walker.finger_search(5..5);
+    /// assert!(walker.is_empty());
+    /// assert_eq!(walker.left_summary().size, 5);
+    ///```
+    fn finger_search<L: crate::Locator<D>>(&mut self, locator: L) {
+        use locators::LocResult;
+        // whether we know that the wanted position is to the right of the current subtree's
+        // left boundary, and to the left of its right boundary.
+        // if the wanted position is to the right of any value in the current subtree, it is
+        // to the right of the subtree's left boundary, and similarly for the right boundary.
+        let mut left_done = false;
+        let mut right_done = false;
+        loop {
+            match locators::walker_locate(self, &locator) {
+                Some(LocResult::Accept) => return,
+                Some(LocResult::GoRight) => left_done = true,
+                Some(LocResult::GoLeft) => right_done = true,
+                // an empty position
+                None => (),
+            }
+            // if there is no boundary, there is nothing to pass.
+            left_done |= self.touches_left_edge();
+            right_done |= self.touches_right_edge();
+            // at the root, the whole tree contains the wanted position.
+            if (left_done && right_done) || self.go_up().is_err() {
+                break;
+            }
+        }
+        self.search_subtree(locator);
+    }
+
     /// Returns a summary of all the values to the left of this point,
     /// That are not children of this point.
     fn far_left_summary(&self) -> D::Summary;
@@ -259,6 +812,35 @@ pub trait SomeWalker<D: Data>: SomeEntry<D> {
     /// That are not children of this point.
     fn far_right_summary(&self) -> D::Summary;
 
+    /// Returns whether the current subtree touches the left edge of the tree,
+    /// i.e., whether there are no values to the left of this point that are not children of it.
+    fn touches_left_edge(&self) -> bool;
+    /// Returns whether the current subtree touches the right edge of the tree,
+    /// i.e., whether there are no values to the right of this point that are not children of it.
+    fn touches_right_edge(&self) -> bool;
+
+    /// Returns the index of the current position, i.e., the number of values to its left.
+    /// If the walker is in a non empty spot, this is the index of the current value.
+    ///```
+    /// use grove::{SomeTreeRef, SomeWalker, avl::AVLTree};
+    /// use grove::example_data::StdNum;
+    /// use grove::locators::FirstWhere;
+    ///
+    /// let mut tree: AVLTree<StdNum> = (20..80).collect();
+    /// let mut walker = (&mut tree).search(FirstWhere(|x: &i32| *x >= 42));
+    /// assert_eq!(walker.index(), 22);
+    ///
+    /// walker.next_filled().unwrap();
+    /// assert_eq!(walker.value(), Some(&42));
+    /// assert_eq!(walker.index(), 22);
+    ///```
+    fn index(&self) -> usize
+    where
+        D::Summary: SizedSummary,
+    {
+        self.left_summary().size()
+    }
+
     /// Returns a summary of all the values to the left of this point.
     /// If the walker is in a non empty spot, this does not include the current node.
     fn left_summary(&self) -> D::Summary {
@@ -412,3 +994,49 @@ pub trait SplittableWalker<D: Data>: ModifiableWalker<D> {
     /// Otherwise returns [`None`].
     fn split_left(&mut self) -> Option<Self::T>;
 }
+
+/// Set operations on trees whose values are ordered by keys, i.e., `D::Value: Keyed`.
+/// All of the operations assume that the values in the trees are in the correct order,
+/// and keep the result in the correct order.
+///
+/// Balanced trees that can be taken apart at their roots implement these operations using
+/// the join-based algorithms. Other trees use the split-based algorithms in [`methods`].
+///```
+/// use grove::{SomeTree, SetOperations, avl::AVLTree, splay::SplayTree};
+/// use grove::example_data::{NoAction, Ordered};
+///
+/// fn common<T: SetOperations<NoAction<Ordered<i32>>>>(mut tree1: T, tree2: T) -> T {
+///     tree1.intersection(tree2);
+///     tree1
+/// }
+///
+/// let tree1: AVLTree<_> = (0..7).map(|x| Ordered(x)).collect();
+/// let tree2: AVLTree<_> = (4..9).map(|x| Ordered(x)).collect();
+/// let tree = common(tree1, tree2);
+/// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///
+/// let tree1: SplayTree<_> = (0..7).map(|x| Ordered(x)).collect();
+/// let tree2: SplayTree<_> = (4..9).map(|x| Ordered(x)).collect();
+/// let tree = common(tree1, tree2);
+/// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+pub trait SetOperations<D: Data>: Sized {
+    /// Computes the union of the two trees, ordered by keys.
+    /// Values with equal keys are all kept, and the values from `other` are placed after
+    /// the values of `self` with the same key.
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed;
+
+    /// Computes the intersection of the two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys appear in `other`. Values with equal keys in `self` are all kept.
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed;
+
+    /// Computes the difference of the two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys don't appear in `other`.
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed;
+}
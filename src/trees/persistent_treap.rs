@@ -0,0 +1,723 @@
+//! Implementation of persistent treaps.
+//!
+//! A [`PersistentTreap`] is immutable: every operation returns a new version of the tree,
+//! and leaves the old version unchanged. The versions share all of the untouched subtrees,
+//! so every operation only allocates `O(log n)` new nodes, and cloning a tree takes `O(1)` time.
+//! This is useful for keeping snapshots of a sequence over time.
+//!
+//! Nodes are shared using [`Arc`], so different versions can be sent to different threads.
+//! Actions are still applied lazily: when an action has to be pushed down, the nodes
+//! are copied along the way (path copying). Therefore, the values have to be [`Clone`].
+//!
+//! Since the tree can't be modified in place, it doesn't have walkers, and doesn't implement
+//! [`SomeTree`](crate::trees::SomeTree). However, its operations receive the same
+//! [`Locator`]s as the other trees.
+//!
+//! Operations take `O(log n)` expected time, probabilistically, like in a regular
+//! [`Treap`](super::treap::Treap).
+
+use crate::locators::*;
+use crate::*;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// convention: a bigger number should go higher up the tree.
+type T = u64;
+
+/// A possibly empty shared subtree.
+type Tree<D> = Option<Arc<Node<D>>>;
+
+/// A node of a persistent treap. Nodes are never modified after they are created.
+struct Node<D: Data> {
+    value: D::Value,
+    /// The summary of the whole subtree, not including `action`.
+    subtree_summary: D::Summary,
+    /// An action that applies to the whole subtree, including `value` and `subtree_summary`.
+    action: D::Action,
+    priority: T,
+    left: Tree<D>,
+    right: Tree<D>,
+}
+
+/// Returns the summary of the whole subtree.
+fn summary<D: Data>(tree: &Tree<D>) -> D::Summary {
+    match tree {
+        None => Default::default(),
+        Some(node) => node.action.act(node.subtree_summary),
+    }
+}
+
+/// Creates a new node with no action.
+fn make<D: Data>(value: D::Value, priority: T, left: Tree<D>, right: Tree<D>) -> Tree<D> {
+    let subtree_summary = summary(&left) + D::to_summary(&value) + summary(&right);
+    Some(Arc::new(Node {
+        value,
+        subtree_summary,
+        action: Default::default(),
+        priority,
+        left,
+        right,
+    }))
+}
+
+/// Returns a copy of the root node with the action applied to the whole subtree.
+/// The sons are shared, and not copied.
+fn act<D: Data>(tree: &Tree<D>, action: D::Action) -> Tree<D>
+where
+    D::Value: Clone,
+{
+    match tree {
+        Some(node) if !action.is_identity() => Some(Arc::new(Node {
+            value: node.value.clone(),
+            subtree_summary: node.subtree_summary,
+            action: action + node.action,
+            priority: node.priority,
+            left: node.left.clone(),
+            right: node.right.clone(),
+        })),
+        _ => tree.clone(),
+    }
+}
+
+/// Returns the left son, value and right son of the node, after applying the node's action.
+/// This is the equivalent of `access()` in the other trees.
+fn expose<D: Data>(node: &Node<D>) -> (Tree<D>, D::Value, Tree<D>)
+where
+    D::Value: Clone,
+{
+    let value = node.action.act(node.value.clone());
+    let left = act(&node.left, node.action);
+    let right = act(&node.right, node.action);
+    if node.action.to_reverse() {
+        (right, value, left)
+    } else {
+        (left, value, right)
+    }
+}
+
+/// A node as it looks after applying the actions of its ancestors.
+/// Unlike [`expose`], this doesn't copy any nodes, and is used for queries.
+struct NodeView<'a, D: Data>
+where
+    D::Value: Clone,
+{
+    /// The left son, in the order after the actions are applied.
+    left: &'a Tree<D>,
+    /// The value, after the actions are applied.
+    /// It is only cloned if there is an action that actually applies to it.
+    value: Cow<'a, D::Value>,
+    /// The right son, in the order after the actions are applied.
+    right: &'a Tree<D>,
+    /// The total action that still applies to the sons' subtrees.
+    action: D::Action,
+}
+
+/// Returns a view of the node, given the total action of its ancestors.
+fn view<D: Data>(node: &Node<D>, action: D::Action) -> NodeView<'_, D>
+where
+    D::Value: Clone,
+{
+    let action = action + node.action;
+    let value = if action.preserves_values() {
+        Cow::Borrowed(&node.value)
+    } else {
+        Cow::Owned(action.act(node.value.clone()))
+    };
+    let (left, right) = if action.to_reverse() {
+        (&node.right, &node.left)
+    } else {
+        (&node.left, &node.right)
+    };
+    NodeView {
+        left,
+        value,
+        right,
+        action,
+    }
+}
+
+impl<'a, D: Data> NodeView<'a, D>
+where
+    D::Value: Clone,
+{
+    /// Returns the summary of the left son's subtree.
+    fn left_summary(&self) -> D::Summary {
+        self.action.act(summary(self.left))
+    }
+
+    /// Returns the summary of the right son's subtree.
+    fn right_summary(&self) -> D::Summary {
+        self.action.act(summary(self.right))
+    }
+
+    /// Returns the summary of the value.
+    fn value_summary(&self) -> D::Summary {
+        D::to_summary(self.value.as_ref())
+    }
+}
+
+/// Computes the summary of the part of the segment inside `tree`, assuming that the segment
+/// continues to the right of `tree`. `action` is the total action of the tree's ancestors, and
+/// `left` and `right` are the summaries of the values to the left and right of the tree.
+fn segment_summary_on_suffix<D: Data, L: Locator<D>>(
+    mut tree: &Tree<D>,
+    mut action: D::Action,
+    locator: &L,
+    mut left: D::Summary,
+    mut right: D::Summary,
+) -> D::Summary
+where
+    D::Value: Clone,
+{
+    let mut res: D::Summary = Default::default();
+    while let Some(node) = tree {
+        let view = view(node, action);
+        let value_summary = view.value_summary();
+        let right_summary = view.right_summary();
+        let near_left_summary = left + view.left_summary();
+        match locator.locate(near_left_summary, &view.value, right_summary + right) {
+            LocResult::Accept => {
+                res = value_summary + right_summary + res;
+                right = value_summary + right_summary + right;
+                tree = view.left;
+            }
+            LocResult::GoRight => {
+                left = near_left_summary + value_summary;
+                tree = view.right;
+            }
+            LocResult::GoLeft => panic!("inconsistent locator"),
+        }
+        action = view.action;
+    }
+    res
+}
+
+/// Computes the summary of the part of the segment inside `tree`, assuming that the segment
+/// continues to the left of `tree`. `action` is the total action of the tree's ancestors, and
+/// `left` and `right` are the summaries of the values to the left and right of the tree.
+fn segment_summary_on_prefix<D: Data, L: Locator<D>>(
+    mut tree: &Tree<D>,
+    mut action: D::Action,
+    locator: &L,
+    mut left: D::Summary,
+    mut right: D::Summary,
+) -> D::Summary
+where
+    D::Value: Clone,
+{
+    let mut res: D::Summary = Default::default();
+    while let Some(node) = tree {
+        let view = view(node, action);
+        let value_summary = view.value_summary();
+        let left_summary = view.left_summary();
+        let near_right_summary = view.right_summary() + right;
+        match locator.locate(left + left_summary, &view.value, near_right_summary) {
+            LocResult::Accept => {
+                res = res + left_summary + value_summary;
+                left = left + left_summary + value_summary;
+                tree = view.right;
+            }
+            LocResult::GoLeft => {
+                right = value_summary + near_right_summary;
+                tree = view.left;
+            }
+            LocResult::GoRight => panic!("inconsistent locator"),
+        }
+        action = view.action;
+    }
+    res
+}
+
+/// Splits the tree at the empty position the locator leads to.
+/// `left` and `right` are the summaries of the values to the left and right of the tree.
+/// Returns [`None`] if the locator accepts a node.
+fn split<D: Data, L: Locator<D>>(
+    tree: &Tree<D>,
+    locator: &L,
+    left: D::Summary,
+    right: D::Summary,
+) -> Option<(Tree<D>, Tree<D>)>
+where
+    D::Value: Clone,
+{
+    let node = match tree {
+        None => return Some((None, None)),
+        Some(node) => node,
+    };
+    let (left_son, value, right_son) = expose(node);
+    let left_summary = left + summary(&left_son);
+    let right_summary = summary(&right_son) + right;
+    match locator.locate(left_summary, &value, right_summary) {
+        LocResult::Accept => None,
+        LocResult::GoRight => {
+            let left_summary = left_summary + D::to_summary(&value);
+            let (mid, right_tree) = split(&right_son, locator, left_summary, right)?;
+            Some((make(value, node.priority, left_son, mid), right_tree))
+        }
+        LocResult::GoLeft => {
+            let right_summary = D::to_summary(&value) + right_summary;
+            let (left_tree, mid) = split(&left_son, locator, left, right_summary)?;
+            Some((left_tree, make(value, node.priority, mid, right_son)))
+        }
+    }
+}
+
+/// Concatenates the trees.
+fn merge<D: Data>(left: &Tree<D>, right: &Tree<D>) -> Tree<D>
+where
+    D::Value: Clone,
+{
+    match (left, right) {
+        (None, _) => right.clone(),
+        (_, None) => left.clone(),
+        (Some(left_node), Some(right_node)) => {
+            if left_node.priority > right_node.priority {
+                let (left_son, value, right_son) = expose(left_node);
+                make(
+                    value,
+                    left_node.priority,
+                    left_son,
+                    merge(&right_son, right),
+                )
+            } else {
+                let (left_son, value, right_son) = expose(right_node);
+                make(
+                    value,
+                    right_node.priority,
+                    merge(left, &left_son),
+                    right_son,
+                )
+            }
+        }
+    }
+}
+
+/// Deletes any node that the locator accepts.
+/// `left` and `right` are the summaries of the values to the left and right of the tree.
+/// Returns [`None`] if the locator doesn't accept any node.
+fn delete<D: Data, L: Locator<D>>(
+    tree: &Tree<D>,
+    locator: &L,
+    left: D::Summary,
+    right: D::Summary,
+) -> Option<(Tree<D>, D::Value)>
+where
+    D::Value: Clone,
+{
+    let node = tree.as_ref()?;
+    let (left_son, value, right_son) = expose(node);
+    let left_summary = left + summary(&left_son);
+    let right_summary = summary(&right_son) + right;
+    match locator.locate(left_summary, &value, right_summary) {
+        LocResult::Accept => Some((merge(&left_son, &right_son), value)),
+        LocResult::GoRight => {
+            let left_summary = left_summary + D::to_summary(&value);
+            let (new_right, res) = delete(&right_son, locator, left_summary, right)?;
+            Some((make(value, node.priority, left_son, new_right), res))
+        }
+        LocResult::GoLeft => {
+            let right_summary = D::to_summary(&value) + right_summary;
+            let (new_left, res) = delete(&left_son, locator, left, right_summary)?;
+            Some((make(value, node.priority, new_left, right_son), res))
+        }
+    }
+}
+
+/// A persistent treap. See the [module documentation](self).
+pub struct PersistentTreap<D: Data> {
+    root: Tree<D>,
+}
+
+impl<D: Data> Clone for PersistentTreap<D> {
+    /// Cloning only copies a reference to the root, and takes `O(1)` time.
+    fn clone(&self) -> Self {
+        PersistentTreap {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<D: Data> Default for PersistentTreap<D> {
+    fn default() -> Self {
+        PersistentTreap::new()
+    }
+}
+
+impl<D: Data> PersistentTreap<D> {
+    /// Creates an empty persistent treap.
+    pub fn new() -> Self {
+        PersistentTreap { root: None }
+    }
+
+    /// Returns `true` if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the summary of all values in the tree.
+    pub fn subtree_summary(&self) -> D::Summary {
+        summary(&self.root)
+    }
+
+    /// Returns `true` if the two trees are the same version, i.e.,
+    /// they share the same root node. Takes `O(1)` time.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (None, None) => true,
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Iterates over the values of the tree. The values are cloned, since the nodes
+    /// only store them before the actions are applied.
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: PersistentTreap<StdNum> = (17..=89).collect();
+    ///
+    /// assert_eq!(tree.iter().collect::<Vec<_>>(), (17..=89).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    pub fn iter(&self) -> Iter<'_, D> {
+        let mut iter = Iter { stack: vec![] };
+        iter.push_left_edge(&self.root, Default::default());
+        iter
+    }
+
+    /// Checks that every node's summary is correct, and that the priorities
+    /// are in heap order. Otherwise, panics.
+    pub fn assert_correctness(&self)
+    where
+        D::Summary: Eq,
+    {
+        let mut stack = vec![&self.root];
+        while let Some(tree) = stack.pop() {
+            if let Some(node) = tree {
+                let expected =
+                    summary(&node.left) + D::to_summary(&node.value) + summary(&node.right);
+                assert!(node.subtree_summary == expected);
+                for son in [&node.left, &node.right].iter() {
+                    if let Some(son) = son {
+                        assert!(son.priority <= node.priority);
+                    }
+                    stack.push(son);
+                }
+            }
+        }
+    }
+}
+
+impl<D: Data> PersistentTreap<D>
+where
+    D::Value: Clone,
+{
+    /// Returns the summary of the segment the locator represents.
+    /// Doesn't copy any nodes: the pending actions are only composed on the way down.
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: PersistentTreap<StdNum> = (1..=100).collect();
+    ///
+    /// assert_eq!(tree.segment_summary(10..20).sum, (11..=20).sum());
+    ///```
+    pub fn segment_summary<L: Locator<D>>(&self, locator: L) -> D::Summary {
+        let mut tree = &self.root;
+        let mut action: D::Action = Default::default();
+        let mut left: D::Summary = Default::default();
+        let mut right: D::Summary = Default::default();
+        while let Some(node) = tree {
+            let view = view(node, action);
+            let value_summary = view.value_summary();
+            let near_left_summary = left + view.left_summary();
+            let near_right_summary = view.right_summary() + right;
+            match locator.locate(near_left_summary, &view.value, near_right_summary) {
+                LocResult::GoRight => {
+                    left = near_left_summary + value_summary;
+                    tree = view.right;
+                }
+                LocResult::GoLeft => {
+                    right = value_summary + near_right_summary;
+                    tree = view.left;
+                }
+                // at this point, we split into the two sides
+                LocResult::Accept => {
+                    let first_half = segment_summary_on_suffix(
+                        view.left,
+                        view.action,
+                        &locator,
+                        left,
+                        value_summary + near_right_summary,
+                    );
+                    let second_half = segment_summary_on_prefix(
+                        view.right,
+                        view.action,
+                        &locator,
+                        near_left_summary + value_summary,
+                        right,
+                    );
+                    return first_half + value_summary + second_half;
+                }
+            }
+            action = view.action;
+        }
+
+        // empty segment case
+        Default::default()
+    }
+
+    /// Returns a copy of any value that the locator accepts.
+    /// Returns [`None`] if there isn't any.
+    /// Doesn't copy any nodes: the pending actions are only composed on the way down.
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::{StdNum, RevAffineAction};
+    ///
+    /// let tree: PersistentTreap<StdNum> = (1..=8).collect();
+    /// let tree = tree.act_segment(RevAffineAction { to_reverse: true, mul: 2, add: 0 }, 2..5);
+    ///
+    /// assert_eq!(tree.search(3), Some(8));
+    /// assert_eq!(tree.search(8), None);
+    ///```
+    pub fn search<L: Locator<D>>(&self, locator: L) -> Option<D::Value> {
+        let mut tree = &self.root;
+        let mut action: D::Action = Default::default();
+        let mut left: D::Summary = Default::default();
+        let mut right: D::Summary = Default::default();
+        while let Some(node) = tree {
+            let view = view(node, action);
+            let value_summary = view.value_summary();
+            let near_left_summary = left + view.left_summary();
+            let near_right_summary = view.right_summary() + right;
+            match locator.locate(near_left_summary, &view.value, near_right_summary) {
+                LocResult::Accept => return Some(view.value.into_owned()),
+                LocResult::GoRight => {
+                    left = near_left_summary + value_summary;
+                    tree = view.right;
+                }
+                LocResult::GoLeft => {
+                    right = value_summary + near_right_summary;
+                    tree = view.left;
+                }
+            }
+            action = view.action;
+        }
+        None
+    }
+
+    /// Returns a new version, with the action applied to the segment the locator represents.
+    /// This works with reversals as well.
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::{StdNum, RevAffineAction};
+    ///
+    /// let tree: PersistentTreap<StdNum> = (1..=8).collect();
+    /// let tree2 = tree.act_segment(RevAffineAction { to_reverse: true, mul: 1, add: 10 }, 2..5);
+    ///
+    /// assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    /// assert_eq!(tree2.iter().collect::<Vec<_>>(), vec![1, 2, 15, 14, 13, 6, 7, 8]);
+    /// # tree2.assert_correctness();
+    ///```
+    pub fn act_segment<L: Locator<D>>(&self, action: D::Action, locator: L) -> Self {
+        let (left, mid, right) = self.split_segment(locator);
+        left.concatenate(&mid.act_subtree(action))
+            .concatenate(&right)
+    }
+
+    /// Returns a new version, with the action applied to the whole tree.
+    /// Takes `O(1)` time.
+    pub fn act_subtree(&self, action: D::Action) -> Self {
+        PersistentTreap {
+            root: act(&self.root, action),
+        }
+    }
+
+    /// Returns a new version, with the value inserted at the empty position
+    /// the locator leads to, i.e, the locator shouldn't accept any node.
+    /// If the locator accepts a node, returns [`None`].
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: PersistentTreap<StdNum> = (1..=5).collect();
+    /// let tree2 = tree.insert(2..2, 13).unwrap();
+    ///
+    /// assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(tree2.iter().collect::<Vec<_>>(), vec![1, 2, 13, 3, 4, 5]);
+    /// # tree2.assert_correctness();
+    ///```
+    pub fn insert<L: Locator<D>>(&self, locator: L, value: D::Value) -> Option<Self> {
+        let (left, right) = self.split(locator)?;
+        let new = PersistentTreap {
+            root: make(value, rand::random(), None, None),
+        };
+        Some(left.concatenate(&new).concatenate(&right))
+    }
+
+    /// Returns a new version, with a value that the locator accepts deleted,
+    /// along with the deleted value.
+    /// If the locator doesn't accept any node, returns [`None`].
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: PersistentTreap<StdNum> = (1..=5).collect();
+    /// let (tree2, value) = tree.delete(3).unwrap();
+    ///
+    /// assert_eq!(value, 4);
+    /// assert_eq!(tree.iter().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    /// assert_eq!(tree2.iter().collect::<Vec<_>>(), vec![1, 2, 3, 5]);
+    /// # tree2.assert_correctness();
+    ///```
+    pub fn delete<L: Locator<D>>(&self, locator: L) -> Option<(Self, D::Value)> {
+        let (root, value) = delete(&self.root, &locator, Default::default(), Default::default())?;
+        Some((PersistentTreap { root }, value))
+    }
+
+    /// Splits the tree at the empty position the locator leads to,
+    /// i.e, the locator shouldn't accept any node.
+    /// If the locator accepts a node, returns [`None`].
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: PersistentTreap<StdNum> = (17..88).collect();
+    /// let (left, right) = tree.split(7..7).unwrap();
+    ///
+    /// assert_eq!(left.iter().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(right.iter().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # left.assert_correctness();
+    /// # right.assert_correctness();
+    ///```
+    pub fn split<L: Locator<D>>(&self, locator: L) -> Option<(Self, Self)> {
+        let (left, right) = split(&self.root, &locator, Default::default(), Default::default())?;
+        Some((
+            PersistentTreap { root: left },
+            PersistentTreap { root: right },
+        ))
+    }
+
+    /// Splits the tree into three parts: the values left of the segment the locator represents,
+    /// the segment itself, and the values right of the segment.
+    pub fn split_segment<L: Locator<D>>(&self, locator: L) -> (Self, Self, Self) {
+        let (left, rest) = split(
+            &self.root,
+            &LeftEdgeOf(locator.clone()),
+            Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+        let (mid, right) = split(
+            &rest,
+            &RightEdgeOf(locator),
+            summary(&left),
+            Default::default(),
+        )
+        .unwrap();
+        (
+            PersistentTreap { root: left },
+            PersistentTreap { root: mid },
+            PersistentTreap { root: right },
+        )
+    }
+
+    /// Returns the concatenation of the two trees.
+    ///```
+    /// use grove::persistent_treap::PersistentTreap;
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree: PersistentTreap<StdNum> = (17..=89).collect();
+    /// let tree2: PersistentTreap<StdNum> = (13..=25).collect();
+    /// let tree3 = tree.concatenate(&tree2);
+    ///
+    /// assert_eq!(tree3.iter().collect::<Vec<_>>(), (17..=89).chain(13..=25).collect::<Vec<_>>());
+    /// # tree3.assert_correctness();
+    ///```
+    pub fn concatenate(&self, right: &Self) -> Self {
+        PersistentTreap {
+            root: merge(&self.root, &right.root),
+        }
+    }
+}
+
+/// A node on the right edge of a tree that is being built.
+/// Its right son isn't known yet.
+struct Frame<D: Data> {
+    value: D::Value,
+    priority: T,
+    left: Tree<D>,
+}
+
+impl<D: Data> std::iter::FromIterator<D::Value> for PersistentTreap<D> {
+    /// This takes `O(n)` worst-case time.
+    fn from_iter<I: IntoIterator<Item = D::Value>>(iter: I) -> Self {
+        // The stack holds the right edge of the tree, with decreasing priorities.
+        let mut stack: Vec<Frame<D>> = vec![];
+        for value in iter {
+            let priority: T = rand::random();
+            let mut left = None;
+            while let Some(frame) = stack.pop() {
+                if frame.priority > priority {
+                    stack.push(frame);
+                    break;
+                }
+                left = make(frame.value, frame.priority, frame.left, left);
+            }
+            stack.push(Frame {
+                value,
+                priority,
+                left,
+            });
+        }
+
+        let mut root = None;
+        while let Some(frame) = stack.pop() {
+            root = make(frame.value, frame.priority, frame.left, root);
+        }
+        PersistentTreap { root }
+    }
+}
+
+/// An iterator over the values of a [`PersistentTreap`].
+/// See [`PersistentTreap::iter`].
+pub struct Iter<'a, D: Data> {
+    /// The nodes whose values haven't been returned yet, along with the total action
+    /// that applies to them.
+    stack: Vec<(&'a Node<D>, D::Action)>,
+}
+
+impl<'a, D: Data> Iter<'a, D> {
+    /// Pushes the left edge of the tree into the stack.
+    /// `action` is the action that applies to the tree from its ancestors.
+    fn push_left_edge(&mut self, mut tree: &'a Tree<D>, mut action: D::Action) {
+        while let Some(node) = tree {
+            action = action + node.action;
+            self.stack.push((node, action));
+            tree = if action.to_reverse() {
+                &node.right
+            } else {
+                &node.left
+            };
+        }
+    }
+}
+
+impl<'a, D: Data> Iterator for Iter<'a, D>
+where
+    D::Value: Clone,
+{
+    type Item = D::Value;
+
+    fn next(&mut self) -> Option<D::Value> {
+        let (node, action) = self.stack.pop()?;
+        let next = if action.to_reverse() {
+            &node.left
+        } else {
+            &node.right
+        };
+        self.push_left_edge(next, action);
+        Some(action.act(node.value.clone()))
+    }
+}
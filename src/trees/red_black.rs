@@ -0,0 +1,941 @@
+//! Implementation of red-black trees.
+//! Balanced by keeping a color in every node, this is a worst-case balancing
+//! algorithm that does at most a constant number of rotations per insertion or deletion.
+
+use crate::locators;
+
+use super::basic_tree::*;
+use super::*;
+
+/// The color of a node in a red-black tree.
+/// Empty trees are considered black.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(missing_docs)]
+pub enum Color {
+    Red,
+    Black,
+}
+
+/// Used for the algorithm-specific data
+type T = Color;
+
+/// A tree taken apart into its root's left subtree, its root, and its root's right subtree.
+type Exposed<D> = (RBTree<D>, Box<BasicNode<D, T>>, RBTree<D>);
+
+/// A red-black tree. Balanced by keeping a color in every node, this is a worst-case balancing
+/// algorithm that does at most a constant number of rotations per insertion or deletion.
+pub struct RBTree<D: Data> {
+    tree: BasicTree<D, T>,
+}
+
+/// Returns the color of the root of the tree. Empty trees are black.
+fn color<D: Data>(tree: &BasicTree<D, T>) -> Color {
+    match tree.alg_data() {
+        None => Color::Black,
+        Some(color) => *color,
+    }
+}
+
+/// Returns the amount of black nodes on the leftmost path of the tree.
+/// If the tree is correct, this is the amount of black nodes on any path from
+/// the root to an empty position.
+/// This doesn't depend on the reversal status of the tree, so it is computed
+/// without accessing the nodes.
+fn black_height<D: Data>(tree: &BasicTree<D, T>) -> usize {
+    let mut res = 0;
+    let mut current = tree;
+    while let Some(node) = current.node() {
+        if node.alg_data == Color::Black {
+            res += 1;
+        }
+        current = &node.left;
+    }
+    res
+}
+
+impl<D: Data> RBTree<D> {
+    /// Creates an empty [`RBTree`].
+    pub fn new() -> Self {
+        RBTree {
+            tree: BasicTree::Empty,
+        }
+    }
+
+    /// Asserts that the colors at the current node are correct.
+    /// Otherwise, panics.
+    pub fn assert_colors_locally(&self) {
+        if let Some(node) = self.tree.node() {
+            Self::assert_colors_locally_internal(node);
+        }
+    }
+
+    fn assert_colors_locally_internal(node: &BasicNode<D, T>) {
+        if node.alg_data == Color::Red {
+            assert!(color(&node.left) == Color::Black);
+            assert!(color(&node.right) == Color::Black);
+        }
+        assert_eq!(black_height(&node.left), black_height(&node.right));
+    }
+
+    /// Asserts that the tree's colors are correct: the root is black,
+    /// no red node has a red son, and every path from the root to an empty position
+    /// goes through the same amount of black nodes.
+    /// Otherwise, panics.
+    pub fn assert_colors(&self) {
+        assert!(color(&self.tree) == Color::Black);
+        self.tree
+            .assert_correctness_with(Self::assert_colors_locally_internal);
+    }
+
+    /// Recolors the root black. This is always legal, and doesn't break any invariant.
+    fn blacken_root(&mut self) {
+        if let Some(node) = self.tree.node_mut() {
+            node.alg_data = Color::Black;
+        }
+    }
+
+    /// Takes the tree apart into the root's left subtree, the root itself, and the root's
+    /// right subtree. Returns [`None`] if the tree is empty.
+    /// The roots of the subtrees are recolored black.
+    fn expose(self) -> Option<Exposed<D>> {
+        let mut node = self.tree.into_node_boxed()?;
+        node.access();
+        let mut left = RBTree {
+            tree: std::mem::replace(&mut node.left, BasicTree::Empty),
+        };
+        let mut right = RBTree {
+            tree: std::mem::replace(&mut node.right, BasicTree::Empty),
+        };
+        left.blacken_root();
+        right.blacken_root();
+        Some((left, node, right))
+    }
+}
+
+impl<D: Data> QueryableTree<D> for RBTree<D> {
+    type ImmTreeData = T;
+
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.segment_summary_imm(locator)
+    }
+
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.search_imm(locator)
+    }
+
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: locators::Locator<D>,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.search_ref(locator)
+    }
+
+    fn iter_locator_imm<L>(
+        &self,
+        locator: L,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, L, T>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.iter_locator_imm(locator)
+    }
+}
+
+impl<D: Data> Default for RBTree<D> {
+    fn default() -> Self {
+        RBTree::new()
+    }
+}
+
+impl<D: Data> SomeTree<D> for RBTree<D> {
+    fn segment_summary<L>(&mut self, locator: L) -> D::Summary
+    where
+        L: crate::Locator<D>,
+    {
+        methods::segment_summary(self, locator)
+    }
+
+    fn act_segment<L>(&mut self, action: D::Action, locator: L)
+    where
+        L: crate::Locator<D>,
+    {
+        if !action.to_reverse() {
+            methods::act_segment(self, action, locator)
+        } else {
+            // split out the middle
+            let mut mid: RBTree<D> = self
+                .slice(locators::LeftEdgeOf(locator.clone()))
+                .split_right()
+                .unwrap();
+
+            let mut walker2 = RBWalker {
+                walker: BasicWalker::new_with_context(
+                    &mut mid.tree,
+                    self.subtree_summary(),
+                    Default::default(),
+                ),
+            };
+            walker2.search_subtree(locators::RightEdgeOf(locator));
+            let right = walker2.split_right().unwrap();
+            drop(walker2);
+
+            // apply action
+            mid.act_subtree(action);
+
+            // glue back together
+            mid.concatenate_right(right);
+            self.concatenate_right(mid);
+        }
+    }
+
+    type TreeData = Color;
+    fn iter_locator<'a, L: locators::Locator<D>>(
+        &'a mut self,
+        locator: L,
+    ) -> basic_tree::iterators::IterLocator<'a, D, L, Color> {
+        iterators::IterLocator::new(&mut self.tree, locator)
+    }
+
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L, Color> {
+        iterators::CursorMut::new(&mut self.tree, locator)
+    }
+
+    fn assert_correctness(&self)
+    where
+        D::Summary: Eq,
+    {
+        assert!(color(&self.tree) == Color::Black);
+        self.tree.assert_correctness_with(|node| {
+            node.assert_correctness_locally();
+            Self::assert_colors_locally_internal(node);
+        });
+    }
+}
+
+impl<'a, D: Data> SomeTreeRef<D> for &'a mut RBTree<D> {
+    type Walker = RBWalker<'a, D>;
+
+    fn walker(self) -> Self::Walker {
+        RBWalker {
+            walker: self.tree.walker(),
+        }
+    }
+}
+
+impl<'a, D: Data> ModifiableTreeRef<D> for &'a mut RBTree<D> {
+    type ModifiableWalker = RBWalker<'a, D>;
+}
+
+impl<'a, D: Data> SplittableTreeRef<D> for &'a mut RBTree<D> {
+    type T = RBTree<D>;
+
+    type SplittableWalker = RBWalker<'a, D>;
+}
+
+derive_SomeEntry! {tree,
+    impl<D: Data> SomeEntry<D> for RBTree<D> {
+        fn assert_correctness_locally(&self)
+        where
+            D::Summary: Eq,
+        {
+            if let Some(node) = self.tree.node() {
+                Self::assert_colors_locally_internal(node);
+                node.assert_correctness_locally();
+            }
+        }
+    }
+}
+
+impl<D: Data> crate::debug::DebugTree<D> for RBTree<D> {
+    type AlgData = T;
+
+    fn basic_tree(&self) -> &BasicTree<D, T> {
+        &self.tree
+    }
+
+    fn describe_alg_data(color: &T) -> Option<String> {
+        Some(format!("{:?}", color))
+    }
+}
+
+impl<D: Data> std::iter::FromIterator<D::Value> for RBTree<D> {
+    /// This takes `O(n)` amortized time: after every insertion the walker
+    /// stays at an ancestor of the inserted node, which is close to the right edge.
+    fn from_iter<I: IntoIterator<Item = D::Value>>(iter: I) -> Self {
+        let mut tree: RBTree<D> = Default::default();
+        let mut walker = tree.walker();
+        for val in iter.into_iter() {
+            while walker.go_right().is_ok() {}
+            walker.insert(val);
+        }
+        drop(walker);
+        tree
+    }
+}
+
+impl<D: Data> IntoIterator for RBTree<D> {
+    type Item = D::Value;
+    type IntoIter = iterators::IntoIter<D, std::ops::RangeFull, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        iterators::IntoIter::new(self.tree, ..)
+    }
+}
+
+/// A walker struct for [`RBTree`].
+pub struct RBWalker<'a, D: Data> {
+    walker: BasicWalker<'a, D, T>,
+}
+
+impl<'a, D: Data> std::ops::Drop for RBWalker<'a, D> {
+    fn drop(&mut self) {
+        self.go_to_root()
+    }
+}
+
+derive_SomeWalker! {walker,
+    impl<'a, D: Data> SomeWalker<D> for RBWalker<'a, D> {
+        fn go_up(&mut self) -> Result<Side, ()> {
+            self.walker.go_up()
+        }
+    }
+}
+
+derive_SomeEntry! {walker,
+    impl<'a, D: Data> SomeEntry<D> for RBWalker<'a, D> {
+        fn assert_correctness_locally(&self)
+        where
+            D::Summary: Eq,
+        {
+            self.walker.assert_correctness_locally();
+            if let Some(node) = self.walker.node() {
+                RBTree::assert_colors_locally_internal(node);
+            }
+        }
+    }
+}
+
+impl<'a, D: Data> RBWalker<'a, D> {
+    /// The color of the current position.
+    fn color(&self) -> Color {
+        color(self.walker.inner())
+    }
+
+    /// Panics if at an empty position.
+    fn set_color(&mut self, color: Color) {
+        self.walker.node_mut().unwrap().alg_data = color;
+    }
+
+    /// The color of the son at the given side of the current node.
+    /// Since the current position is always clean, the sides are correct.
+    fn son_color(&self, side: Side) -> Color {
+        let node = self.walker.node().unwrap();
+        match side {
+            Side::Left => color(&node.left),
+            Side::Right => color(&node.right),
+        }
+    }
+
+    /// Panics if the son is empty.
+    fn set_son_color(&mut self, side: Side, color: Color) {
+        let node = self.walker.node_mut().unwrap();
+        let son = match side {
+            Side::Left => &mut node.left,
+            Side::Right => &mut node.right,
+        };
+        son.node_mut().unwrap().alg_data = color;
+    }
+
+    fn go_to_son(&mut self, side: Side) -> Result<(), ()> {
+        match side {
+            Side::Left => self.go_left(),
+            Side::Right => self.go_right(),
+        }
+    }
+
+    /// Rotates the son at the given side of the current node upwards.
+    /// The walker stays at the same position, i.e., at the son that moved up.
+    fn rot_son_up(&mut self, side: Side) {
+        self.walker
+            .rot_side(side.flip())
+            .expect("son went missing?");
+    }
+
+    /// Fixes a red node with a red parent. Called at the current node,
+    /// which must be red, after it was inserted.
+    /// This does at most two rotations.
+    fn fix_red(&mut self) {
+        loop {
+            // invariant: the current node is red, and it is the only node
+            // that may have a red parent.
+            let side = match self.go_up() {
+                Err(()) => {
+                    // the root is always black
+                    self.set_color(Color::Black);
+                    return;
+                }
+                Ok(side) => side,
+            };
+            if self.color() == Color::Black {
+                return;
+            }
+            // the parent is red, so it isn't the root.
+            let parent_side = self.go_up().unwrap();
+            if self.son_color(parent_side.flip()) == Color::Red {
+                // the uncle is red: recolor and continue upwards
+                self.set_son_color(Side::Left, Color::Black);
+                self.set_son_color(Side::Right, Color::Black);
+                self.set_color(Color::Red);
+                continue;
+            }
+
+            if side != parent_side {
+                // zig-zag case: rotate the parent first
+                self.go_to_son(parent_side).unwrap();
+                self.rot_son_up(side);
+                self.go_up().unwrap();
+            }
+            // zig-zig case
+            self.rot_son_up(parent_side);
+            self.set_color(Color::Black);
+            self.set_son_color(parent_side.flip(), Color::Red);
+            return;
+        }
+    }
+
+    /// Fixes the current position, which has one less black node
+    /// on its paths than its sibling has.
+    /// This does at most three rotations.
+    fn fix_double_black(&mut self) {
+        loop {
+            let side = match self.go_up() {
+                Err(()) => return, // reached the root
+                Ok(side) => side,
+            };
+            // the sibling can't be empty, since its black height is at least one.
+            if self.son_color(side.flip()) == Color::Red {
+                // make the sibling black, keeping the current node as the parent
+                self.rot_son_up(side.flip());
+                self.set_color(Color::Black);
+                self.go_to_son(side).unwrap();
+                self.set_color(Color::Red);
+            }
+
+            // the sibling is now black
+            self.go_to_son(side.flip()).unwrap();
+            let near = self.son_color(side);
+            let far = self.son_color(side.flip());
+            if near == Color::Black && far == Color::Black {
+                self.set_color(Color::Red);
+                self.go_up().unwrap();
+                if self.color() == Color::Red {
+                    self.set_color(Color::Black);
+                    return;
+                }
+                continue;
+            }
+
+            if far == Color::Black {
+                // the near nephew is red: rotate it to be the far nephew
+                self.rot_son_up(side);
+                self.set_color(Color::Black);
+                self.set_son_color(side.flip(), Color::Red);
+            }
+            self.go_up().unwrap();
+
+            // the far nephew is red
+            let parent_color = self.color();
+            self.rot_son_up(side.flip());
+            self.set_color(parent_color);
+            self.set_son_color(Side::Left, Color::Black);
+            self.set_son_color(Side::Right, Color::Black);
+            return;
+        }
+    }
+
+    // TODO: specify where the walker will be.
+    /// Deletes a node and returns it with the box.
+    fn delete_boxed(&mut self) -> Option<Box<BasicNode<D, T>>> {
+        let node = self.walker.node()?;
+        if !node.left.is_empty() && !node.right.is_empty() {
+            // swap the value with the next value, and delete that node instead
+            let depth = self.depth();
+            self.go_right().unwrap();
+            while self.go_left().is_ok() {}
+            let res = self.go_up();
+            assert_eq!(res, Ok(Side::Left));
+            let mut next_node = self.walker.take_subtree().into_node_boxed().unwrap();
+
+            while self.depth() > depth {
+                self.go_up().unwrap();
+            }
+            std::mem::swap(
+                &mut next_node.node_value,
+                &mut self.walker.node_mut().unwrap().node_value,
+            );
+
+            self.go_right().unwrap();
+            while self.go_left().is_ok() {}
+            next_node.rebuild();
+            self.walker
+                .put_subtree(BasicTree::Root(next_node))
+                .unwrap();
+        }
+
+        // the current node has at most one son
+        let mut node = self.walker.take_subtree().into_node_boxed().unwrap();
+        let son = if node.left.is_empty() {
+            std::mem::replace(&mut node.right, BasicTree::Empty)
+        } else {
+            std::mem::replace(&mut node.left, BasicTree::Empty)
+        };
+        self.walker.put_subtree(son).unwrap();
+        if node.alg_data == Color::Black {
+            if self.color() == Color::Red {
+                self.set_color(Color::Black);
+            } else {
+                self.fix_double_black();
+            }
+        }
+        Some(node)
+    }
+}
+
+impl<'a, D: Data> ModifiableWalker<D> for RBWalker<'a, D> {
+    /// Inserts the value into the tree at the current empty position.
+    /// If the current position is not empty, return [`None`].
+    /// When the function returns, the walker will be at a position which is an ancestor of the
+    /// newly inserted node.
+    fn insert(&mut self, val: D::Value) -> Option<()> {
+        self.walker.insert_with_alg_data(val, Color::Red)?;
+        self.fix_red();
+        Some(())
+    }
+
+    // TODO: specify where the walker will be.
+    fn delete(&mut self) -> Option<D::Value> {
+        Some(self.delete_boxed()?.node_value)
+    }
+}
+
+impl<'a, D: Data> SplittableWalker<D> for RBWalker<'a, D> {
+    type T = RBTree<D>;
+
+    /// Will only do anything if the current position is empty.
+    /// If it is empty, it will split the tree: the elements
+    /// to the left will remain, and the elements to the right
+    /// will be put in the new output tree.
+    /// The walker will be at the root after this operation, if it succeeds.
+    ///
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: RBTree<StdNum> = (17..88).collect();
+    /// let mut tree2 = tree.slice(7..7).split_right().unwrap();
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    /// # tree2.assert_correctness();
+    ///```
+    fn split_right(&mut self) -> Option<Self::T> {
+        if !self.is_empty() {
+            return None;
+        }
+        let mut left = RBTree::new();
+        let mut right = RBTree::new();
+
+        while let Ok(side) = self.walker.go_up() {
+            let mut node = self.walker.take_subtree().into_node_boxed().unwrap();
+            match side {
+                Side::Left => {
+                    assert!(node.left.is_empty());
+                    let auxiliary_right = RBTree { tree: node.right };
+                    node.right = BasicTree::Empty;
+                    right.concatenate_boxed_middle_right(node, auxiliary_right);
+                }
+                Side::Right => {
+                    assert!(node.right.is_empty());
+                    let auxiliary_left = RBTree { tree: node.left };
+                    node.left = BasicTree::Empty;
+                    left.concatenate_boxed_middle_left(auxiliary_left, node);
+                }
+            }
+        }
+
+        // the `self` tree is empty by this point.
+        self.walker.put_subtree(left.tree).unwrap();
+        Some(right)
+    }
+
+    /// Will only do anything if the current position is empty.
+    /// If it is empty, it will split the tree: the elements
+    /// to the left will remain, and the elements to the right
+    /// will be put in the new output tree.
+    /// The walker will be at the root after this operation, if it succeeds.
+    ///
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: RBTree<StdNum> = (17..88).collect();
+    /// let mut tree2 = tree.slice(7..7).split_left().unwrap();
+    ///
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    /// # tree2.assert_correctness();
+    ///```
+    fn split_left(&mut self) -> Option<Self::T> {
+        let mut right = self.split_right()?;
+        std::mem::swap(&mut right.tree, self.walker.inner_mut());
+        Some(right)
+    }
+}
+
+impl<D: Data> RBTree<D> {
+    /// Concatenates the trees together, in place, with a given value for the middle.
+    /// Complexity: `O(log n)`.
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: RBTree<StdNum> = (17..=89).collect();
+    /// let tree2: RBTree<StdNum> = (13..=25).collect();
+    /// tree.concatenate_middle_right(5, tree2);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    pub fn concatenate_middle_right(&mut self, mid: D::Value, right: RBTree<D>) {
+        let node = BasicNode::new_alg(mid, Color::Red);
+        self.concatenate_boxed_middle_right(Box::new(node), right);
+    }
+
+    fn concatenate_boxed_middle_right(
+        &mut self,
+        mut mid: Box<BasicNode<D, T>>,
+        mut right: RBTree<D>,
+    ) {
+        self.blacken_root();
+        right.blacken_root();
+        let right_height = black_height(&right.tree);
+        let mut height = black_height(&self.tree);
+        if height < right_height {
+            std::mem::swap(self, &mut right);
+            self.concatenate_boxed_middle_left(right, mid);
+            return;
+        }
+        // find a black position on the right edge with the same black height as `right`
+        let mut walker = self.walker();
+        while height > right_height || walker.color() == Color::Red {
+            if walker.color() == Color::Black {
+                height -= 1;
+            }
+            walker.go_right().unwrap();
+        }
+        mid.alg_data = Color::Red;
+        mid.left = walker.walker.take_subtree();
+        mid.right = right.tree;
+        mid.rebuild();
+        walker.walker.put_subtree(BasicTree::Root(mid)).unwrap();
+        walker.fix_red();
+    }
+
+    /// Concatenates the trees together, in place, with a given value for the middle.
+    /// Complexity: `O(log n)`.
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree1: RBTree<StdNum> = (17..=89).collect();
+    /// let mut tree2: RBTree<StdNum> = (13..=25).collect();
+    /// tree2.concatenate_middle_left(tree1, 5);
+    ///
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+    /// # tree2.assert_correctness();
+    ///```
+    pub fn concatenate_middle_left(&mut self, left: RBTree<D>, mid: D::Value) {
+        let node = BasicNode::new_alg(mid, Color::Red);
+        self.concatenate_boxed_middle_left(left, Box::new(node));
+    }
+
+    fn concatenate_boxed_middle_left(
+        &mut self,
+        mut left: RBTree<D>,
+        mut mid: Box<BasicNode<D, T>>,
+    ) {
+        self.blacken_root();
+        left.blacken_root();
+        let left_height = black_height(&left.tree);
+        let mut height = black_height(&self.tree);
+        if height < left_height {
+            std::mem::swap(self, &mut left);
+            self.concatenate_boxed_middle_right(mid, left);
+            return;
+        }
+        // find a black position on the left edge with the same black height as `left`
+        let mut walker = self.walker();
+        while height > left_height || walker.color() == Color::Red {
+            if walker.color() == Color::Black {
+                height -= 1;
+            }
+            walker.go_left().unwrap();
+        }
+        mid.alg_data = Color::Red;
+        mid.right = walker.walker.take_subtree();
+        mid.left = left.tree;
+        mid.rebuild();
+        walker.walker.put_subtree(BasicTree::Root(mid)).unwrap();
+        walker.fix_red();
+    }
+}
+
+impl<D: Data> RBTree<D> {
+    /// Computes the union of two trees, ordered by keys.
+    /// We order the resulting tree based on the `D::Value: Keyed` instance, assuming that
+    /// the values in the existing trees are also in the correct order.
+    ///
+    /// Values with equal keys are all kept, and the values from `tree2` are placed after
+    /// the values of `self` with the same key.
+    ///
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = RBTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.union(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3,4,4,5,5,6,6,7,8].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn union(&mut self, tree2: RBTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = union_internal(tree1, tree2);
+    }
+
+    /// Computes the intersection of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys appear in `tree2`. Values with equal keys in `self` are all kept.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = RBTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.intersection(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn intersection(&mut self, tree2: RBTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = intersection_internal(tree1, tree2);
+    }
+
+    /// Computes the difference of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys don't appear in `tree2`.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, red_black::RBTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = RBTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.difference(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn difference(&mut self, tree2: RBTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = difference_internal(tree1, tree2);
+    }
+}
+
+/// Splits the tree into the values with keys smaller than `key`, the values with keys equal
+/// to `key`, and the values with keys bigger than `key`.
+fn split_by_key<D: Data>(
+    mut tree: RBTree<D>,
+    key: &<D::Value as Keyed>::Key,
+) -> (RBTree<D>, RBTree<D>, RBTree<D>)
+where
+    D::Value: Keyed,
+{
+    let mut mid = tree
+        .slice(locators::LeftEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    let right = mid
+        .slice(locators::RightEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    (tree, mid, right)
+}
+
+fn union_internal<D: Data>(tree1: RBTree<D>, tree2: RBTree<D>) -> RBTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree2;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let mut left1 = tree1;
+    let right1 = left1
+        .slice(locators::RightEdgeOf(locators::ByKey((mid.node_value.get_key(),))))
+        .split_right()
+        .unwrap();
+
+    let mut res = union_internal(left1, left2);
+    res.concatenate_boxed_middle_right(mid, union_internal(right1, right2));
+    res
+}
+
+fn intersection_internal<D: Data>(tree1: RBTree<D>, tree2: RBTree<D>) -> RBTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return RBTree::new(),
+        Some(parts) => parts,
+    };
+    let (left1, equal1, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = intersection_internal(left1, left2);
+    res.concatenate_right(equal1);
+    res.concatenate_right(intersection_internal(right1, right2));
+    res
+}
+
+fn difference_internal<D: Data>(tree1: RBTree<D>, tree2: RBTree<D>) -> RBTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let (left1, _, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = difference_internal(left1, left2);
+    res.concatenate_right(difference_internal(right1, right2));
+    res
+}
+
+impl<D: Data> SetOperations<D> for RBTree<D> {
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        RBTree::union(self, other);
+    }
+
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        RBTree::intersection(self, other);
+    }
+
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        RBTree::difference(self, other);
+    }
+}
+
+impl<D: Data> ConcatenableTree<D> for RBTree<D> {
+    /// Concatenates the trees together, in place.
+    /// Complexity: `O(log n)`.
+    ///```
+    /// use grove::{SomeTree, ConcatenableTree, red_black::RBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: RBTree<StdNum> = (17..=89).collect();
+    /// let tree2: RBTree<StdNum> = (13..=25).collect();
+    /// tree.concatenate_right(tree2);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(13..=25).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn concatenate_right(&mut self, mut right: Self) {
+        if !right.is_empty() {
+            let mut walker = right.search(locators::LeftEdgeOf(..));
+            walker.go_up().unwrap();
+            let mid = walker.delete_boxed().unwrap();
+            drop(walker);
+            self.concatenate_boxed_middle_right(mid, right);
+        }
+    }
+}
+
+/// Concatenates the trees together, in place, with a given value for the middle.
+/// Complexity: `O(log n)`.
+///```
+/// use grove::{SomeTree, red_black::RBTree, red_black::concatenate_with_middle};
+/// use grove::example_data::StdNum;
+///
+/// let tree1: RBTree<StdNum> = (17..=89).collect();
+/// let tree2: RBTree<StdNum> = (13..=25).collect();
+/// let mut tree3 = concatenate_with_middle(tree1, 5, tree2);
+///
+/// assert_eq!(tree3.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+/// # tree3.assert_correctness();
+///```
+pub fn concatenate_with_middle<D: Data>(
+    mut left: RBTree<D>,
+    mid: D::Value,
+    right: RBTree<D>,
+) -> RBTree<D> {
+    left.concatenate_middle_right(mid, right);
+    left
+}
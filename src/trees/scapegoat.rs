@@ -0,0 +1,851 @@
+//! Implementation of scapegoat trees.
+//! Balanced by rebuilding whole subtrees when they get too deep, this is an amortized balancing
+//! algorithm that stores no balancing data in the nodes at all. Instead, only the tree's size and
+//! maximum size are kept.
+//!
+//! Since the nodes don't know the sizes of their subtrees, these are counted when needed:
+//! when looking for a scapegoat, and when splitting and concatenating trees. Splitting and
+//! concatenating take time linear in the size of the smaller part.
+
+use crate::locators;
+
+use super::basic_tree::*;
+use super::*;
+
+/// A tree taken apart into its root's left subtree, its root, and its root's right subtree.
+type Exposed<D> = (ScapegoatTree<D>, Box<BasicNode<D>>, ScapegoatTree<D>);
+
+/// The balance factor of the tree. A subtree is rebuilt when one of its sons
+/// contains more than `ALPHA` of its nodes.
+const ALPHA: f64 = 0.7;
+
+/// The maximum depth a node is allowed to have in a tree of the given size,
+/// before a scapegoat is searched for.
+fn max_depth(size: usize) -> usize {
+    if size <= 1 {
+        return 0;
+    }
+    ((size as f64).ln() / (1.0 / ALPHA).ln()).floor() as usize
+}
+
+/// Counts the nodes of a tree. Takes `O(n)` time.
+fn count_nodes<D: Data>(tree: &BasicTree<D>) -> usize {
+    let mut res = 0;
+    let mut stack = vec![tree];
+    while let Some(tree) = stack.pop() {
+        if let Some(node) = tree.node() {
+            res += 1;
+            stack.push(&node.left);
+            stack.push(&node.right);
+        }
+    }
+    res
+}
+
+/// Returns the sizes of two trees, given the sum of their sizes.
+/// Counts the nodes of both trees at the same pace, and stops as soon as one of them
+/// has been counted completely. Therefore, this takes time linear in the size of the smaller tree.
+fn count_sizes<D: Data>(
+    tree1: &BasicTree<D>,
+    tree2: &BasicTree<D>,
+    total: usize,
+) -> (usize, usize) {
+    let mut stacks = [vec![tree1], vec![tree2]];
+    let mut counts = [0, 0];
+    loop {
+        for i in 0..2 {
+            // find the next node of this tree
+            loop {
+                match stacks[i].pop() {
+                    None => {
+                        let other = total - counts[i];
+                        return if i == 0 {
+                            (counts[0], other)
+                        } else {
+                            (other, counts[1])
+                        };
+                    }
+                    Some(tree) => {
+                        if let Some(node) = tree.node() {
+                            counts[i] += 1;
+                            stacks[i].push(&node.left);
+                            stacks[i].push(&node.right);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the height of the tree, i.e., the number of nodes in its longest path from the root.
+/// Takes `O(n)` time.
+fn height<D: Data>(tree: &BasicTree<D>) -> usize {
+    let mut res = 0;
+    let mut stack = vec![(tree, 0)];
+    while let Some((tree, depth)) = stack.pop() {
+        if let Some(node) = tree.node() {
+            res = std::cmp::max(res, depth + 1);
+            stack.push((&node.left, depth + 1));
+            stack.push((&node.right, depth + 1));
+        }
+    }
+    res
+}
+
+/// Takes apart the tree into its nodes, in order. Every node is accessed
+/// before its sons are taken from it, so the nodes are all clean.
+fn flatten<D: Data>(tree: BasicTree<D>) -> Vec<Box<BasicNode<D>>> {
+    let mut res = vec![];
+    let mut stack: Vec<Box<BasicNode<D>>> = vec![];
+    let mut current = tree;
+    loop {
+        match current.into_node_boxed() {
+            Some(mut node) => {
+                node.access();
+                current = std::mem::replace(&mut node.left, BasicTree::Empty);
+                stack.push(node);
+            }
+            None => match stack.pop() {
+                None => break,
+                Some(mut node) => {
+                    current = std::mem::replace(&mut node.right, BasicTree::Empty);
+                    res.push(node);
+                }
+            },
+        }
+    }
+    res
+}
+
+/// Rebuilds the tree into a perfectly balanced tree, reusing its nodes.
+/// Takes `O(n)` time.
+fn rebuild_balanced<D: Data>(tree: BasicTree<D>) -> BasicTree<D> {
+    let nodes = flatten(tree);
+    let count = nodes.len();
+    basic_tree::build_balanced(&mut nodes.into_iter(), count, |_| ())
+}
+
+fn go_to_son<D: Data, W: SomeWalker<D>>(walker: &mut W, side: Side) -> Result<(), ()> {
+    match side {
+        Side::Left => walker.go_left(),
+        Side::Right => walker.go_right(),
+    }
+}
+
+/// A scapegoat tree. Balanced by rebuilding whole subtrees when they get too deep,
+/// this is an amortized balancing algorithm that stores no balancing data in the nodes.
+pub struct ScapegoatTree<D: Data> {
+    tree: BasicTree<D>,
+    size: usize,
+    /// The maximum size the tree had since the last time it was rebuilt completely,
+    /// split or concatenated.
+    max_size: usize,
+}
+
+impl<D: Data> ScapegoatTree<D> {
+    /// Creates an empty [`ScapegoatTree`].
+    pub fn new() -> Self {
+        ScapegoatTree {
+            tree: BasicTree::Empty,
+            size: 0,
+            max_size: 0,
+        }
+    }
+
+    /// Returns the amount of values in the tree.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Asserts that the size counters of the tree are correct.
+    /// Otherwise, panics.
+    pub fn assert_size(&self) {
+        assert_eq!(count_nodes(&self.tree), self.size);
+        assert!(self.size <= self.max_size);
+    }
+
+    /// Takes the tree apart into the root's left subtree, the root itself, and the root's
+    /// right subtree. Returns [`None`] if the tree is empty.
+    /// Takes time linear in the size of the smaller subtree.
+    fn expose(self) -> Option<Exposed<D>> {
+        let mut node = self.tree.into_node_boxed()?;
+        node.access();
+        let left = std::mem::replace(&mut node.left, BasicTree::Empty);
+        let right = std::mem::replace(&mut node.right, BasicTree::Empty);
+        let (left_size, right_size) = count_sizes(&left, &right, self.size - 1);
+        let left = ScapegoatTree {
+            tree: left,
+            size: left_size,
+            max_size: left_size,
+        };
+        let right = ScapegoatTree {
+            tree: right,
+            size: right_size,
+            max_size: right_size,
+        };
+        Some((left, node, right))
+    }
+}
+
+impl<D: Data> QueryableTree<D> for ScapegoatTree<D> {
+    type ImmTreeData = ();
+
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.segment_summary_imm(locator)
+    }
+
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.search_imm(locator)
+    }
+
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: locators::Locator<D>,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.search_ref(locator)
+    }
+
+    fn iter_locator_imm<L>(
+        &self,
+        locator: L,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, L, ()>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.iter_locator_imm(locator)
+    }
+}
+
+impl<D: Data> Default for ScapegoatTree<D> {
+    fn default() -> Self {
+        ScapegoatTree::new()
+    }
+}
+
+impl<D: Data> SomeTree<D> for ScapegoatTree<D> {
+    fn segment_summary<L>(&mut self, locator: L) -> D::Summary
+    where
+        L: crate::Locator<D>,
+    {
+        methods::segment_summary(self, locator)
+    }
+
+    fn act_segment<L>(&mut self, action: D::Action, locator: L)
+    where
+        L: crate::Locator<D>,
+    {
+        if !action.to_reverse() {
+            methods::act_segment(self, action, locator)
+        } else {
+            // split out the middle
+            let mut mid: ScapegoatTree<D> = self
+                .slice(locators::LeftEdgeOf(locator.clone()))
+                .split_right()
+                .unwrap();
+
+            let mut walker2 = ScapegoatWalker {
+                walker: BasicWalker::new_with_context(
+                    &mut mid.tree,
+                    self.subtree_summary(),
+                    Default::default(),
+                ),
+                size: &mut mid.size,
+                max_size: &mut mid.max_size,
+            };
+            walker2.search_subtree(locators::RightEdgeOf(locator));
+            let right = walker2.split_right().unwrap();
+            drop(walker2);
+
+            // apply action
+            mid.act_subtree(action);
+
+            // glue back together
+            mid.concatenate_right(right);
+            self.concatenate_right(mid);
+        }
+    }
+
+    type TreeData = ();
+    fn iter_locator<'a, L: locators::Locator<D>>(
+        &'a mut self,
+        locator: L,
+    ) -> basic_tree::iterators::IterLocator<'a, D, L> {
+        iterators::IterLocator::new(&mut self.tree, locator)
+    }
+
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L> {
+        iterators::CursorMut::new(&mut self.tree, locator)
+    }
+
+    fn assert_correctness(&self)
+    where
+        D::Summary: Eq,
+    {
+        self.tree.assert_correctness();
+        self.assert_size();
+    }
+}
+
+impl<'a, D: Data> SomeTreeRef<D> for &'a mut ScapegoatTree<D> {
+    type Walker = ScapegoatWalker<'a, D>;
+
+    fn walker(self) -> Self::Walker {
+        ScapegoatWalker {
+            walker: self.tree.walker(),
+            size: &mut self.size,
+            max_size: &mut self.max_size,
+        }
+    }
+}
+
+impl<'a, D: Data> ModifiableTreeRef<D> for &'a mut ScapegoatTree<D> {
+    type ModifiableWalker = ScapegoatWalker<'a, D>;
+}
+
+impl<'a, D: Data> SplittableTreeRef<D> for &'a mut ScapegoatTree<D> {
+    type T = ScapegoatTree<D>;
+
+    type SplittableWalker = ScapegoatWalker<'a, D>;
+}
+
+derive_SomeEntry! {tree,
+    impl<D: Data> SomeEntry<D> for ScapegoatTree<D> {
+        fn assert_correctness_locally(&self)
+        where
+            D::Summary: Eq,
+        {
+            self.tree.assert_correctness_locally();
+        }
+    }
+}
+
+impl<D: Data> crate::debug::DebugTree<D> for ScapegoatTree<D> {
+    type AlgData = ();
+
+    fn basic_tree(&self) -> &BasicTree<D> {
+        &self.tree
+    }
+
+    fn describe_alg_data(_: &()) -> Option<String> {
+        None
+    }
+}
+
+impl<D: Data> std::iter::FromIterator<D::Value> for ScapegoatTree<D> {
+    /// Builds a perfectly balanced tree.
+    /// This takes `O(n)` worst-case time.
+    fn from_iter<I: IntoIterator<Item = D::Value>>(iter: I) -> Self {
+        let nodes: Vec<_> = iter
+            .into_iter()
+            .map(|val| Box::new(BasicNode::new(val)))
+            .collect();
+        let size = nodes.len();
+        ScapegoatTree {
+            tree: basic_tree::build_balanced(&mut nodes.into_iter(), size, |_| ()),
+            size,
+            max_size: size,
+        }
+    }
+}
+
+impl<D: Data> IntoIterator for ScapegoatTree<D> {
+    type Item = D::Value;
+    type IntoIter = iterators::IntoIter<D, std::ops::RangeFull>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        iterators::IntoIter::new(self.tree, ..)
+    }
+}
+
+/// A walker struct for [`ScapegoatTree`].
+pub struct ScapegoatWalker<'a, D: Data> {
+    walker: BasicWalker<'a, D>,
+    size: &'a mut usize,
+    max_size: &'a mut usize,
+}
+
+derive_SomeWalker! {walker,
+    impl<'a, D: Data> SomeWalker<D> for ScapegoatWalker<'a, D> {
+        fn go_up(&mut self) -> Result<Side, ()> {
+            self.walker.go_up()
+        }
+    }
+}
+
+derive_SomeEntry! {walker,
+    impl<'a, D: Data> SomeEntry<D> for ScapegoatWalker<'a, D> {
+        fn assert_correctness_locally(&self)
+        where
+            D::Summary: Eq,
+        {
+            self.walker.assert_correctness_locally();
+        }
+    }
+}
+
+impl<'a, D: Data> ScapegoatWalker<'a, D> {
+    /// Rebuilds the current subtree into a perfectly balanced tree.
+    fn rebuild_subtree(&mut self) {
+        let tree = self.walker.take_subtree();
+        self.walker.put_subtree(rebuild_balanced(tree)).unwrap();
+    }
+
+    /// Called when the current subtree, of size `size`, contains a node that is too deep.
+    /// Goes up until finding an ancestor in which one of the sons is too big,
+    /// and rebuilds it. The sizes of the ancestors are found by counting the nodes
+    /// of the siblings on the way up.
+    fn rebuild_scapegoat(&mut self, mut size: usize) {
+        while let Ok(side) = self.walker.go_up() {
+            let node = self.walker.node().unwrap();
+            let sibling = match side {
+                Side::Left => &node.right,
+                Side::Right => &node.left,
+            };
+            let node_size = size + count_nodes(sibling) + 1;
+            if size as f64 > ALPHA * node_size as f64 {
+                break;
+            }
+            size = node_size;
+        }
+        // if no scapegoat was found, we are at the root, and rebuild the whole tree.
+        self.rebuild_subtree();
+    }
+}
+
+impl<'a, D: Data> ModifiableWalker<D> for ScapegoatWalker<'a, D> {
+    /// Inserts the value into the tree at the current empty position.
+    /// If the current position is not empty, return [`None`].
+    /// When the function returns, the walker will be at a position which is an ancestor of the
+    /// newly inserted node, or at the newly inserted node itself.
+    fn insert(&mut self, val: D::Value) -> Option<()> {
+        self.walker.insert(val)?;
+        *self.size += 1;
+        *self.max_size = std::cmp::max(*self.max_size, *self.size);
+        if self.depth() > max_depth(*self.size) {
+            self.rebuild_scapegoat(1);
+        }
+        Some(())
+    }
+
+    /// Removes the current value from the tree, and returns it.
+    /// If currently at an empty position, returns [`None`].
+    /// If the tree had to be rebuilt, the walker will be at the root.
+    /// Otherwise, it will stay at the same position.
+    fn delete(&mut self) -> Option<D::Value> {
+        let res = self.walker.delete()?;
+        *self.size -= 1;
+        if (*self.size as f64) < ALPHA * *self.max_size as f64 {
+            self.walker.go_to_root();
+            self.rebuild_subtree();
+            *self.max_size = *self.size;
+        }
+        Some(res)
+    }
+}
+
+impl<'a, D: Data> SplittableWalker<D> for ScapegoatWalker<'a, D> {
+    type T = ScapegoatTree<D>;
+
+    /// Will only do anything if the current position is empty.
+    /// If it is empty, it will split the tree: the elements
+    /// to the left will remain, and the elements to the right
+    /// will be put in the new output tree.
+    /// The walker will be at the root after this operation, if it succeeds.
+    /// The maximum sizes of both trees are set to their current sizes.
+    ///
+    /// Since the nodes don't store their sizes, the sizes of the two parts are counted.
+    /// This takes `O(log n + k)` time, where `k` is the size of the smaller part.
+    ///
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: ScapegoatTree<StdNum> = (17..88).collect();
+    /// let mut tree2 = tree.slice(7..7).split_right().unwrap();
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    /// # tree2.assert_correctness();
+    ///```
+    fn split_right(&mut self) -> Option<Self::T> {
+        if !self.is_empty() {
+            return None;
+        }
+        let mut left = BasicTree::Empty;
+        let mut right = BasicTree::Empty;
+
+        // every node is put above the parts that were already split,
+        // so no node gets deeper than it was.
+        while let Ok(side) = self.walker.go_up() {
+            let mut node = self.walker.take_subtree().into_node_boxed().unwrap();
+            match side {
+                Side::Left => {
+                    assert!(node.left.is_empty());
+                    node.left = right;
+                    node.rebuild();
+                    right = BasicTree::Root(node);
+                }
+                Side::Right => {
+                    assert!(node.right.is_empty());
+                    node.right = left;
+                    node.rebuild();
+                    left = BasicTree::Root(node);
+                }
+            }
+        }
+
+        // the `self` tree is empty by this point.
+        let (left_size, right_size) = count_sizes(&left, &right, *self.size);
+        self.walker.put_subtree(left).unwrap();
+        *self.size = left_size;
+        *self.max_size = left_size;
+        Some(ScapegoatTree {
+            tree: right,
+            size: right_size,
+            max_size: right_size,
+        })
+    }
+
+    /// Will only do anything if the current position is empty.
+    /// If it is empty, it will split the tree: the elements
+    /// to the left will remain, and the elements to the right
+    /// will be put in the new output tree.
+    /// The walker will be at the root after this operation, if it succeeds.
+    /// The maximum sizes of both trees are set to their current sizes.
+    ///
+    /// Since the nodes don't store their sizes, the sizes of the two parts are counted.
+    /// This takes `O(log n + k)` time, where `k` is the size of the smaller part.
+    ///
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: ScapegoatTree<StdNum> = (17..88).collect();
+    /// let mut tree2 = tree.slice(7..7).split_left().unwrap();
+    ///
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    /// # tree2.assert_correctness();
+    ///```
+    fn split_left(&mut self) -> Option<Self::T> {
+        let mut right = self.split_right()?;
+        std::mem::swap(&mut right.tree, self.walker.inner_mut());
+        std::mem::swap(&mut right.size, self.size);
+        std::mem::swap(&mut right.max_size, self.max_size);
+        Some(right)
+    }
+}
+
+impl<D: Data> ScapegoatTree<D> {
+    /// Concatenates the trees together, in place, with a given value for the middle.
+    /// The middle value and the smaller tree are put at the end of the inner spine of the
+    /// bigger tree. If that makes the tree too deep, a scapegoat is rebuilt, like after an
+    /// insertion.
+    /// Complexity: `O(log n + k)` amortized, where `k` is the size of the smaller tree.
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: ScapegoatTree<StdNum> = (17..=89).collect();
+    /// let tree2: ScapegoatTree<StdNum> = (13..=25).collect();
+    /// tree.concatenate_middle_right(5, tree2);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    pub fn concatenate_middle_right(&mut self, mid: D::Value, right: ScapegoatTree<D>) {
+        self.concatenate_boxed_middle_right(Box::new(BasicNode::new(mid)), right);
+    }
+
+    /// Concatenates the trees together, in place, with a given value for the middle.
+    /// The middle value and the smaller tree are put at the end of the inner spine of the
+    /// bigger tree. If that makes the tree too deep, a scapegoat is rebuilt, like after an
+    /// insertion.
+    /// Complexity: `O(log n + k)` amortized, where `k` is the size of the smaller tree.
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree1: ScapegoatTree<StdNum> = (17..=89).collect();
+    /// let mut tree2: ScapegoatTree<StdNum> = (13..=25).collect();
+    /// tree2.concatenate_middle_left(tree1, 5);
+    ///
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+    /// # tree2.assert_correctness();
+    ///```
+    pub fn concatenate_middle_left(&mut self, mut left: ScapegoatTree<D>, mid: D::Value) {
+        std::mem::swap(self, &mut left);
+        self.concatenate_middle_right(mid, left);
+    }
+
+    /// `mid` should be a clean node with no sons.
+    fn concatenate_boxed_middle_right(
+        &mut self,
+        mut mid: Box<BasicNode<D>>,
+        right: ScapegoatTree<D>,
+    ) {
+        let size = self.size + right.size + 1;
+        let small_size = std::cmp::min(self.size, right.size);
+        // descend along the inner spine of the bigger tree
+        let (spine_side, small) = if self.size >= right.size {
+            (Side::Right, right.tree)
+        } else {
+            let left = std::mem::replace(&mut self.tree, right.tree);
+            (Side::Left, left)
+        };
+        let small_height = height(&small);
+        match spine_side {
+            Side::Right => mid.right = small,
+            Side::Left => mid.left = small,
+        }
+        mid.rebuild();
+        self.size = size;
+        self.max_size = size;
+
+        let mut walker = self.walker();
+        while go_to_son(&mut walker, spine_side).is_ok() {}
+        walker.walker.put_subtree(BasicTree::Root(mid)).unwrap();
+        if walker.depth() + small_height > max_depth(size) {
+            walker.rebuild_scapegoat(small_size + 1);
+        }
+    }
+}
+
+impl<D: Data> ScapegoatTree<D> {
+    /// Computes the union of two trees, ordered by keys.
+    /// We order the resulting tree based on the `D::Value: Keyed` instance, assuming that
+    /// the values in the existing trees are also in the correct order.
+    ///
+    /// Values with equal keys are all kept, and the values from `tree2` are placed after
+    /// the values of `self` with the same key.
+    ///
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = ScapegoatTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.union(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3,4,4,5,5,6,6,7,8].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, this does `O(k)` splits and joins of subtrees.
+    /// Since the nodes don't store their sizes, these take time linear in the sizes of the
+    /// smaller parts, which sums up to `O((n+k)*log(k))` amortized time.
+    pub fn union(&mut self, tree2: ScapegoatTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = union_internal(tree1, tree2);
+    }
+
+    /// Computes the intersection of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys appear in `tree2`. Values with equal keys in `self` are all kept.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = ScapegoatTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.intersection(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, this does `O(k)` splits and joins of subtrees.
+    /// Since the nodes don't store their sizes, these take time linear in the sizes of the
+    /// smaller parts, which sums up to `O((n+k)*log(k))` amortized time.
+    pub fn intersection(&mut self, tree2: ScapegoatTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = intersection_internal(tree1, tree2);
+    }
+
+    /// Computes the difference of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys don't appear in `tree2`.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = ScapegoatTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.difference(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, this does `O(k)` splits and joins of subtrees.
+    /// Since the nodes don't store their sizes, these take time linear in the sizes of the
+    /// smaller parts, which sums up to `O((n+k)*log(k))` amortized time.
+    pub fn difference(&mut self, tree2: ScapegoatTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = difference_internal(tree1, tree2);
+    }
+}
+
+/// Splits the tree into the values with keys smaller than `key`, the values with keys equal
+/// to `key`, and the values with keys bigger than `key`.
+fn split_by_key<D: Data>(
+    mut tree: ScapegoatTree<D>,
+    key: &<D::Value as Keyed>::Key,
+) -> (ScapegoatTree<D>, ScapegoatTree<D>, ScapegoatTree<D>)
+where
+    D::Value: Keyed,
+{
+    let mut mid = tree
+        .slice(locators::LeftEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    let right = mid
+        .slice(locators::RightEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    (tree, mid, right)
+}
+
+fn union_internal<D: Data>(tree1: ScapegoatTree<D>, tree2: ScapegoatTree<D>) -> ScapegoatTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree2;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let mut left1 = tree1;
+    let right1 = left1
+        .slice(locators::RightEdgeOf(locators::ByKey((mid.node_value.get_key(),))))
+        .split_right()
+        .unwrap();
+
+    let mut res = union_internal(left1, left2);
+    res.concatenate_boxed_middle_right(mid, union_internal(right1, right2));
+    res
+}
+
+fn intersection_internal<D: Data>(
+    tree1: ScapegoatTree<D>,
+    tree2: ScapegoatTree<D>,
+) -> ScapegoatTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return ScapegoatTree::new(),
+        Some(parts) => parts,
+    };
+    let (left1, equal1, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = intersection_internal(left1, left2);
+    res.concatenate_right(equal1);
+    res.concatenate_right(intersection_internal(right1, right2));
+    res
+}
+
+fn difference_internal<D: Data>(
+    tree1: ScapegoatTree<D>,
+    tree2: ScapegoatTree<D>,
+) -> ScapegoatTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let (left1, _, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = difference_internal(left1, left2);
+    res.concatenate_right(difference_internal(right1, right2));
+    res
+}
+
+impl<D: Data> SetOperations<D> for ScapegoatTree<D> {
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        ScapegoatTree::union(self, other);
+    }
+
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        ScapegoatTree::intersection(self, other);
+    }
+
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        ScapegoatTree::difference(self, other);
+    }
+}
+
+impl<D: Data> ConcatenableTree<D> for ScapegoatTree<D> {
+    /// Concatenates the trees together, in place.
+    /// Complexity: `O(log n)`, or `O(n)` if the tree is rebuilt.
+    ///```
+    /// use grove::{SomeTree, ConcatenableTree, scapegoat::ScapegoatTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: ScapegoatTree<StdNum> = (17..=89).collect();
+    /// let tree2: ScapegoatTree<StdNum> = (13..=25).collect();
+    /// tree.concatenate_right(tree2);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(13..=25).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn concatenate_right(&mut self, mut right: Self) {
+        if !right.is_empty() {
+            let mut walker = right.search(locators::LeftEdgeOf(..));
+            walker.go_up().unwrap();
+            let mid = walker.delete().unwrap();
+            drop(walker);
+            self.concatenate_middle_right(mid, right);
+        }
+    }
+}
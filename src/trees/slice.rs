@@ -71,6 +71,7 @@ where
     /// Assumes that the this subsegment is empty.
     /// Inserts the value into the tree into the position of this empty subsegment.
     /// If the current subsegment is not empty, returns [`None`].
+    /// To insert at a position that is always empty, see [`SomeTree::insert_at`].
     pub fn insert(&mut self, value: D::Value) -> Option<()> {
         let mut walker = self.tree.search(self.locator.clone());
         walker.insert(value)
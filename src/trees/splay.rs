@@ -56,6 +56,9 @@ use crate::locators;
 /// When a [`SplayWalker`] is dropped, the walker automatically splays up the tree,
 /// ensuring that nodes that need to be rebuilt are rebuilt, but also that
 /// the splaytree's complexity properties remain.
+///
+/// For the same reason, splay trees don't implement [`QueryableTree`]: querying a splay tree
+/// must splay it, so even queries require a `&mut` reference to the tree.
 pub struct SplayTree<D: Data> {
     tree: BasicTree<D>,
 }
@@ -304,6 +307,27 @@ impl<D: Data> SomeTree<D> for SplayTree<D> {
         walker.act_subtree(action);
     }
 
+    // Separate queries, because the shared traversal uses `go_up`. Thanks to the
+    // dynamic finger property, sorted queries are still efficient.
+    fn segment_summaries<L>(&mut self, locators: &[L]) -> Vec<D::Summary>
+    where
+        L: locators::Locator<D>,
+    {
+        locators
+            .iter()
+            .map(|locator| self.segment_summary(locator.clone()))
+            .collect()
+    }
+
+    fn act_segments<L>(&mut self, action: D::Action, locators: &[L])
+    where
+        L: locators::Locator<D>,
+    {
+        for locator in locators {
+            self.act_segment(action, locator.clone());
+        }
+    }
+
     type TreeData = ();
     fn iter_locator<'a, L: locators::Locator<D>>(
         &'a mut self,
@@ -313,6 +337,14 @@ impl<D: Data> SomeTree<D> for SplayTree<D> {
         iterators::IterLocator::new(&mut self.tree, locator)
     }
 
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L> {
+        self.isolate_segment(locator.clone());
+        iterators::CursorMut::new(&mut self.tree, locator)
+    }
+
     fn assert_correctness(&self)
     where
         D::Summary: Eq,
@@ -345,6 +377,18 @@ impl<'a, D: Data> ModifiableTreeRef<D> for &'a mut SplayTree<D> {
     type ModifiableWalker = Self::Walker;
 }
 
+impl<D: Data> crate::debug::DebugTree<D> for SplayTree<D> {
+    type AlgData = ();
+
+    fn basic_tree(&self) -> &BasicTree<D> {
+        &self.tree
+    }
+
+    fn describe_alg_data(_: &()) -> Option<String> {
+        None
+    }
+}
+
 impl<D: Data> std::iter::FromIterator<D::Value> for SplayTree<D> {
     fn from_iter<T: IntoIterator<Item = D::Value>>(iter: T) -> Self {
         SplayTree {
@@ -353,6 +397,68 @@ impl<D: Data> std::iter::FromIterator<D::Value> for SplayTree<D> {
     }
 }
 
+#[cfg(feature = "snapshot")]
+impl<D: Data> SplayTree<D> {
+    /// Writes a snapshot of the tree, that preserves its exact shape.
+    /// See [`basic_tree::snapshot`].
+    pub fn to_snapshot(&self) -> Vec<u8>
+    where
+        D::Value: serde::Serialize,
+        D::Summary: serde::Serialize,
+        D::Action: serde::Serialize,
+    {
+        snapshot::write(&self.tree, snapshot::TreeKind::Splay)
+    }
+
+    /// Loads a snapshot written by [`SplayTree::to_snapshot`], in `O(n)` time.
+    /// Checks the summaries of all nodes.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, snapshot::SnapshotError>
+    where
+        D::Value: serde::de::DeserializeOwned,
+        D::Summary: serde::de::DeserializeOwned + Eq,
+        D::Action: serde::de::DeserializeOwned,
+    {
+        let tree = snapshot::read(bytes, snapshot::TreeKind::Splay, |_| true)?;
+        Ok(SplayTree { tree })
+    }
+}
+
+/// Serializes the tree as the sequence of its values, in order, with all pending actions applied.
+#[cfg(feature = "serde")]
+impl<D: Data> serde::Serialize for SplayTree<D>
+where
+    D::Value: serde::Serialize + Clone,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.tree.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<D: Data> SplayTree<D> {
+    /// Serializes the tree by reference, without requiring the values to be [`Clone`].
+    /// See [`BasicTree::serialize_ref`].
+    pub fn serialize_ref<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        D::Value: serde::Serialize,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.serialize_ref(serializer)
+    }
+}
+
+/// Deserializes a sequence of values, and builds a balanced tree out of them in `O(n)` time.
+#[cfg(feature = "serde")]
+impl<'de, D: Data> serde::Deserialize<'de> for SplayTree<D>
+where
+    D::Value: serde::Deserialize<'de>,
+{
+    fn deserialize<De: serde::Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let values = Vec::<D::Value>::deserialize(deserializer)?;
+        Ok(values.into_iter().collect())
+    }
+}
+
 impl<D: Data> IntoIterator for SplayTree<D> {
     type Item = D::Value;
     type IntoIter = <BasicTree<D> as IntoIterator>::IntoIter;
@@ -502,6 +608,114 @@ impl<'a, D: Data> ModifiableWalker<D> for SplayWalker<'a, D> {
     }
 }
 
+/// Returns whether `tree1` has at most as many nodes as `tree2`.
+/// Counts the nodes of both trees at the same pace, and stops as soon as one of them
+/// has been counted completely. Therefore, this takes time linear in the size of the smaller tree.
+fn is_smaller_or_equal<D: Data>(tree1: &BasicTree<D>, tree2: &BasicTree<D>) -> bool {
+    let mut stacks = [vec![tree1], vec![tree2]];
+    loop {
+        for (i, stack) in stacks.iter_mut().enumerate() {
+            // find the next node of this tree
+            loop {
+                match stack.pop() {
+                    None => return i == 0,
+                    Some(tree) => {
+                        if let Some(node) = tree.node() {
+                            stack.push(&node.left);
+                            stack.push(&node.right);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<D: Data> SplayTree<D> {
+    /// Computes the union of two trees, ordered by keys.
+    /// Values with equal keys are all kept, and the values from `tree2` are placed after
+    /// the values of `self` with the same key.
+    /// Goes over the values of the smaller tree, and splits the bigger tree at each of them.
+    /// See [`methods::union`].
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `k <= n`, then the complexity is
+    /// `O(k*log(1+n/k))` amortized.
+    pub fn union(&mut self, tree2: SplayTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = if is_smaller_or_equal(&tree2.tree, &tree1.tree) {
+            methods::union_ordered(tree1, tree2, true)
+        } else {
+            methods::union_ordered(tree2, tree1, false)
+        };
+    }
+
+    /// Computes the intersection of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys appear in `tree2`. Values with equal keys in `self` are all kept.
+    /// Goes over the values of the smaller tree, and splits the bigger tree at each of them.
+    /// See [`methods::intersection`].
+    ///
+    /// # Complexity
+    /// The same as [`SplayTree::union`].
+    pub fn intersection(&mut self, tree2: SplayTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = if is_smaller_or_equal(&tree2.tree, &tree1.tree) {
+            methods::intersection(tree1, tree2)
+        } else {
+            methods::intersection_by_first(tree1, tree2)
+        };
+    }
+
+    /// Computes the difference of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys don't appear in `tree2`.
+    /// Goes over the values of the smaller tree, and splits the bigger tree at each of them.
+    /// See [`methods::difference`].
+    ///
+    /// # Complexity
+    /// The same as [`SplayTree::union`].
+    pub fn difference(&mut self, tree2: SplayTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = if is_smaller_or_equal(&tree2.tree, &tree1.tree) {
+            methods::difference(tree1, tree2)
+        } else {
+            methods::difference_by_first(tree1, tree2)
+        };
+    }
+}
+
+impl<D: Data> SetOperations<D> for SplayTree<D> {
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        SplayTree::union(self, other);
+    }
+
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        SplayTree::intersection(self, other);
+    }
+
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        SplayTree::difference(self, other);
+    }
+}
+
 impl<D: Data> ConcatenableTree<D> for SplayTree<D> {
     // `tree3 = union(tree1, tree2)`, not
     // `tree1.concatenate(tree2)`.
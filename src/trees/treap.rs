@@ -75,6 +75,13 @@ impl<D: Data> SomeTree<D> for Treap<D> {
         iterators::IterLocator::new(&mut self.tree, locator)
     }
 
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L, T> {
+        iterators::CursorMut::new(&mut self.tree, locator)
+    }
+
     /// Checks that invariants remain correct. i.e., that every node's summary
     /// is the sum of the summaries of its children, and that the priorities are ordered.
     /// If it finds any violation, it panics.
@@ -89,6 +96,45 @@ impl<D: Data> SomeTree<D> for Treap<D> {
     }
 }
 
+impl<D: Data> QueryableTree<D> for Treap<D> {
+    type ImmTreeData = T;
+
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.segment_summary_imm(locator)
+    }
+
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.search_imm(locator)
+    }
+
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: locators::Locator<D>,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.search_ref(locator)
+    }
+
+    fn iter_locator_imm<L>(
+        &self,
+        locator: L,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, L, T>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.iter_locator_imm(locator)
+    }
+}
+
 impl<D: Data> Default for Treap<D> {
     fn default() -> Self {
         Treap::new()
@@ -150,7 +196,7 @@ impl<D: Data> Treap<D> {
     /// This is different from concatenate, because concatenate puts first all elements of the first tree,
     /// and then all of the elements of the second tree.
     ///
-    /// If elements with equal keys are found, they are placed in an arbitrary order.
+    /// If elements with equal keys are found, the elements of `tree2` are placed after the elements of this tree.
     ///
     /// # Complexity
     /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
@@ -162,7 +208,67 @@ impl<D: Data> Treap<D> {
     where
         D::Value: Keyed,
     {
-        union_internal(&mut self.tree, tree2);
+        union_internal(&mut self.tree, tree2, true);
+    }
+
+    /// Computes the intersection of two treaps, ordered by keys.
+    /// Keeps the values of this tree whose keys appear in `tree2`. If this tree has
+    /// several values with the same key, all of them are kept.
+    ///
+    /// To merge the values with equal keys into a single value, see [`Treap::intersection_with`].
+    ///
+    /// # Complexity
+    /// The same as [`Treap::union`]: if the sizes of the two trees are `n,k`, with `n < k`,
+    /// then the complexity is `O(n*log(1+k/n))` in the average case.
+    pub fn intersection(&mut self, tree2: Treap<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = intersection_internal(tree1, tree2);
+    }
+
+    /// Computes the intersection of two treaps, ordered by keys, and merges the values with
+    /// equal keys using `combine`.
+    /// For every key that appears in both trees, all of the values with that key are folded into
+    /// a single value: first the values from this tree, in order, and then the values from `tree2`,
+    /// in order.
+    ///
+    /// # Complexity
+    /// The same as [`Treap::intersection`], not counting the calls to `combine`.
+    pub fn intersection_with<F>(&mut self, tree2: Treap<D>, mut combine: F)
+    where
+        D::Value: Keyed,
+        F: FnMut(D::Value, D::Value) -> D::Value,
+    {
+        let tree1 = std::mem::take(self);
+        *self = intersection_with_internal(tree1, tree2, &mut combine);
+    }
+
+    /// Computes the difference of two treaps, ordered by keys.
+    /// Keeps the values of this tree whose keys don't appear in `tree2`.
+    ///
+    /// # Complexity
+    /// The same as [`Treap::union`].
+    pub fn difference(&mut self, tree2: Treap<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = difference_internal(tree1, tree2);
+    }
+
+    /// Computes the symmetric difference of two treaps, ordered by keys.
+    /// Keeps the values of each tree whose keys don't appear in the other tree.
+    ///
+    /// # Complexity
+    /// The same as [`Treap::union`].
+    pub fn symmetric_difference(&mut self, tree2: Treap<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = symmetric_difference_internal(tree1, tree2);
     }
 
     /// Asserts that the priorities maintain the priority invariant
@@ -175,12 +281,22 @@ impl<D: Data> Treap<D> {
     }
 
     fn assert_priorities_locally_internal(node: &BasicNode<D, T>) {
+        assert!(Self::priorities_correct_locally(node));
+    }
+
+    /// Returns `true` if the node's priority is bigger than its sons' priorities.
+    fn priorities_correct_locally(node: &BasicNode<D, T>) -> bool {
         if let Some(left) = node.left.node() {
-            assert!(node.alg_data() > left.alg_data());
+            if node.alg_data() <= left.alg_data() {
+                return false;
+            }
         }
         if let Some(right) = node.right.node() {
-            assert!(node.alg_data() > right.alg_data());
+            if node.alg_data() <= right.alg_data() {
+                return false;
+            }
         }
+        true
     }
 
     /// Asserts that the priorities maintain the priority invariant.
@@ -191,6 +307,18 @@ impl<D: Data> Treap<D> {
     }
 }
 
+impl<D: Data> crate::debug::DebugTree<D> for Treap<D> {
+    type AlgData = T;
+
+    fn basic_tree(&self) -> &BasicTree<D, T> {
+        &self.tree
+    }
+
+    fn describe_alg_data(priority: &T) -> Option<String> {
+        Some(format!("priority {}", priority))
+    }
+}
+
 impl<D: Data> std::iter::FromIterator<D::Value> for Treap<D> {
     /// This takes [`O(n)`] worst-case time.
     fn from_iter<T: IntoIterator<Item = D::Value>>(iter: T) -> Self {
@@ -210,6 +338,72 @@ impl<D: Data> std::iter::FromIterator<D::Value> for Treap<D> {
     }
 }
 
+#[cfg(feature = "snapshot")]
+impl<D: Data> Treap<D> {
+    /// Writes a snapshot of the tree, that preserves its exact shape, including the priorities.
+    /// See [`basic_tree::snapshot`].
+    pub fn to_snapshot(&self) -> Vec<u8>
+    where
+        D::Value: serde::Serialize,
+        D::Summary: serde::Serialize,
+        D::Action: serde::Serialize,
+    {
+        snapshot::write(&self.tree, snapshot::TreeKind::Treap)
+    }
+
+    /// Loads a snapshot written by [`Treap::to_snapshot`], in `O(n)` time.
+    /// Checks the summaries and the priorities of all nodes.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, snapshot::SnapshotError>
+    where
+        D::Value: serde::de::DeserializeOwned,
+        D::Summary: serde::de::DeserializeOwned + Eq,
+        D::Action: serde::de::DeserializeOwned,
+    {
+        let tree = snapshot::read(
+            bytes,
+            snapshot::TreeKind::Treap,
+            Self::priorities_correct_locally,
+        )?;
+        Ok(Treap { tree })
+    }
+}
+
+/// Serializes the tree as the sequence of its values, in order, with all pending actions applied.
+#[cfg(feature = "serde")]
+impl<D: Data> serde::Serialize for Treap<D>
+where
+    D::Value: serde::Serialize + Clone,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.tree.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<D: Data> Treap<D> {
+    /// Serializes the tree by reference, without requiring the values to be [`Clone`].
+    /// See [`BasicTree::serialize_ref`].
+    pub fn serialize_ref<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        D::Value: serde::Serialize,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.serialize_ref(serializer)
+    }
+}
+
+/// Deserializes a sequence of values, and builds a balanced tree out of them in `O(n)` time.
+#[cfg(feature = "serde")]
+impl<'de, D: Data> serde::Deserialize<'de> for Treap<D>
+where
+    D::Value: serde::Deserialize<'de>,
+{
+    fn deserialize<De: serde::Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        let values = Vec::<D::Value>::deserialize(deserializer)?;
+        Ok(values.into_iter().collect())
+    }
+}
+
 impl<D: Data> IntoIterator for Treap<D> {
     type Item = D::Value;
     type IntoIter = iterators::IntoIter<D, std::ops::RangeFull, T>;
@@ -334,7 +528,8 @@ impl<'a, D: Data> ModifiableWalker<D> for TreapWalker<'a, D> {
 /// This is different from concatenate, because concatenate puts first all elements of the first tree,
 /// and then all of the elements of the second tree.
 ///
-/// If elements with equal keys are found, they are placed in an arbitrary order.
+/// If elements with equal keys are found, the elements of `tree2` are placed after the elements of `tree1`
+/// if `tree1_first` is true, and before them otherwise.
 ///
 /// # Complexity
 /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
@@ -342,7 +537,7 @@ impl<'a, D: Data> ModifiableWalker<D> for TreapWalker<'a, D> {
 /// This has the effect that if you start with `n` different singletone trees,
 /// and you united them together in any way whatsoever, the overall complexity would be
 /// `O(n*log(n))`.
-fn union_internal<D: Data>(tree1: &mut BasicTree<D, T>, mut tree2: Treap<D>)
+fn union_internal<D: Data>(tree1: &mut BasicTree<D, T>, mut tree2: Treap<D>, mut tree1_first: bool)
 where
     D::Value: Keyed,
 {
@@ -355,6 +550,7 @@ where
     }
     if tree1.priority().unwrap() < tree2.priority().unwrap() {
         std::mem::swap(tree1, &mut tree2.tree);
+        tree1_first = !tree1_first;
     }
     let node = tree1.node_mut().unwrap();
 
@@ -376,20 +572,11 @@ where
 
     let key = node.node_value().get_key(); // this performs access()
 
-    // TODO: replace by a locator that does the handling of the equality case by itself
-    let mut split_walker = tree2.search(locators::ByKey((key,)));
-    // if an element with the same key was found, arbitrarily decide to put it more to the right
-    if !split_walker.is_empty() {
-        split_walker.previous_empty().unwrap();
-    }
-    // split
-    let right = split_walker.split_right().unwrap();
-    drop(split_walker);
-    let left = tree2;
+    let (left, right) = split_for_union(tree2, key, tree1_first);
 
     // TODO: nice possible location for parrallelization
-    union_internal(&mut node.left, left);
-    union_internal(&mut node.right, right);
+    union_internal(&mut node.left, left, tree1_first);
+    union_internal(&mut node.right, right, tree1_first);
     node.rebuild();
 }
 
@@ -403,7 +590,7 @@ use async_recursion::async_recursion;
 /// This is different from concatenate, because concatenate puts first all elements of the first tree,
 /// and then all of the elements of the second tree.
 ///
-/// If elements with equal keys are found, they are placed in an arbitrary order.
+/// If elements with equal keys are found, the elements of `tree2` are placed after the elements of `tree1`.
 ///
 /// # Complexity
 /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
@@ -411,14 +598,31 @@ use async_recursion::async_recursion;
 /// This has the effect that if you start with `n` different singletone trees,
 /// and you united them together in any way whatsoever, the overall complexity would be
 /// `O(n*log(n))`.
-#[async_recursion]
-pub async fn union_internal_concurrent<D: Data>(tree1: &mut BasicTree<D, T>, mut tree2: Treap<D>)
+pub async fn union_internal_concurrent<D: Data>(tree1: &mut BasicTree<D, T>, tree2: Treap<D>)
 where
     D::Value: Keyed,
     <D::Value as Keyed>::Key: Sync,
     D::Action: Send,
     D::Summary: Send,
     D::Value: Send,
+{
+    union_ordered_concurrent(tree1, tree2, true).await;
+}
+
+#[cfg(feature = "async_union")]
+/// Same as [`union_internal_concurrent`], except that the values of `tree2` with equal keys
+/// are placed before the values of `tree1` if `tree1_first` is false.
+#[async_recursion]
+async fn union_ordered_concurrent<D: Data>(
+    tree1: &mut BasicTree<D, T>,
+    mut tree2: Treap<D>,
+    mut tree1_first: bool,
+) where
+    D::Value: Keyed,
+    <D::Value as Keyed>::Key: Sync,
+    D::Action: Send,
+    D::Summary: Send,
+    D::Value: Send,
 {
     if tree2.is_empty() {
         return;
@@ -429,6 +633,7 @@ where
     }
     if tree1.priority().unwrap() < tree2.priority().unwrap() {
         std::mem::swap(tree1, &mut tree2.tree);
+        tree1_first = !tree1_first;
     }
     let node = tree1.node_mut().unwrap();
 
@@ -450,20 +655,11 @@ where
 
     let key = node.node_value().get_key(); // this performs access()
 
-    // TODO: replace by a locator that does the handling of the equality case by itself
-    let mut split_walker = tree2.search(locators::ByKey((key,)));
-    // if an element with the same key was found, arbitrarily decide to put it more to the right
-    if !split_walker.is_empty() {
-        split_walker.previous_empty().unwrap();
-    }
-    // split
-    let right = split_walker.split_right().unwrap();
-    drop(split_walker);
-    let left = tree2;
+    let (left, right) = split_for_union(tree2, key, tree1_first);
 
     futures::join!(
-        union_internal_concurrent(&mut node.left, left),
-        union_internal_concurrent(&mut node.right, right)
+        union_ordered_concurrent(&mut node.left, left, tree1_first),
+        union_ordered_concurrent(&mut node.right, right, tree1_first)
     );
     node.rebuild();
 }
@@ -475,7 +671,7 @@ where
 /// This is different from concatenate, because concatenate puts first all elements of the first tree,
 /// and then all of the elements of the second tree.
 ///
-/// If elements with equal keys are found, they are placed in an arbitrary order.
+/// If elements with equal keys are found, the elements of `tree2` are placed after the elements of `tree1`.
 ///
 ///```rust
 /// use grove::{SomeTree, treap, treap::Treap};
@@ -516,7 +712,7 @@ where
 /// This is different from concatenate, because concatenate puts first all elements of the first tree,
 /// and then all of the elements of the second tree.
 ///
-/// If elements with equal keys are found, they are placed in an arbitrary order.
+/// If elements with equal keys are found, the elements of `tree2` are placed after the elements of `tree1`.
 ///
 ///```rust
 ///use grove::{SomeTree, treap, treap::Treap};
@@ -544,6 +740,522 @@ where
     tree1
 }
 
+fn intersection_internal<D: Data>(tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match expose(tree2) {
+        None => return Treap::new(),
+        Some(parts) => parts,
+    };
+    let (left1, equal1, right1) = split_by_key(tree1, mid.get_key());
+
+    let mut res = intersection_internal(left1, left2);
+    res.concatenate_right(equal1);
+    res.concatenate_right(intersection_internal(right1, right2));
+    res
+}
+
+fn intersection_with_internal<D: Data, F>(
+    tree1: Treap<D>,
+    tree2: Treap<D>,
+    combine: &mut F,
+) -> Treap<D>
+where
+    D::Value: Keyed,
+    F: FnMut(D::Value, D::Value) -> D::Value,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match expose(tree2) {
+        None => return Treap::new(),
+        Some(parts) => parts,
+    };
+    let key = mid.get_key();
+    let (left1, equal1, right1) = split_by_key(tree1, key);
+    // values equal to `mid` in `tree2`
+    let (left2, equal_left2, _) = split_by_key(left2, key);
+    let (_, equal_right2, right2) = split_by_key(right2, key);
+
+    let mut res = intersection_with_internal(left1, left2, combine);
+    if !equal1.is_empty() {
+        let mut values = equal1
+            .into_iter()
+            .chain(equal_left2)
+            .chain(std::iter::once(mid))
+            .chain(equal_right2);
+        let first = values.next().unwrap();
+        let value = values.fold(first, &mut *combine);
+        res.concatenate_right(std::iter::once(value).collect());
+    }
+    res.concatenate_right(intersection_with_internal(right1, right2, combine));
+    res
+}
+
+fn difference_internal<D: Data>(tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match expose(tree2) {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let (left1, _, right1) = split_by_key(tree1, mid.get_key());
+
+    let mut res = difference_internal(left1, left2);
+    res.concatenate_right(difference_internal(right1, right2));
+    res
+}
+
+fn symmetric_difference_internal<D: Data>(tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree2;
+    }
+    let (left2, mid, right2) = match expose(tree2) {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let key = mid.get_key();
+    let (left1, equal1, right1) = split_by_key(tree1, key);
+    // values equal to `mid` in `tree2`
+    let (left2, equal_left2, _) = split_by_key(left2, key);
+    let (_, equal_right2, right2) = split_by_key(right2, key);
+
+    let mut res = symmetric_difference_internal(left1, left2);
+    if equal1.is_empty() {
+        res.concatenate_right(equal_left2);
+        res.concatenate_right(std::iter::once(mid).collect());
+        res.concatenate_right(equal_right2);
+    }
+    res.concatenate_right(symmetric_difference_internal(right1, right2));
+    res
+}
+
+/// Computes the intersection of two treaps, ordered by keys.
+/// Keeps the values of `tree1` whose keys appear in `tree2`.
+/// See [`Treap::intersection`].
+///
+///```rust
+///use grove::{SomeTree, treap, treap::Treap};
+///use grove::example_data::{NoAction, Ordered};
+///
+///type T = Treap<NoAction<Ordered<i32>>>;
+///let tree1: T = [0, 1, 2, 4, 4, 5, 6].iter().map(|&x| Ordered(x)).collect();
+///let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+///let tree = treap::intersection(tree1, tree2);
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+pub fn intersection<D: Data>(mut tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed,
+{
+    tree1.intersection(tree2);
+    tree1
+}
+
+/// Computes the intersection of two treaps, ordered by keys, and merges the values with
+/// equal keys using `combine`. See [`Treap::intersection_with`].
+///
+///```rust
+///use grove::{SomeTree, Keyed, treap, treap::Treap};
+///use grove::example_data::NoAction;
+///
+///// word counts
+///#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///struct Count(&'static str, usize);
+///impl Keyed for Count {
+///    type Key = &'static str;
+///    fn get_key(&self) -> &&'static str {
+///        &self.0
+///    }
+///}
+///
+///type T = Treap<NoAction<Count>>;
+///let tree1: T = vec![Count("a", 1), Count("b", 2), Count("b", 1), Count("c", 3)].into_iter().collect();
+///let tree2: T = vec![Count("b", 5), Count("c", 1), Count("d", 1)].into_iter().collect();
+///let tree = treap::intersection_with(tree1, tree2, |x, y| Count(x.0, x.1 + y.1));
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), vec![Count("b", 8), Count("c", 4)]);
+///```
+pub fn intersection_with<D: Data, F>(mut tree1: Treap<D>, tree2: Treap<D>, combine: F) -> Treap<D>
+where
+    D::Value: Keyed,
+    F: FnMut(D::Value, D::Value) -> D::Value,
+{
+    tree1.intersection_with(tree2, combine);
+    tree1
+}
+
+/// Computes the difference of two treaps, ordered by keys.
+/// Keeps the values of `tree1` whose keys don't appear in `tree2`.
+/// See [`Treap::difference`].
+///
+///```rust
+///use grove::{SomeTree, treap, treap::Treap};
+///use grove::example_data::{NoAction, Ordered};
+///
+///type T = Treap<NoAction<Ordered<i32>>>;
+///let tree1: T = [0, 1, 1, 2, 4, 5, 6].iter().map(|&x| Ordered(x)).collect();
+///let tree2: T = [2, 4, 4, 9].iter().map(|&x| Ordered(x)).collect();
+///let tree = treap::difference(tree1, tree2);
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,1,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+pub fn difference<D: Data>(mut tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed,
+{
+    tree1.difference(tree2);
+    tree1
+}
+
+/// Computes the symmetric difference of two treaps, ordered by keys.
+/// Keeps the values of each tree whose keys don't appear in the other tree.
+/// See [`Treap::symmetric_difference`].
+///
+///```rust
+///use grove::{SomeTree, treap, treap::Treap};
+///use grove::example_data::{NoAction, Ordered};
+///
+///type T = Treap<NoAction<Ordered<i32>>>;
+///let tree1: T = [0, 1, 1, 2, 4, 5].iter().map(|&x| Ordered(x)).collect();
+///let tree2: T = [2, 4, 4, 7, 7].iter().map(|&x| Ordered(x)).collect();
+///let tree = treap::symmetric_difference(tree1, tree2);
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,1,5,7,7].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+pub fn symmetric_difference<D: Data>(mut tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed,
+{
+    tree1.symmetric_difference(tree2);
+    tree1
+}
+
+/// Splits the tree into the values with keys smaller than `key`, the values with keys
+/// equal to `key`, and the values with keys bigger than `key`.
+fn split_by_key<D: Data>(
+    mut tree: Treap<D>,
+    key: &<D::Value as Keyed>::Key,
+) -> (Treap<D>, Treap<D>, Treap<D>)
+where
+    D::Value: Keyed,
+{
+    let mut mid = tree
+        .slice(locators::LeftEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    let right = mid
+        .slice(locators::RightEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    (tree, mid, right)
+}
+
+/// Splits the tree into the values with keys smaller than `key` and the values with keys
+/// bigger than `key`. The values with keys equal to `key` go to the right part if `after`
+/// is true, and to the left part otherwise.
+fn split_for_union<D: Data>(
+    mut tree: Treap<D>,
+    key: &<D::Value as Keyed>::Key,
+    after: bool,
+) -> (Treap<D>, Treap<D>)
+where
+    D::Value: Keyed,
+{
+    let right = if after {
+        tree.slice(locators::LeftEdgeOf(locators::ByKey((key,))))
+            .split_right()
+            .unwrap()
+    } else {
+        tree.slice(locators::RightEdgeOf(locators::ByKey((key,))))
+            .split_right()
+            .unwrap()
+    };
+    (tree, right)
+}
+
+/// Takes the root out of the tree, and returns it along with its left and right subtrees.
+/// Returns [`None`] if the tree is empty.
+fn expose<D: Data>(tree: Treap<D>) -> Option<(Treap<D>, D::Value, Treap<D>)> {
+    let mut node = tree.tree.into_node_boxed()?;
+    node.access();
+    let BasicNode {
+        left,
+        right,
+        node_value,
+        ..
+    } = *node;
+    Some((Treap { tree: left }, node_value, Treap { tree: right }))
+}
+
+#[cfg(feature = "rayon")]
+/// Returns whether the tree has less than [`basic_tree::SEQUENTIAL_THRESHOLD`] nodes,
+/// in which case it isn't worth running the set operations on it in parallel.
+/// Looks at no more than that many nodes.
+fn is_small<D: Data>(tree: &BasicTree<D, T>) -> bool {
+    let mut stack = vec![tree];
+    let mut count = 0;
+    while let Some(tree) = stack.pop() {
+        if let Some(node) = tree.node() {
+            count += 1;
+            if count >= basic_tree::SEQUENTIAL_THRESHOLD {
+                return false;
+            }
+            stack.push(&node.left);
+            stack.push(&node.right);
+        }
+    }
+    true
+}
+
+#[cfg(feature = "rayon")]
+fn par_union_internal<D: Data>(
+    tree1: &mut BasicTree<D, T>,
+    mut tree2: Treap<D>,
+    mut tree1_first: bool,
+) where
+    D::Value: Keyed + Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    if tree2.is_empty() {
+        return;
+    }
+    if tree1.is_empty() {
+        *tree1 = tree2.tree;
+        return;
+    }
+    if is_small(tree1) || is_small(&tree2.tree) {
+        union_internal(tree1, tree2, tree1_first);
+        return;
+    }
+    if tree1.priority().unwrap() < tree2.priority().unwrap() {
+        std::mem::swap(tree1, &mut tree2.tree);
+        tree1_first = !tree1_first;
+    }
+    let node = tree1.node_mut().unwrap();
+
+    let key = node.node_value().get_key(); // this performs access()
+
+    let (left, right) = split_for_union(tree2, key, tree1_first);
+
+    let (node_left, node_right) = (&mut node.left, &mut node.right);
+    rayon::join(
+        || par_union_internal(node_left, left, tree1_first),
+        || par_union_internal(node_right, right, tree1_first),
+    );
+    node.rebuild();
+}
+
+#[cfg(feature = "rayon")]
+/// Computes the union of two treaps, ordered by keys, using fork-join parallelism.
+/// Same as [`union`], except that the two recursive calls run in parallel on the
+/// [`rayon`] thread pool, as long as both trees are big enough.
+///
+/// If elements with equal keys are found, the elements of `tree2` are placed after the elements of `tree1`.
+///
+///```rust
+///use grove::{SomeTree, treap, treap::Treap};
+///use grove::example_data::{NoAction, Ordered};
+///
+///type T = Treap<NoAction<Ordered<i32>>>;
+///let tree1: T = (0..7).map(|x| Ordered(x)).collect();
+///let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+///let tree = treap::par_union(tree1, tree2);
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3,4,4,5,5,6,6,7,8].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+///
+/// # Complexity
+/// The total work is the same as in [`union`]: if the sizes of the two trees are `n,k`,
+/// with `n < k`, then it is `O(n*log(1+k/n))` in the average case.
+pub fn par_union<D: Data>(mut tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed + Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    par_union_internal(&mut tree1.tree, tree2, true);
+    tree1
+}
+
+#[cfg(feature = "rayon")]
+/// Computes the intersection of two treaps, ordered by keys, using fork-join parallelism.
+/// The recursive calls run in parallel as long as both trees are big enough.
+/// The result contains the values of `tree1` whose keys appear in `tree2`,
+/// so if `tree1` has several values with the same key, all of them are kept.
+///
+///```rust
+///use grove::{SomeTree, treap, treap::Treap};
+///use grove::example_data::{NoAction, Ordered};
+///
+///type T = Treap<NoAction<Ordered<i32>>>;
+///let tree1: T = (0..7).map(|x| Ordered(x)).collect();
+///let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+///let tree = treap::par_intersection(tree1, tree2);
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+///
+/// # Complexity
+/// The total work is the same as in [`intersection`]: if the sizes of the two trees are `n,k`,
+/// with `n < k`, then it is `O(n*log(1+k/n))` in the average case.
+pub fn par_intersection<D: Data>(tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed + Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    if is_small(&tree1.tree) || is_small(&tree2.tree) {
+        return intersection_internal(tree1, tree2);
+    }
+    let (left2, mid, right2) = match expose(tree2) {
+        None => return Treap::new(),
+        Some(parts) => parts,
+    };
+    let (left1, equal1, right1) = split_by_key(tree1, mid.get_key());
+
+    let (mut res, right_res) = rayon::join(
+        || par_intersection(left1, left2),
+        || par_intersection(right1, right2),
+    );
+    res.concatenate_right(equal1);
+    res.concatenate_right(right_res);
+    res
+}
+
+#[cfg(feature = "rayon")]
+/// Computes the difference of two treaps, ordered by keys, using fork-join parallelism.
+/// The recursive calls run in parallel as long as both trees are big enough.
+/// The result contains the values of `tree1` whose keys don't appear in `tree2`.
+///
+///```rust
+///use grove::{SomeTree, treap, treap::Treap};
+///use grove::example_data::{NoAction, Ordered};
+///
+///type T = Treap<NoAction<Ordered<i32>>>;
+///let tree1: T = (0..7).map(|x| Ordered(x)).collect();
+///let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+///let tree = treap::par_difference(tree1, tree2);
+/// # tree.assert_correctness();
+///assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+///```
+///
+/// # Complexity
+/// The total work is the same as in [`difference`]: if the sizes of the two trees are `n,k`,
+/// with `n < k`, then it is `O(n*log(1+k/n))` in the average case.
+pub fn par_difference<D: Data>(tree1: Treap<D>, tree2: Treap<D>) -> Treap<D>
+where
+    D::Value: Keyed + Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    if is_small(&tree1.tree) || is_small(&tree2.tree) {
+        return difference_internal(tree1, tree2);
+    }
+    let (left2, mid, right2) = match expose(tree2) {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let (left1, _, right1) = split_by_key(tree1, mid.get_key());
+
+    let (mut res, right_res) = rayon::join(
+        || par_difference(left1, left2),
+        || par_difference(right1, right2),
+    );
+    res.concatenate_right(right_res);
+    res
+}
+
+#[cfg(feature = "rayon")]
+/// Builds a treap from the values, in parallel.
+/// Takes the values out of the slice, which should contain only [`Some`] values.
+fn par_build<D: Data>(values: &mut [Option<D::Value>]) -> Treap<D>
+where
+    D::Value: Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    if values.len() < basic_tree::SEQUENTIAL_THRESHOLD {
+        return values.iter_mut().map(|value| value.take().unwrap()).collect();
+    }
+    let (left_values, right_values) = values.split_at_mut(values.len() / 2);
+    let (mut left, right) = rayon::join(|| par_build(left_values), || par_build(right_values));
+    left.concatenate_right(right);
+    left
+}
+
+#[cfg(feature = "rayon")]
+impl<D: Data> rayon::iter::FromParallelIterator<D::Value> for Treap<D>
+where
+    D::Value: Send,
+    D::Summary: Send,
+    D::Action: Send,
+{
+    /// Builds a treap from the values, in the same order as the iterator.
+    /// The values are first collected, and then parts of the tree are built in parallel and
+    /// concatenated together.
+    ///```
+    /// use grove::{SomeTree, treap::Treap};
+    /// use grove::example_data::StdNum;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut tree: Treap<StdNum> = (0..10_000).into_par_iter().collect();
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (0..10_000).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: rayon::iter::IntoParallelIterator<Item = D::Value>,
+    {
+        use rayon::prelude::*;
+        let mut values: Vec<_> = par_iter.into_par_iter().map(Some).collect();
+        par_build(&mut values)
+    }
+}
+
+impl<D: Data> SetOperations<D> for Treap<D> {
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        Treap::union(self, other);
+    }
+
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        Treap::intersection(self, other);
+    }
+
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        Treap::difference(self, other);
+    }
+}
+
 impl<D: Data> ConcatenableTree<D> for Treap<D> {
     /// Concatenates the trees together, in place.
     ///```
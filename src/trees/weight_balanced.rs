@@ -0,0 +1,831 @@
+//! Implementation of weight-balanced trees (also known as BB[α] trees).
+//! Balanced by keeping track of subtree sizes, this is a deterministic worst-case balancing
+//! algorithm, with efficient join-based set operations.
+
+use crate::locators;
+
+use super::basic_tree::*;
+use super::*;
+
+/// The type that is used for size bookkeeping.
+type T = usize;
+
+/// A tree taken apart into its root's left subtree, its root, and its root's right subtree.
+type Exposed<D> = (WBTree<D>, Box<BasicNode<D, T>>, WBTree<D>);
+
+/// Returns the size of the tree, i.e., the number of values in it.
+fn size<D: Data>(tree: &BasicTree<D, T>) -> usize {
+    match tree.alg_data() {
+        None => 0,
+        Some(size) => *size,
+    }
+}
+
+/// The weight of a tree is its size plus one.
+fn weight<D: Data>(tree: &BasicTree<D, T>) -> usize {
+    size(tree) + 1
+}
+
+/// Returns the weights of the sons of the tree, in order.
+/// Takes into account that the sons might be swapped by a reversal
+/// that wasn't pushed down yet.
+fn sons_weights<D: Data>(tree: &BasicTree<D, T>) -> (usize, usize) {
+    let node = tree.node().unwrap();
+    let (left, right) = (weight(&node.left), weight(&node.right));
+    if node.action().to_reverse() {
+        (right, left)
+    } else {
+        (left, right)
+    }
+}
+
+/// Returns whether two trees with weights `a` and `b` are balanced enough
+/// to be the sons of the same node. This is the case when each son has at least
+/// a quarter of the total weight.
+fn like(a: usize, b: usize) -> bool {
+    3 * a >= b && 3 * b >= a
+}
+
+fn rebuild_size<D: Data>(node: &mut BasicNode<D, T>) {
+    node.alg_data = size(&node.left) + size(&node.right) + 1;
+}
+
+/// A weight-balanced tree. Balanced by keeping track of subtree sizes, this is a deterministic
+/// worst-case balancing algorithm, with efficient join-based set operations.
+pub struct WBTree<D: Data> {
+    tree: BasicTree<D, T>,
+}
+
+impl<D: Data> WBTree<D> {
+    /// Creates an empty [`WBTree`].
+    pub fn new() -> Self {
+        WBTree {
+            tree: BasicTree::Empty,
+        }
+    }
+
+    /// Returns the amount of values in the tree.
+    pub fn size(&self) -> usize {
+        size(&self.tree)
+    }
+
+    /// Asserts that the size and balance of the current node are correct.
+    /// Otherwise, panics.
+    pub fn assert_balance_locally(&self) {
+        if let Some(node) = self.tree.node() {
+            Self::assert_balance_locally_internal(node);
+        }
+    }
+
+    fn assert_balance_locally_internal(node: &BasicNode<D, T>) {
+        assert_eq!(*node.alg_data(), size(&node.left) + size(&node.right) + 1);
+        assert!(like(weight(&node.left), weight(&node.right)));
+    }
+
+    /// Asserts that the sizes and balance of all nodes of the tree are correct.
+    /// Otherwise, panics.
+    pub fn assert_balance(&self) {
+        self.tree
+            .assert_correctness_with(Self::assert_balance_locally_internal);
+    }
+
+    /// Takes the tree apart into the root's left subtree, the root itself, and the root's
+    /// right subtree. Returns [`None`] if the tree is empty.
+    fn expose(self) -> Option<Exposed<D>> {
+        let mut node = self.tree.into_node_boxed()?;
+        node.access();
+        let left = std::mem::replace(&mut node.left, BasicTree::Empty);
+        let right = std::mem::replace(&mut node.right, BasicTree::Empty);
+        Some((WBTree { tree: left }, node, WBTree { tree: right }))
+    }
+}
+
+impl<D: Data> QueryableTree<D> for WBTree<D> {
+    type ImmTreeData = T;
+
+    fn segment_summary_imm<L>(&self, locator: L) -> D::Summary
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.segment_summary_imm(locator)
+    }
+
+    fn search_imm<L>(&self, locator: L) -> Option<std::borrow::Cow<'_, D::Value>>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.search_imm(locator)
+    }
+
+    fn search_ref<L>(&self, locator: L) -> Option<&D::Value>
+    where
+        L: locators::Locator<D>,
+        D::Action: ValuePreservingAction,
+    {
+        self.tree.search_ref(locator)
+    }
+
+    fn iter_locator_imm<L>(
+        &self,
+        locator: L,
+    ) -> basic_tree::iterators::ImmIterLocator<'_, D, L, T>
+    where
+        L: locators::Locator<D>,
+        D::Value: Clone,
+    {
+        self.tree.iter_locator_imm(locator)
+    }
+}
+
+impl<D: Data> Default for WBTree<D> {
+    fn default() -> Self {
+        WBTree::new()
+    }
+}
+
+impl<D: Data> SomeTree<D> for WBTree<D> {
+    fn segment_summary<L>(&mut self, locator: L) -> D::Summary
+    where
+        L: crate::Locator<D>,
+    {
+        methods::segment_summary(self, locator)
+    }
+
+    fn act_segment<L>(&mut self, action: D::Action, locator: L)
+    where
+        L: crate::Locator<D>,
+    {
+        if !action.to_reverse() {
+            methods::act_segment(self, action, locator)
+        } else {
+            // split out the middle
+            let mut mid: WBTree<D> = self
+                .slice(locators::LeftEdgeOf(locator.clone()))
+                .split_right()
+                .unwrap();
+
+            let mut walker2 = WBWalker {
+                walker: BasicWalker::new_with_context(
+                    &mut mid.tree,
+                    self.subtree_summary(),
+                    Default::default(),
+                ),
+            };
+            walker2.search_subtree(locators::RightEdgeOf(locator));
+            let right = walker2.split_right().unwrap();
+            drop(walker2);
+
+            // apply action
+            mid.act_subtree(action);
+
+            // glue back together
+            mid.concatenate_right(right);
+            self.concatenate_right(mid);
+        }
+    }
+
+    type TreeData = usize;
+    fn iter_locator<'a, L: locators::Locator<D>>(
+        &'a mut self,
+        locator: L,
+    ) -> basic_tree::iterators::IterLocator<'a, D, L, usize> {
+        iterators::IterLocator::new(&mut self.tree, locator)
+    }
+
+    fn cursor_mut<L: locators::Locator<D>>(
+        &mut self,
+        locator: L,
+    ) -> basic_tree::iterators::CursorMut<'_, D, L, usize> {
+        iterators::CursorMut::new(&mut self.tree, locator)
+    }
+
+    fn assert_correctness(&self)
+    where
+        D::Summary: Eq,
+    {
+        self.tree.assert_correctness_with(|node| {
+            node.assert_correctness_locally();
+            Self::assert_balance_locally_internal(node);
+        });
+    }
+}
+
+impl<'a, D: Data> SomeTreeRef<D> for &'a mut WBTree<D> {
+    type Walker = WBWalker<'a, D>;
+
+    fn walker(self) -> Self::Walker {
+        WBWalker {
+            walker: self.tree.walker(),
+        }
+    }
+}
+
+impl<'a, D: Data> ModifiableTreeRef<D> for &'a mut WBTree<D> {
+    type ModifiableWalker = WBWalker<'a, D>;
+}
+
+impl<'a, D: Data> SplittableTreeRef<D> for &'a mut WBTree<D> {
+    type T = WBTree<D>;
+
+    type SplittableWalker = WBWalker<'a, D>;
+}
+
+derive_SomeEntry! {tree,
+    impl<D: Data> SomeEntry<D> for WBTree<D> {
+        fn assert_correctness_locally(&self)
+        where
+            D::Summary: Eq,
+        {
+            if let Some(node) = self.tree.node() {
+                Self::assert_balance_locally_internal(node);
+                node.assert_correctness_locally();
+            }
+        }
+    }
+}
+
+impl<D: Data> crate::debug::DebugTree<D> for WBTree<D> {
+    type AlgData = T;
+
+    fn basic_tree(&self) -> &BasicTree<D, T> {
+        &self.tree
+    }
+
+    fn describe_alg_data(size: &T) -> Option<String> {
+        Some(format!("size {}", size))
+    }
+}
+
+impl<D: Data> std::iter::FromIterator<D::Value> for WBTree<D> {
+    /// Builds a perfectly balanced tree.
+    /// This takes `O(n)` worst-case time.
+    fn from_iter<I: IntoIterator<Item = D::Value>>(iter: I) -> Self {
+        let nodes: Vec<_> = iter
+            .into_iter()
+            .map(|val| Box::new(BasicNode::new_alg(val, 1)))
+            .collect();
+        let count = nodes.len();
+        WBTree {
+            tree: basic_tree::build_balanced(&mut nodes.into_iter(), count, rebuild_size),
+        }
+    }
+}
+
+impl<D: Data> IntoIterator for WBTree<D> {
+    type Item = D::Value;
+    type IntoIter = iterators::IntoIter<D, std::ops::RangeFull, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        iterators::IntoIter::new(self.tree, ..)
+    }
+}
+
+/// A walker struct for [`WBTree`].
+pub struct WBWalker<'a, D: Data> {
+    walker: BasicWalker<'a, D, T>,
+}
+
+impl<'a, D: Data> std::ops::Drop for WBWalker<'a, D> {
+    fn drop(&mut self) {
+        self.go_to_root()
+    }
+}
+
+derive_SomeWalker! {walker,
+    impl<'a, D: Data> SomeWalker<D> for WBWalker<'a, D> {
+        fn go_up(&mut self) -> Result<Side, ()> {
+            let res = self.walker.go_up()?;
+            let node = self.walker.node_mut().unwrap();
+            let old_size = node.alg_data;
+            rebuild_size(node);
+            // sizes are always fixed right after the tree is modified
+            assert_eq!(old_size, node.alg_data);
+            Ok(res)
+        }
+    }
+}
+
+derive_SomeEntry! {walker,
+    impl<'a, D: Data> SomeEntry<D> for WBWalker<'a, D> {
+        fn assert_correctness_locally(&self)
+        where
+            D::Summary: Eq,
+        {
+            self.walker.assert_correctness_locally();
+            if let Some(node) = self.walker.node() {
+                WBTree::assert_balance_locally_internal(node);
+            }
+        }
+    }
+}
+
+impl<'a, D: Data> WBWalker<'a, D> {
+    fn rot_left(&mut self) -> Option<()> {
+        self.walker.rot_left_with_custom_rebuilder(rebuild_size)
+    }
+
+    fn rot_right(&mut self) -> Option<()> {
+        self.walker.rot_right_with_custom_rebuilder(rebuild_size)
+    }
+
+    /// Goes up without checking the sizes, since they might be incorrect.
+    fn go_up_unchecked(&mut self) -> Result<Side, ()> {
+        let res = self.walker.go_up()?;
+        rebuild_size(self.walker.node_mut().unwrap());
+        Ok(res)
+    }
+
+    /// Rebalances the current node, assuming its sons are balanced,
+    /// and that their weights are only a little bit off.
+    /// Does a single or a double rotation.
+    fn rebalance_node(&mut self) {
+        let node = self.walker.node().unwrap();
+        let (left, right) = (&node.left, &node.right);
+        let (left_weight, right_weight) = (weight(left), weight(right));
+        if like(left_weight, right_weight) {
+            return;
+        }
+        if left_weight < right_weight {
+            let (inner_weight, outer_weight) = sons_weights(right);
+            if like(left_weight, inner_weight) && like(left_weight + inner_weight, outer_weight) {
+                self.rot_left().unwrap();
+            } else {
+                self.go_right().unwrap();
+                self.rot_right().unwrap();
+                self.go_up_unchecked().unwrap();
+                self.rot_left().unwrap();
+            }
+        } else {
+            let (outer_weight, inner_weight) = sons_weights(left);
+            if like(right_weight, inner_weight) && like(right_weight + inner_weight, outer_weight)
+            {
+                self.rot_right().unwrap();
+            } else {
+                self.go_left().unwrap();
+                self.rot_left().unwrap();
+                self.go_up_unchecked().unwrap();
+                self.rot_right().unwrap();
+            }
+        }
+    }
+
+    /// This function gets called when a node is deleted or inserted,
+    /// at the current position. It fixes the sizes of all the nodes up to the root,
+    /// and rebalances them. The walker ends at the root.
+    fn rebalance(&mut self) {
+        if let Some(node) = self.walker.node_mut() {
+            rebuild_size(node);
+            self.rebalance_node();
+        }
+        while self.go_up_unchecked().is_ok() {
+            self.rebalance_node();
+        }
+    }
+
+    /// Deletes a node and returns it with the box.
+    fn delete_boxed(&mut self) -> Option<Box<BasicNode<D, T>>> {
+        // the delete implementation is copied from `BasicTree`,
+        // in order for rebalancing to be done properly.
+        let mut node = self.walker.take_subtree().into_node_boxed()?;
+        if node.right.is_empty() {
+            self.walker.put_subtree(node.left).unwrap();
+            node.left = BasicTree::Empty;
+        } else {
+            // find the next node and move it to the current position
+            let mut walker = node.right.walker();
+            while walker.go_left().is_ok() {}
+            let res = walker.go_up();
+            assert_eq!(res, Ok(Side::Left));
+
+            let mut boxed_replacement_node = walker.take_subtree().into_node_boxed().unwrap();
+            assert!(boxed_replacement_node.left.is_empty());
+            walker.put_subtree(boxed_replacement_node.right).unwrap();
+            WBWalker { walker }.rebalance(); // rebalance here
+
+            boxed_replacement_node.left = node.left;
+            node.left = BasicTree::Empty;
+            boxed_replacement_node.right = node.right;
+            node.right = BasicTree::Empty;
+            boxed_replacement_node.rebuild();
+            self.walker
+                .put_subtree(BasicTree::Root(boxed_replacement_node))
+                .unwrap();
+        }
+        self.rebalance();
+        Some(node)
+    }
+}
+
+impl<'a, D: Data> ModifiableWalker<D> for WBWalker<'a, D> {
+    /// Inserts the value into the tree at the current empty position.
+    /// If the current position is not empty, return [`None`].
+    /// When the function returns, the walker will be at the root.
+    fn insert(&mut self, val: D::Value) -> Option<()> {
+        self.walker.insert_with_alg_data(val, 1)?;
+        self.rebalance();
+        Some(())
+    }
+
+    /// Removes the current value from the tree, and returns it.
+    /// If currently at an empty position, returns [`None`].
+    /// When the function returns, the walker will be at the root.
+    fn delete(&mut self) -> Option<D::Value> {
+        Some(self.delete_boxed()?.node_value)
+    }
+}
+
+impl<'a, D: Data> SplittableWalker<D> for WBWalker<'a, D> {
+    type T = WBTree<D>;
+
+    /// Will only do anything if the current position is empty.
+    /// If it is empty, it will split the tree: the elements
+    /// to the left will remain, and the elements to the right
+    /// will be put in the new output tree.
+    /// The walker will be at the root after this operation, if it succeeds.
+    ///
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: WBTree<StdNum> = (17..88).collect();
+    /// let mut tree2 = tree.slice(7..7).split_right().unwrap();
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    /// # tree2.assert_correctness();
+    ///```
+    fn split_right(&mut self) -> Option<Self::T> {
+        if !self.is_empty() {
+            return None;
+        }
+        let mut left = WBTree::new();
+        let mut right = WBTree::new();
+
+        // sizes may be incorrect, so go up with the inner walker
+        while let Ok(side) = self.walker.go_up() {
+            let mut node = self.walker.take_subtree().into_node_boxed().unwrap();
+            match side {
+                Side::Left => {
+                    assert!(node.left.is_empty());
+                    let auxiliary_right = WBTree { tree: node.right };
+                    node.right = BasicTree::Empty;
+                    right.concatenate_boxed_middle_right(node, auxiliary_right);
+                }
+                Side::Right => {
+                    assert!(node.right.is_empty());
+                    let auxiliary_left = WBTree { tree: node.left };
+                    node.left = BasicTree::Empty;
+                    left.concatenate_boxed_middle_left(auxiliary_left, node);
+                }
+            }
+        }
+
+        // the `self` tree is empty by this point.
+        self.walker.put_subtree(left.tree).unwrap();
+        Some(right)
+    }
+
+    /// Will only do anything if the current position is empty.
+    /// If it is empty, it will split the tree: the elements
+    /// to the left will remain, and the elements to the right
+    /// will be put in the new output tree.
+    /// The walker will be at the root after this operation, if it succeeds.
+    ///
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: WBTree<StdNum> = (17..88).collect();
+    /// let mut tree2 = tree.slice(7..7).split_left().unwrap();
+    ///
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (17..24).collect::<Vec<_>>());
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (24..88).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    /// # tree2.assert_correctness();
+    ///```
+    fn split_left(&mut self) -> Option<Self::T> {
+        let mut right = self.split_right()?;
+        std::mem::swap(&mut right.tree, self.walker.inner_mut());
+        Some(right)
+    }
+}
+
+impl<D: Data> WBTree<D> {
+    /// Concatenates the trees together, in place, with a given value for the middle.
+    /// Complexity: `O(log n)`.
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: WBTree<StdNum> = (17..=89).collect();
+    /// let tree2: WBTree<StdNum> = (13..=25).collect();
+    /// tree.concatenate_middle_right(5, tree2);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    pub fn concatenate_middle_right(&mut self, mid: D::Value, right: WBTree<D>) {
+        let node = BasicNode::new_alg(mid, 1);
+        self.concatenate_boxed_middle_right(Box::new(node), right);
+    }
+
+    fn concatenate_boxed_middle_right(
+        &mut self,
+        mut mid: Box<BasicNode<D, T>>,
+        mut right: WBTree<D>,
+    ) {
+        if weight(&self.tree) < weight(&right.tree) {
+            std::mem::swap(self, &mut right);
+            self.concatenate_boxed_middle_left(right, mid);
+            return;
+        }
+        // find the first subtree on the right edge which is light enough
+        // to be a sibling of `right`
+        let mut walker = self.walker();
+        while !like(weight(walker.walker.inner()), weight(&right.tree)) {
+            walker.go_right().unwrap();
+        }
+        mid.left = walker.walker.take_subtree();
+        mid.right = right.tree;
+        mid.rebuild();
+        walker.walker.put_subtree(BasicTree::Root(mid)).unwrap();
+        walker.rebalance();
+    }
+
+    /// Concatenates the trees together, in place, with a given value for the middle.
+    /// Complexity: `O(log n)`.
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let tree1: WBTree<StdNum> = (17..=89).collect();
+    /// let mut tree2: WBTree<StdNum> = (13..=25).collect();
+    /// tree2.concatenate_middle_left(tree1, 5);
+    ///
+    /// assert_eq!(tree2.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+    /// # tree2.assert_correctness();
+    ///```
+    pub fn concatenate_middle_left(&mut self, left: WBTree<D>, mid: D::Value) {
+        let node = BasicNode::new_alg(mid, 1);
+        self.concatenate_boxed_middle_left(left, Box::new(node));
+    }
+
+    fn concatenate_boxed_middle_left(
+        &mut self,
+        mut left: WBTree<D>,
+        mut mid: Box<BasicNode<D, T>>,
+    ) {
+        if weight(&self.tree) < weight(&left.tree) {
+            std::mem::swap(self, &mut left);
+            self.concatenate_boxed_middle_right(mid, left);
+            return;
+        }
+        // find the first subtree on the left edge which is light enough
+        // to be a sibling of `left`
+        let mut walker = self.walker();
+        while !like(weight(walker.walker.inner()), weight(&left.tree)) {
+            walker.go_left().unwrap();
+        }
+        mid.right = walker.walker.take_subtree();
+        mid.left = left.tree;
+        mid.rebuild();
+        walker.walker.put_subtree(BasicTree::Root(mid)).unwrap();
+        walker.rebalance();
+    }
+
+    /// Computes the union of two trees, ordered by keys.
+    /// We order the resulting tree based on the `D::Value: Keyed` instance, assuming that
+    /// the values in the existing trees are also in the correct order.
+    ///
+    /// Values with equal keys are all kept, and the values from `tree2` are placed after
+    /// the values of `self` with the same key.
+    ///
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = WBTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.union(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3,4,4,5,5,6,6,7,8].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn union(&mut self, tree2: WBTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = union_internal(tree1, tree2);
+    }
+
+    /// Computes the intersection of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys appear in `tree2`. Values with equal keys in `self` are all kept.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = WBTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.intersection(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [4,5,6].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn intersection(&mut self, tree2: WBTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = intersection_internal(tree1, tree2);
+    }
+
+    /// Computes the difference of two trees, ordered by keys: keeps only the values of `self`
+    /// whose keys don't appear in `tree2`.
+    /// We assume that the values in the existing trees are in the correct order.
+    ///
+    ///```
+    /// use grove::{SomeTree, weight_balanced::WBTree};
+    /// use grove::example_data::{NoAction, Ordered};
+    ///
+    /// type T = WBTree<NoAction<Ordered<i32>>>;
+    /// let mut tree: T = (0..7).map(|x| Ordered(x)).collect();
+    /// let tree2: T = (4..9).map(|x| Ordered(x)).collect();
+    /// tree.difference(tree2);
+    /// # tree.assert_correctness();
+    /// assert_eq!(tree.into_iter().collect::<Vec<_>>(), [0,1,2,3].iter().map(|x| Ordered(*x)).collect::<Vec<_>>());
+    ///```
+    ///
+    /// # Complexity
+    /// If the sizes of the two trees are `n,k`, with `n < k`, then the complexity is
+    /// `O(n*log(1+k/n))` in the worst case.
+    pub fn difference(&mut self, tree2: WBTree<D>)
+    where
+        D::Value: Keyed,
+    {
+        let tree1 = std::mem::take(self);
+        *self = difference_internal(tree1, tree2);
+    }
+}
+
+/// Splits the tree into the values with keys smaller than `key`, the values with keys equal
+/// to `key`, and the values with keys bigger than `key`.
+fn split_by_key<D: Data>(
+    mut tree: WBTree<D>,
+    key: &<D::Value as Keyed>::Key,
+) -> (WBTree<D>, WBTree<D>, WBTree<D>)
+where
+    D::Value: Keyed,
+{
+    let mut mid = tree
+        .slice(locators::LeftEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    let right = mid
+        .slice(locators::RightEdgeOf(locators::ByKey((key,))))
+        .split_right()
+        .unwrap();
+    (tree, mid, right)
+}
+
+fn union_internal<D: Data>(tree1: WBTree<D>, tree2: WBTree<D>) -> WBTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree2;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let mut left1 = tree1;
+    let right1 = left1
+        .slice(locators::RightEdgeOf(locators::ByKey((mid.node_value.get_key(),))))
+        .split_right()
+        .unwrap();
+
+    let mut res = union_internal(left1, left2);
+    res.concatenate_boxed_middle_right(mid, union_internal(right1, right2));
+    res
+}
+
+fn intersection_internal<D: Data>(tree1: WBTree<D>, tree2: WBTree<D>) -> WBTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return WBTree::new(),
+        Some(parts) => parts,
+    };
+    let (left1, equal1, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = intersection_internal(left1, left2);
+    res.concatenate_right(equal1);
+    res.concatenate_right(intersection_internal(right1, right2));
+    res
+}
+
+fn difference_internal<D: Data>(tree1: WBTree<D>, tree2: WBTree<D>) -> WBTree<D>
+where
+    D::Value: Keyed,
+{
+    if tree1.is_empty() {
+        return tree1;
+    }
+    let (left2, mid, right2) = match tree2.expose() {
+        None => return tree1,
+        Some(parts) => parts,
+    };
+    let (left1, _, right1) = split_by_key(tree1, mid.node_value.get_key());
+
+    let mut res = difference_internal(left1, left2);
+    res.concatenate_right(difference_internal(right1, right2));
+    res
+}
+
+impl<D: Data> SetOperations<D> for WBTree<D> {
+    fn union(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        WBTree::union(self, other);
+    }
+
+    fn intersection(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        WBTree::intersection(self, other);
+    }
+
+    fn difference(&mut self, other: Self)
+    where
+        D::Value: Keyed,
+    {
+        WBTree::difference(self, other);
+    }
+}
+
+impl<D: Data> ConcatenableTree<D> for WBTree<D> {
+    /// Concatenates the trees together, in place.
+    /// Complexity: `O(log n)`.
+    ///```
+    /// use grove::{SomeTree, ConcatenableTree, weight_balanced::WBTree};
+    /// use grove::example_data::StdNum;
+    ///
+    /// let mut tree: WBTree<StdNum> = (17..=89).collect();
+    /// let tree2: WBTree<StdNum> = (13..=25).collect();
+    /// tree.concatenate_right(tree2);
+    ///
+    /// assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(13..=25).collect::<Vec<_>>());
+    /// # tree.assert_correctness();
+    ///```
+    fn concatenate_right(&mut self, mut right: Self) {
+        if !right.is_empty() {
+            let mut walker = right.search(locators::LeftEdgeOf(..));
+            walker.go_up().unwrap();
+            let mid = walker.delete_boxed().unwrap();
+            drop(walker);
+            self.concatenate_boxed_middle_right(mid, right);
+        }
+    }
+}
+
+/// Concatenates the trees together, in place, with a given value for the middle.
+/// Complexity: `O(log n)`.
+///```
+/// use grove::{SomeTree, weight_balanced::WBTree, weight_balanced::concatenate_with_middle};
+/// use grove::example_data::StdNum;
+///
+/// let tree1: WBTree<StdNum> = (17..=89).collect();
+/// let tree2: WBTree<StdNum> = (13..=25).collect();
+/// let mut tree3 = concatenate_with_middle(tree1, 5, tree2);
+///
+/// assert_eq!(tree3.iter().cloned().collect::<Vec<_>>(), (17..=89).chain(5..=5).chain(13..=25).collect::<Vec<_>>());
+/// # tree3.assert_correctness();
+///```
+pub fn concatenate_with_middle<D: Data>(
+    mut left: WBTree<D>,
+    mid: D::Value,
+    right: WBTree<D>,
+) -> WBTree<D> {
+    left.concatenate_middle_right(mid, right);
+    left
+}
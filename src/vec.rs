@@ -0,0 +1,321 @@
+//! A vector-like sequence, implemented on top of any of the splittable trees in this library.
+//!
+//! [`GroveVec`] is an indexable sequence, which supports inserting and removing values at
+//! any index, splitting, appending, reversing and rotating segments, all in logarithmic time.
+//! It is a thin wrapper around a tree with the [`SequenceData`] instance, so it doesn't
+//! require writing a [`Data`] instance or using locators directly.
+//!
+//! Like the trees themselves, most queries receive `&mut self`, since some trees (e.g, splay trees)
+//! restructure themselves on every access. Indexing with `vec[i]` is also possible, for trees
+//! that can be queried through shared references (see [`QueryableTree`]).
+//!
+//!```
+//! use grove::vec::GroveVec;
+//!
+//! let mut vec: GroveVec<i32> = (0..10).collect();
+//! vec.reverse(2..5);
+//! vec.insert(0, 100);
+//! assert_eq!(vec.remove(3), 4);
+//!
+//! let mut tail = vec.split_off(5);
+//! tail.rotate_left(.., 2);
+//! vec.append(&mut tail);
+//!
+//! assert_eq!(vec[0], 100);
+//! assert_eq!(vec.iter().cloned().collect::<Vec<_>>(), vec![100, 0, 1, 3, 2, 7, 8, 9, 5, 6]);
+//!```
+
+use crate::example_data::{ReverseOrder, SequenceData};
+use crate::treap::Treap;
+use crate::*;
+use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeBounds};
+
+/// A vector-like sequence of values of type `T`, implemented using a tree of type `Tree`.
+/// See the [module documentation](self).
+pub struct GroveVec<T, Tree = Treap<SequenceData<T>>> {
+    tree: Tree,
+    phantom: PhantomData<T>,
+}
+
+impl<T, Tree> GroveVec<T, Tree>
+where
+    Tree: ConcatenableTree<SequenceData<T>>,
+    for<'a> &'a mut Tree: SplittableTreeRef<SequenceData<T>, T = Tree>,
+{
+    /// Creates an empty vector.
+    pub fn new() -> Self {
+        GroveVec {
+            tree: Tree::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of values in the vector.
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Returns [`true`] if the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all values from the vector.
+    pub fn clear(&mut self) {
+        self.tree = Tree::default();
+    }
+
+    /// Returns a reference to the underlying tree.
+    pub fn as_tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Returns the underlying tree.
+    pub fn into_tree(self) -> Tree {
+        self.tree
+    }
+
+    /// Converts any range of indices into a [`Range`].
+    /// Panics if the range is out of bounds.
+    fn index_range<R: RangeBounds<usize>>(&self, range: R) -> Range<usize> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(
+            start <= end && end <= self.len(),
+            "range {}..{} out of bounds for length {}",
+            start,
+            end,
+            self.len()
+        );
+        start..end
+    }
+
+    /// Appends a value to the end of the vector.
+    pub fn push(&mut self, value: T) {
+        let len = self.len();
+        self.tree.search(len..len).insert(value);
+    }
+
+    /// Removes the last value and returns it, or returns [`None`] if the vector is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        let index = self.len().checked_sub(1)?;
+        self.tree.search(index).delete()
+    }
+
+    /// Inserts a value at the given index, shifting all values after it to the right.
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        self.tree.search(index..index).insert(value);
+    }
+
+    /// Removes the value at the given index and returns it, shifting all values after it to the left.
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(
+            index < len,
+            "removal index (is {}) should be < len (is {})",
+            index,
+            len
+        );
+        self.tree.search(index).delete().unwrap()
+    }
+
+    /// Returns a reference to the value at the given index.
+    pub fn get(&mut self, index: usize) -> Option<&T> {
+        self.tree.iter_locator(index).next()
+    }
+
+    /// Returns a guard that allows modifying the value at the given index in place.
+    /// Returns [`None`] if `index` is out of bounds.
+    ///
+    /// The guard holds a walker positioned at the value. When the guard is dropped,
+    /// the node and all of its ancestors are rebuilt.
+    ///```
+    /// use grove::vec::GroveVec;
+    ///
+    /// let mut vec: GroveVec<String> = vec!["a".to_string(), "b".to_string()].into_iter().collect();
+    /// vec.get_mut(1).unwrap().push('c');
+    ///
+    /// assert_eq!(vec.get(1).unwrap(), "bc");
+    /// assert!(vec.get_mut(2).is_none());
+    ///```
+    pub fn get_mut(&mut self, index: usize) -> Option<ValueMut<'_, T, Tree::TreeData>> {
+        let mut cursor = self.tree.cursor_mut(index);
+        cursor.next()?;
+        Some(ValueMut { cursor })
+    }
+
+    /// Modifies the value at the given index in place, and returns the closure's result.
+    /// Returns [`None`] if `index` is out of bounds.
+    ///```
+    /// use grove::vec::GroveVec;
+    ///
+    /// let mut vec: GroveVec<String> = vec!["a".to_string(), "b".to_string()].into_iter().collect();
+    /// assert_eq!(vec.with_value(1, |value| { value.push('c'); value.len() }), Some(2));
+    ///
+    /// assert_eq!(vec.get(1).unwrap(), "bc");
+    /// assert_eq!(vec.with_value(2, |value| value.push('d')), None);
+    ///```
+    pub fn with_value<F, R>(&mut self, index: usize, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.tree.search(index).with_value(f)
+    }
+
+    /// Iterates over the values of the vector, in order.
+    pub fn iter(
+        &mut self,
+    ) -> basic_tree::iterators::IterLocator<'_, SequenceData<T>, std::ops::RangeFull, Tree::TreeData>
+    {
+        self.tree.iter()
+    }
+
+    /// Splits the vector into two at the given index.
+    /// Returns a new vector containing the values from index `at` onwards,
+    /// and leaves the values before index `at` in `self`.
+    /// Panics if `at > len`.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len();
+        assert!(
+            at <= len,
+            "`at` split index (is {}) should be <= len (is {})",
+            at,
+            len
+        );
+        GroveVec {
+            tree: self.tree.slice(at..at).split_right().unwrap(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Moves all the values of `other` to the end of `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        self.tree.concatenate_right(std::mem::take(&mut other.tree));
+    }
+
+    /// Reverses the order of the values in the given range of indices.
+    /// Panics if the range is out of bounds.
+    pub fn reverse<R: RangeBounds<usize>>(&mut self, range: R) {
+        let range = self.index_range(range);
+        self.tree
+            .act_segment(ReverseOrder { to_reverse: true }, range);
+    }
+
+    /// Rotates the given range of indices in-place, such that the first `mid` values of the range
+    /// move to its end, like [`slice::rotate_left`].
+    /// Panics if the range is out of bounds, or if `mid` is bigger than the range's length.
+    pub fn rotate_left<R: RangeBounds<usize>>(&mut self, range: R, mid: usize) {
+        let range = self.index_range(range);
+        assert!(mid <= range.len());
+        let mut right = self.split_off(range.end);
+        let mut second = self.split_off(range.start + mid);
+        let mut first = self.split_off(range.start);
+        self.append(&mut second);
+        self.append(&mut first);
+        self.append(&mut right);
+    }
+
+    /// Rotates the given range of indices in-place, such that the last `k` values of the range
+    /// move to its start, like [`slice::rotate_right`].
+    /// Panics if the range is out of bounds, or if `k` is bigger than the range's length.
+    pub fn rotate_right<R: RangeBounds<usize>>(&mut self, range: R, k: usize) {
+        let range = self.index_range(range);
+        assert!(k <= range.len());
+        let mid = range.len() - k;
+        self.rotate_left(range, mid);
+    }
+}
+
+impl<T, Tree> Default for GroveVec<T, Tree>
+where
+    Tree: ConcatenableTree<SequenceData<T>>,
+    for<'a> &'a mut Tree: SplittableTreeRef<SequenceData<T>, T = Tree>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Tree> Extend<T> for GroveVec<T, Tree>
+where
+    Tree: ConcatenableTree<SequenceData<T>>,
+    for<'a> &'a mut Tree: SplittableTreeRef<SequenceData<T>, T = Tree>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.tree.concatenate_right(iter.into_iter().collect());
+    }
+}
+
+impl<T, Tree> std::iter::FromIterator<T> for GroveVec<T, Tree>
+where
+    Tree: ConcatenableTree<SequenceData<T>>,
+    for<'a> &'a mut Tree: SplittableTreeRef<SequenceData<T>, T = Tree>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        GroveVec {
+            tree: iter.into_iter().collect(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, Tree: IntoIterator<Item = T>> IntoIterator for GroveVec<T, Tree> {
+    type Item = T;
+    type IntoIter = Tree::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.tree.into_iter()
+    }
+}
+
+/// A guard for modifying a value in a [`GroveVec`] in place. See [`GroveVec::get_mut`].
+pub struct ValueMut<'a, T, TreeData> {
+    // always positioned at the value.
+    cursor: basic_tree::iterators::CursorMut<'a, SequenceData<T>, usize, TreeData>,
+}
+
+impl<'a, T, TreeData> std::ops::Deref for ValueMut<'a, T, TreeData> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.cursor.current().unwrap()
+    }
+}
+
+impl<'a, T, TreeData> std::ops::DerefMut for ValueMut<'a, T, TreeData> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.cursor.current_mut().unwrap()
+    }
+}
+
+/// Indexing through a shared reference. Available for trees that implement [`QueryableTree`].
+impl<T, Tree> std::ops::Index<usize> for GroveVec<T, Tree>
+where
+    Tree: QueryableTree<SequenceData<T>>,
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.tree
+            .search_ref(index)
+            .unwrap_or_else(|| panic!("index out of bounds: the index is {}", index))
+    }
+}
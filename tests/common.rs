@@ -1,5 +1,6 @@
 
 use grove::*;
+use grove::persistent_treap::PersistentTreap;
 use example_data::{StdNum, RevAffineAction};
 use rand::{self, Rng};
 
@@ -145,3 +146,1197 @@ where
         );
     }
 }
+
+/// Runs random operations on a persistent treap and on a regular tree, and checks that
+/// they agree. Also checks that the old versions of the persistent treap stay unchanged.
+pub fn check_persistent_consistency<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: ModifiableTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut len: usize = INITIAL_SIZE;
+
+    let range = 0..(len as _);
+    let mut tree1: PersistentTreap<StdNum> = range.clone().collect();
+    let mut tree2: T = range.collect();
+    // old versions, along with their expected contents
+    let mut versions = vec![];
+
+    for round in 0..NUM_ROUNDS {
+        match rng.gen_range(0..4) {
+            // act on a segment
+            0 => {
+                let range = &random_range(len);
+                let action = random_action(&mut rng);
+                tree1 = tree1.act_segment(action, range);
+                tree2.act_segment(action, range);
+            }
+            // query a segment
+            1 => {
+                let range = &random_range(len);
+                let sum1 = tree1.segment_summary(range);
+                let sum2 = tree2.segment_summary(range);
+                assert_eq!(sum1, sum2);
+                assert_eq!(sum1.size(), range.len());
+                assert_eq!(tree1.search(range.start), tree2.select(range.start).copied());
+            }
+            // insert a value
+            2 => {
+                let value = rng.gen_range(-MAX_ADD..=MAX_ADD);
+                let index = rng.gen_range(0..=len);
+                tree1 = tree1.insert(index..index, value).unwrap();
+                tree2.slice(index..index).insert(value).unwrap();
+                len += 1;
+            }
+            // delete a value
+            3 if len > 0 => {
+                let index = rng.gen_range(0..len);
+                let (new_tree, val1) = tree1.delete(index).unwrap();
+                tree1 = new_tree;
+                let val2 = tree2.slice(index..=index).delete();
+                assert_eq!(Some(val1), val2);
+                len -= 1;
+            }
+            // delete but the tree is empty
+            3 => {}
+            _ => panic!(),
+        }
+        let s1 = tree1.subtree_summary();
+        let s2 = tree2.subtree_summary();
+        assert_eq!(s1, s2);
+        assert_eq!(s1.size(), len);
+        tree1.assert_correctness();
+
+        if round % 100 == 0 {
+            versions.push((tree1.clone(), tree1.iter().collect::<Vec<_>>()));
+        }
+    }
+
+    assert_eq!(
+        tree1.iter().collect::<Vec<_>>(),
+        tree2.into_iter().collect::<Vec<_>>()
+    );
+    for (version, values) in versions {
+        assert_eq!(version.iter().collect::<Vec<_>>(), values);
+        version.assert_correctness();
+    }
+}
+
+/// Acts on random segments of the tree, and checks that the immutable queries agree
+/// with the regular queries of a splay tree.
+/// Only uses reversals if `supports_reversals` is true.
+pub fn check_immutable_queries<T>(supports_reversals: bool)
+where
+    T: SomeTree<StdNum> + QueryableTree<StdNum>,
+    for<'a> &'a mut T: ModifiableTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let len: usize = INITIAL_SIZE;
+
+    let range = 0..(len as _);
+    let mut tree1: T = range.clone().collect();
+    let mut tree2: splay::SplayTree<StdNum> = range.collect();
+
+    for _ in 0..NUM_ROUNDS {
+        match rng.gen_range(0..3) {
+            // act on a segment
+            0 => {
+                let range = &random_range(len);
+                let mut action = random_action(&mut rng);
+                action.to_reverse &= supports_reversals;
+                tree1.act_segment(action, range);
+                tree2.act_segment(action, range);
+            }
+            // query a segment
+            1 => {
+                let range = &random_range(len);
+                let sum1 = tree1.segment_summary_imm(range);
+                let sum2 = tree2.segment_summary(range);
+                assert_eq!(sum1, sum2);
+                assert_eq!(sum1.size(), range.len());
+            }
+            // search a value
+            2 => {
+                let index = rng.gen_range(0..len);
+                let val1 = tree1.search_imm(index).map(|val| *val);
+                let val2 = tree2.search(index).value().cloned();
+                assert_eq!(val1, val2);
+                assert!(val1.is_some());
+            }
+            _ => panic!(),
+        }
+    }
+
+    let range = &random_range(len);
+    assert_eq!(
+        tree1.iter_locator_imm(range).map(|val| *val).collect::<Vec<_>>(),
+        tree2.iter_locator(range).cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        tree1.iter_imm().map(|val| *val).collect::<Vec<_>>(),
+        tree2.iter().cloned().collect::<Vec<_>>()
+    );
+    tree1.assert_correctness();
+}
+
+/// Queries the tree from several threads at once, and checks that the batched parallel
+/// queries agree with the regular queries.
+pub fn check_concurrent_queries<T>(supports_reversals: bool)
+where
+    T: SomeTree<StdNum> + QueryableTree<StdNum> + Send + Sync + 'static,
+    for<'a> &'a mut T: ModifiableTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let len: usize = INITIAL_SIZE;
+
+    let mut tree: T = (0..(len as _)).collect();
+    for _ in 0..100 {
+        let mut action = random_action(&mut rng);
+        action.to_reverse &= supports_reversals;
+        tree.act_segment(action, &random_range(len));
+    }
+
+    let locators: Vec<_> = (0..1000).map(|_| random_range(len)).collect();
+    let expected: Vec<_> = locators
+        .iter()
+        .map(|locator| tree.segment_summary(locator))
+        .collect();
+    assert_eq!(tree.par_segment_summaries(&locators), expected);
+
+    let tree = std::sync::Arc::new(std::sync::RwLock::new(tree));
+    let locators = std::sync::Arc::new(locators);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let tree = tree.clone();
+            let locators = locators.clone();
+            std::thread::spawn(move || {
+                let guard = tree.read().unwrap();
+                locators
+                    .iter()
+                    .map(|locator| guard.segment_summary_imm(locator))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), expected);
+    }
+}
+
+/// Checks the set operations against sorted vectors, with many duplicate keys.
+pub fn check_set_operations<T>()
+where
+    T: SomeTree<example_data::NoAction<example_data::Ordered<i32>>>
+        + SetOperations<example_data::NoAction<example_data::Ordered<i32>>>,
+    for<'a> &'a mut T: SomeTreeRef<example_data::NoAction<example_data::Ordered<i32>>>,
+{
+    use example_data::Ordered;
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        // either tree may be the smaller one
+        let mut values1: Vec<i32> = (0..rng.gen_range(0..500)).map(|_| rng.gen_range(0..300)).collect();
+        let mut values2: Vec<i32> = (0..rng.gen_range(0..500)).map(|_| rng.gen_range(0..300)).collect();
+        values1.sort_unstable();
+        values2.sort_unstable();
+        let make_tree = |values: &Vec<i32>| -> T { values.iter().map(|&x| Ordered(x)).collect() };
+        let contains = |values: &Vec<i32>, x: &i32| values.binary_search(x).is_ok();
+
+        let mut tree = make_tree(&values1);
+        tree.union(make_tree(&values2));
+        tree.assert_correctness();
+        let mut expected: Vec<i32> = values1.iter().chain(values2.iter()).cloned().collect();
+        expected.sort_unstable();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+        let mut tree = make_tree(&values1);
+        tree.intersection(make_tree(&values2));
+        tree.assert_correctness();
+        let expected: Vec<i32> = values1.iter().filter(|x| contains(&values2, x)).cloned().collect();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+        let mut tree = make_tree(&values1);
+        tree.difference(make_tree(&values2));
+        tree.assert_correctness();
+        let expected: Vec<i32> = values1.iter().filter(|x| !contains(&values2, x)).cloned().collect();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+    }
+}
+
+/// Checks that a union keeps all values with equal keys, and puts the values of
+/// the second tree after the values of the first tree.
+pub fn check_union_order<T>()
+where
+    T: SomeTree<example_data::NoAction<(i32, usize)>>
+        + SetOperations<example_data::NoAction<(i32, usize)>>,
+    for<'a> &'a mut T: SomeTreeRef<example_data::NoAction<(i32, usize)>>,
+{
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        // either tree may be the smaller one
+        let (size1, size2) = (rng.gen_range(0..300), rng.gen_range(0..300));
+        let mut values1: Vec<(i32, usize)> = (0..size1).map(|i| (rng.gen_range(0..50), i)).collect();
+        let mut values2: Vec<(i32, usize)> = (size1..size1 + size2)
+            .map(|i| (rng.gen_range(0..50), i))
+            .collect();
+        values1.sort_by_key(|x| x.0);
+        values2.sort_by_key(|x| x.0);
+
+        let mut tree: T = values1.iter().cloned().collect();
+        tree.union(values2.iter().cloned().collect());
+        tree.assert_correctness();
+        let mut expected: Vec<(i32, usize)> = values1.iter().chain(values2.iter()).cloned().collect();
+        // stable, so values with equal keys stay in order
+        expected.sort_by_key(|x| x.0);
+        assert_eq!(tree.into_iter().collect::<Vec<_>>(), expected);
+    }
+}
+
+/// Key-value pairs, with summaries of the sum of the values, and actions that add to the values.
+pub struct SumValues {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SizeSum {
+    size: usize,
+    sum: i64,
+}
+
+impl std::ops::Add for SizeSum {
+    type Output = SizeSum;
+    fn add(self, other: SizeSum) -> SizeSum {
+        SizeSum {
+            size: self.size + other.size,
+            sum: self.sum + other.sum,
+        }
+    }
+}
+
+impl SizedSummary for SizeSum {
+    fn size(self) -> usize {
+        self.size
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AddToValues(i64);
+
+impl std::ops::Add for AddToValues {
+    type Output = AddToValues;
+    fn add(self, other: AddToValues) -> AddToValues {
+        AddToValues(self.0 + other.0)
+    }
+}
+
+impl Action for AddToValues {
+    fn is_identity(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Acts<(i32, i64)> for AddToValues {
+    fn act_inplace(&self, entry: &mut (i32, i64)) {
+        entry.1 += self.0;
+    }
+}
+
+impl Acts<SizeSum> for AddToValues {
+    fn act_inplace(&self, summary: &mut SizeSum) {
+        summary.sum += self.0 * summary.size as i64;
+    }
+}
+
+impl Data for SumValues {
+    type Value = (i32, i64);
+    type Summary = SizeSum;
+    type Action = AddToValues;
+
+    fn to_summary(entry: &(i32, i64)) -> SizeSum {
+        SizeSum {
+            size: 1,
+            sum: entry.1,
+        }
+    }
+
+    fn segment_len(summary: SizeSum) -> Option<usize> {
+        Some(summary.size)
+    }
+}
+
+impl SizedData for SumValues {}
+
+pub fn check_map<T>()
+where
+    T: SomeTree<SumValues>,
+    for<'a> &'a mut T: ModifiableTreeRef<SumValues>,
+{
+    use grove::map::GroveMap;
+    use std::collections::BTreeMap;
+
+    const MAX_KEY: i32 = 300;
+    let mut rng = rand::thread_rng();
+    let mut map: GroveMap<i32, i64, SumValues, T> = GroveMap::new();
+    let mut expected: BTreeMap<i32, i64> = BTreeMap::new();
+
+    for _ in 0..NUM_ROUNDS {
+        let key = rng.gen_range(0..MAX_KEY);
+        let (a, b) = (rng.gen_range(0..MAX_KEY), rng.gen_range(0..MAX_KEY));
+        let range = std::cmp::min(a, b)..=std::cmp::max(a, b);
+        match rng.gen_range(0..8) {
+            0 | 1 => {
+                let value = rng.gen_range(-100..100);
+                assert_eq!(map.insert(key, value), expected.insert(key, value));
+            }
+            2 => assert_eq!(map.remove(&key), expected.remove(&key)),
+            3 => {
+                map.entry(key).and_modify(|value| *value *= 2).or_insert(1);
+                expected.entry(key).and_modify(|value| *value *= 2).or_insert(1);
+            }
+            4 => {
+                let add = rng.gen_range(-100..100);
+                map.act_range(range.clone(), AddToValues(add));
+                for (_, value) in expected.range_mut(range) {
+                    *value += add;
+                }
+            }
+            5 => {
+                let summary = map.range_summary(range.clone());
+                let values = expected.range(range);
+                assert_eq!(summary.size, values.clone().count());
+                assert_eq!(summary.sum, values.map(|(_, value)| value).sum::<i64>());
+            }
+            6 => {
+                let rank = map.rank(&key);
+                assert_eq!(rank, expected.range(..key).count());
+                assert_eq!(map.select(rank), expected.range(key..).next());
+            }
+            _ => {
+                if rng.gen() {
+                    assert_eq!(map.pop_first(), expected.pop_first());
+                } else {
+                    assert_eq!(map.pop_last(), expected.pop_last());
+                }
+            }
+        }
+        assert_eq!(map.get(&key), expected.get(&key));
+        assert_eq!(map.len(), expected.len());
+    }
+
+    map.as_tree().assert_correctness();
+    assert_eq!(map.iter().size_hint(), (expected.len(), Some(expected.len())));
+    assert_eq!(map.first_key_value(), expected.iter().next());
+    assert_eq!(map.last_key_value(), expected.iter().next_back());
+    let bounds = (std::ops::Bound::Excluded(MAX_KEY / 3), std::ops::Bound::Unbounded);
+    assert!(map.range(bounds).eq(expected.range(bounds)));
+    assert!(map.into_iter().eq(expected.into_iter()));
+}
+
+pub fn check_vec<T>()
+where
+    T: ConcatenableTree<example_data::SequenceData<i32>>,
+    for<'a> &'a mut T: SplittableTreeRef<example_data::SequenceData<i32>, T = T>,
+{
+    use grove::vec::GroveVec;
+
+    let mut rng = rand::thread_rng();
+    let mut vec: GroveVec<i32, T> = (0..INITIAL_SIZE as i32).collect();
+    let mut expected: Vec<i32> = (0..INITIAL_SIZE as i32).collect();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let len = expected.len();
+        let index = rng.gen_range(0..len + 1);
+        let range = random_range(len);
+        match rng.gen_range(0..9) {
+            0 => {
+                let value = rng.gen();
+                vec.insert(index, value);
+                expected.insert(index, value);
+            }
+            1 if index < len => assert_eq!(vec.remove(index), expected.remove(index)),
+            2 => {
+                let value = rng.gen();
+                vec.push(value);
+                expected.push(value);
+            }
+            3 => assert_eq!(vec.pop(), expected.pop()),
+            4 => {
+                vec.reverse(range.clone());
+                expected[range].reverse();
+            }
+            5 => {
+                let mid = rng.gen_range(0..range.len() + 1);
+                vec.rotate_left(range.clone(), mid);
+                expected[range].rotate_left(mid);
+            }
+            6 => {
+                // swap the two halves
+                let mut tail = vec.split_off(index);
+                let mut expected_tail = expected.split_off(index);
+                assert_eq!(tail.len(), expected_tail.len());
+                tail.as_tree().assert_correctness();
+                tail.append(&mut vec);
+                expected_tail.append(&mut expected);
+                assert!(vec.is_empty());
+                vec = tail;
+                expected = expected_tail;
+            }
+            7 => {
+                if let Some(mut value) = vec.get_mut(index) {
+                    *value /= 2;
+                    expected[index] /= 2;
+                }
+            }
+            _ => {
+                if vec.with_value(index, |value| *value += 1).is_some() {
+                    expected[index] += 1;
+                }
+            }
+        }
+        assert_eq!(vec.get(index), expected.get(index));
+        assert_eq!(vec.len(), expected.len());
+    }
+
+    vec.as_tree().assert_correctness();
+    assert_eq!(vec.into_iter().collect::<Vec<_>>(), expected);
+}
+
+pub fn check_rope<T>()
+where
+    T: ConcatenableTree<grove::rope::RopeData>,
+    for<'a> &'a mut T: SplittableTreeRef<grove::rope::RopeData, T = T>,
+{
+    use grove::rope::*;
+
+    const PIECES: [&str; 6] = ["a", "bcd", "\n", "αβ", "😀x", "line\r\n"];
+    let mut rng = rand::thread_rng();
+    let random_text = |rng: &mut rand::prelude::ThreadRng, len: usize| -> String {
+        (0..len).map(|_| PIECES[rng.gen_range(0..PIECES.len())]).collect()
+    };
+    let initial = random_text(&mut rng, 1000);
+    let mut rope: Rope<T> = Rope::from(initial.as_str());
+    let mut expected = initial;
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let boundaries: Vec<usize> = expected
+            .char_indices()
+            .map(|(index, _)| index)
+            .chain(std::iter::once(expected.len()))
+            .collect();
+        let char_range = random_range(boundaries.len() - 1);
+        let (start, end) = (boundaries[char_range.start], boundaries[char_range.end]);
+        match rng.gen_range(0..5) {
+            0 | 1 => {
+                let max_len = if rng.gen() { 10 } else { 300 };
+                let len = rng.gen_range(0..max_len);
+                let text = random_text(&mut rng, len);
+                rope.insert_str(CharIndex(char_range.start), &text);
+                expected.insert_str(start, &text);
+            }
+            2 => {
+                rope.remove(ByteOffset(start)..ByteOffset(end));
+                expected.replace_range(start..end, "");
+            }
+            3 => {
+                let before = &expected[..start];
+                let line = before.matches('\n').count();
+                let col = before.chars().rev().take_while(|&c| c != '\n').count();
+                let summary = rope.summary_before(LineCol(line, col));
+                assert_eq!(summary.bytes, start);
+                assert_eq!(summary.chars, char_range.start);
+                let utf16 = before.encode_utf16().count();
+                assert_eq!(rope.byte_offset(Utf16Index(utf16)), start);
+            }
+            _ => {
+                assert_eq!(rope.slice(CharIndex(char_range.start)..CharIndex(char_range.end)), &expected[start..end]);
+            }
+        }
+        assert_eq!(rope.len_bytes(), expected.len());
+        assert_eq!(rope.len_chars(), expected.chars().count());
+        assert_eq!(rope.len_utf16(), expected.encode_utf16().count());
+    }
+
+    rope.as_tree().assert_correctness();
+    // edits merge the chunks around them, so no two adjacent chunks fit in one chunk of 512 bytes.
+    let chunk_lens: Vec<usize> = rope.chunks().map(str::len).collect();
+    assert!(chunk_lens.windows(2).all(|pair| pair[0] + pair[1] > 512));
+    assert!(rope.lines().eq(expected.lines().map(String::from)));
+    assert_eq!(String::from(rope), expected);
+}
+
+pub fn check_for_each_mut<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+    let mut expected: Vec<i32> = (0..INITIAL_SIZE as i32).collect();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let range = random_range(INITIAL_SIZE);
+        if rng.gen() {
+            let action = random_action(&mut rng);
+            tree.act_segment(action, range.clone());
+            for value in expected[range.clone()].iter_mut() {
+                *value = action.act(*value);
+            }
+            if action.to_reverse() {
+                expected[range].reverse();
+            }
+        } else {
+            let mut index = 0;
+            tree.for_each_mut(range.clone(), |value| {
+                *value = *value / 2 + index;
+                index += 1;
+            });
+            assert_eq!(index as usize, range.len());
+            for (index, value) in expected[range].iter_mut().enumerate() {
+                *value = *value / 2 + index as i32;
+            }
+        }
+        let range = random_range(INITIAL_SIZE);
+        let summary = tree.segment_summary(range.clone());
+        assert_eq!(summary.sum, expected[range.clone()].iter().sum::<i32>());
+        assert_eq!(summary.max, expected[range].iter().cloned().max());
+    }
+
+    tree.assert_correctness();
+    assert_eq!(tree.iter().cloned().collect::<Vec<_>>(), expected);
+}
+
+pub fn check_reverse_iteration<T>()
+where
+    T: SomeTree<StdNum> + IntoIterator<Item = i32>,
+    <T as IntoIterator>::IntoIter: DoubleEndedIterator,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let range = random_range(INITIAL_SIZE);
+        tree.act_segment(random_action(&mut rng), range);
+
+        let range = random_range(INITIAL_SIZE);
+        let mut forward: Vec<i32> = tree.iter_locator(range.clone()).cloned().collect();
+        let backward: Vec<i32> = tree.iter_rev_locator(range.clone()).cloned().collect();
+        assert_eq!(forward.len(), range.len());
+        forward.reverse();
+        assert_eq!(forward, backward);
+
+        // alternate between both ends
+        let mut iter = tree.iter_locator(range.clone());
+        let mut front = vec![];
+        let mut back = vec![];
+        loop {
+            let len = range.len() - front.len() - back.len();
+            assert_eq!(iter.size_hint(), (len, Some(len)));
+            assert_eq!(iter.len(), len);
+            let next = if rng.gen() {
+                iter.next().map(|&x| front.push(x))
+            } else {
+                iter.next_back().map(|&x| back.push(x))
+            };
+            if next.is_none() {
+                break;
+            }
+        }
+        back.reverse();
+        front.extend(back);
+        forward.reverse();
+        assert_eq!(front, forward);
+    }
+
+    tree.assert_correctness();
+    assert_eq!(tree.len(), INITIAL_SIZE);
+    let mut forward: Vec<i32> = tree.iter().cloned().collect();
+    forward.reverse();
+    let into_iter = tree.into_iter();
+    assert_eq!(into_iter.size_hint(), (INITIAL_SIZE, Some(INITIAL_SIZE)));
+    assert_eq!(into_iter.rev().collect::<Vec<_>>(), forward);
+}
+
+pub fn check_finger_search<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+    let mut walker = (&mut tree).walker();
+    let mut index: usize = 0;
+
+    for _ in 0..NUM_ROUNDS {
+        // mostly nearby searches, sometimes far away ones
+        index = if rng.gen_range(0..10) == 0 {
+            rng.gen_range(0..INITIAL_SIZE)
+        } else {
+            let index = index as i64 + rng.gen_range(-5..=5);
+            index.clamp(0, INITIAL_SIZE as i64 - 1) as usize
+        };
+        if rng.gen() {
+            walker.finger_search(index);
+            assert_eq!(walker.value(), Some(&(index as i32)));
+            assert_eq!(walker.right_summary().size(), INITIAL_SIZE - index - 1);
+        } else {
+            walker.finger_search(index..index);
+            assert!(walker.is_empty());
+            assert_eq!(walker.right_summary().size(), INITIAL_SIZE - index);
+        }
+        assert_eq!(walker.left_summary().size(), index);
+    }
+
+    drop(walker);
+    tree.assert_correctness();
+}
+
+/// Counts the nodes that finger searches look at, and checks that nearby searches
+/// take fewer steps than searches from the root.
+pub fn check_finger_search_steps<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    use example_data::NumSummary;
+    use locators::LocResult;
+
+    const SIZE: usize = 1 << 14;
+    const ROUNDS: usize = 5000;
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..SIZE as i32).collect();
+    let steps = std::cell::Cell::new(0);
+    // a locator for the given index, that counts the nodes it looks at
+    let at = |index: usize| {
+        let steps = &steps;
+        move |left: NumSummary, _: &i32, _: NumSummary| {
+            steps.set(steps.get() + 1);
+            match index.cmp(&left.size()) {
+                std::cmp::Ordering::Less => LocResult::GoLeft,
+                std::cmp::Ordering::Equal => LocResult::Accept,
+                std::cmp::Ordering::Greater => LocResult::GoRight,
+            }
+        }
+    };
+
+    // searching for all positions in order takes a constant amount of steps per search
+    let mut walker = (&mut tree).walker();
+    for index in 0..SIZE {
+        walker.finger_search(at(index));
+    }
+    assert!(steps.get() <= 8 * SIZE);
+
+    // searching from a random position for a nearby position
+    let mut targets = vec![];
+    let mut finger_steps = 0;
+    for _ in 0..ROUNDS {
+        let start = rng.gen_range(0..SIZE);
+        walker.finger_search(at(start));
+        let index = (start as i64 + rng.gen_range(-2..=2)).clamp(0, SIZE as i64 - 1) as usize;
+        steps.set(0);
+        walker.finger_search(at(index));
+        assert_eq!(walker.value(), Some(&(index as i32)));
+        finger_steps += steps.get();
+        targets.push(index);
+    }
+    drop(walker);
+
+    steps.set(0);
+    for &index in &targets {
+        (&mut tree).search(at(index));
+    }
+    let root_steps = steps.get();
+    assert!(3 * finger_steps < 2 * root_steps);
+
+    // neighbors at the edges of the tree, where one side has no boundary to pass
+    let mut finger_steps = 0;
+    let mut root_steps = 0;
+    for _ in 0..10 {
+        let mut tree: T = (0..SIZE as i32).collect();
+        let pairs = [(0, 1), (1, 0), (SIZE - 1, SIZE - 2), (SIZE - 2, SIZE - 1)];
+        for &(start, index) in &pairs {
+            let mut walker = (&mut tree).walker();
+            walker.finger_search(at(start));
+            steps.set(0);
+            walker.finger_search(at(index));
+            assert_eq!(walker.value(), Some(&(index as i32)));
+            finger_steps += steps.get();
+            drop(walker);
+
+            steps.set(0);
+            (&mut tree).search(at(index));
+            root_steps += steps.get();
+        }
+    }
+    assert!(2 * finger_steps < root_steps);
+}
+
+pub fn check_splitters<T>()
+where
+    T: SomeTree<SumValues>,
+    for<'a> &'a mut T: SplittableTreeRef<SumValues, T = T> + ModifiableTreeRef<SumValues>,
+{
+    use locators::{AfterKey, BeforeIndex, FirstWhere};
+
+    const MAX_KEY: i32 = 100;
+    let mut rng = rand::thread_rng();
+    let mut tree: T = T::default();
+    // sorted by key, and equal keys are kept in insertion order.
+    let mut expected: Vec<(i32, i64)> = vec![];
+
+    for round in 0..NUM_ROUNDS / 10 {
+        let key = rng.gen_range(0..MAX_KEY);
+        let entry = (key, round as i64);
+        match rng.gen_range(0..3) {
+            0 => tree.insert_at(AfterKey(&key), entry),
+            1 => tree.insert_at(FirstWhere(|&(k, _): &(i32, i64)| k > key), entry),
+            _ => {
+                let index = expected.partition_point(|&(k, _)| k <= key);
+                tree.insert_at(BeforeIndex(index), entry);
+            }
+        }
+        let index = expected.partition_point(|&(k, _)| k <= key);
+        expected.insert(index, entry);
+    }
+    tree.assert_correctness();
+
+    let index = rng.gen_range(0..=expected.len());
+    let (mut left, mut right) = tree.split_at(BeforeIndex(index));
+    left.assert_correctness();
+    right.assert_correctness();
+    assert!(left.iter().eq(expected[..index].iter()));
+    assert!(right.iter().eq(expected[index..].iter()));
+
+    let key = rng.gen_range(0..MAX_KEY);
+    let expected = &expected[index..];
+    let index = expected.partition_point(|&(k, _)| k <= key);
+    let (mut left, mut right) = right.split_at(AfterKey(&key));
+    left.assert_correctness();
+    right.assert_correctness();
+    assert!(left.iter().eq(expected[..index].iter()));
+    assert!(right.iter().eq(expected[index..].iter()));
+}
+
+pub fn check_summary_searches<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    use example_data::NumSummary;
+    use locators::{PrefixWhere, SuffixWhere};
+
+    let mut rng = rand::thread_rng();
+    let values: Vec<i32> = (0..INITIAL_SIZE).map(|_| rng.gen_range(0..MAX_ADD)).collect();
+    let mut tree: T = values.iter().cloned().collect();
+    let total: i32 = values.iter().sum();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let bound = rng.gen_range(0..total + MAX_ADD);
+        let prefix_sums: Vec<i32> = values
+            .iter()
+            .scan(0, |sum, x| {
+                *sum += x;
+                Some(*sum)
+            })
+            .collect();
+        let expected = prefix_sums.partition_point(|&sum| sum <= bound);
+        assert_eq!(tree.partition_point(|s: NumSummary| s.sum <= bound), expected);
+        assert_eq!(
+            tree.iter_locator(PrefixWhere(|s: NumSummary| s.sum > bound)).next(),
+            values.get(expected)
+        );
+
+        let suffix_sums: Vec<i32> = values
+            .iter()
+            .rev()
+            .scan(0, |sum, x| {
+                *sum += x;
+                Some(*sum)
+            })
+            .collect();
+        let from_end = suffix_sums.partition_point(|&sum| sum <= bound);
+        let walker = tree.search(SuffixWhere(|s: NumSummary| s.sum > bound));
+        assert_eq!(walker.value(), values.iter().rev().nth(from_end));
+        assert_eq!(walker.right_summary().size(), from_end);
+        drop(walker);
+
+        let threshold = rng.gen_range(0..MAX_ADD);
+        let expected = values.iter().position(|&x| x >= threshold).unwrap_or(INITIAL_SIZE);
+        assert_eq!(tree.partition_point(|s: NumSummary| s.max < Some(threshold)), expected);
+    }
+    tree.assert_correctness();
+}
+
+pub fn check_locator_combinators<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    use locators::LocatorBuilder;
+
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+    let values: Vec<i32> = (0..INITIAL_SIZE as i32).collect();
+    let sum = |range: std::ops::Range<usize>| values[range].iter().sum::<i32>();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let (a, b) = (random_range(INITIAL_SIZE), random_range(INITIAL_SIZE));
+        if a.is_empty() || b.is_empty() {
+            continue;
+        }
+        let intersection = a.start.max(b.start)..a.end.min(b.end).max(a.start.max(b.start));
+        assert_eq!(tree.segment_summary(a.clone().and(b.clone())).sum, sum(intersection));
+        let union = a.start.min(b.start)..a.end.max(b.end);
+        assert_eq!(tree.segment_summary(a.clone().or(b.clone())).sum, sum(union));
+        let until = a.start..b.start.max(a.start);
+        assert_eq!(tree.segment_summary(a.until(b)).sum, sum(until));
+    }
+    tree.assert_correctness();
+}
+
+fn random_disjoint_ranges(len: usize) -> Vec<std::ops::Range<usize>> {
+    let mut rng = rand::thread_rng();
+    let mut bounds: Vec<usize> = (0..rng.gen_range(0..20)).map(|_| rng.gen_range(0..=len)).collect();
+    bounds.sort_unstable();
+    bounds.chunks_exact(2).map(|pair| pair[0]..pair[1]).collect()
+}
+
+pub fn check_multi_segments<T>()
+where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree1: T = (0..INITIAL_SIZE as i32).collect();
+    let mut tree2: T = (0..INITIAL_SIZE as i32).collect();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let ranges = random_disjoint_ranges(INITIAL_SIZE);
+        if rng.gen() {
+            let action = random_action(&mut rng);
+            tree1.act_segments(action, &ranges);
+            for range in ranges.iter() {
+                tree2.act_segment(action, range);
+            }
+        } else {
+            let summaries = tree1.segment_summaries(&ranges);
+            assert_eq!(summaries.len(), ranges.len());
+            for (summary, range) in summaries.into_iter().zip(ranges.iter()) {
+                assert_eq!(summary, tree2.segment_summary(range));
+                assert_eq!(summary.size(), range.len());
+            }
+        }
+    }
+
+    tree1.assert_correctness();
+    assert!(tree1.iter().eq(tree2.iter()));
+}
+
+pub fn check_rank_select<T>()
+where
+    T: SomeTree<SumValues>,
+    for<'a> &'a mut T: SomeTreeRef<SumValues>,
+{
+    const MAX_KEY: i32 = 100;
+    let mut rng = rand::thread_rng();
+    let mut entries: Vec<(i32, i64)> = (0..INITIAL_SIZE)
+        .map(|i| (rng.gen_range(0..MAX_KEY), i as i64))
+        .collect();
+    entries.sort_unstable();
+    let mut tree: T = entries.iter().cloned().collect();
+
+    for _ in 0..NUM_ROUNDS / 10 {
+        let index = rng.gen_range(0..=INITIAL_SIZE);
+        assert_eq!(tree.select(index), entries.get(index));
+        let range = random_range(INITIAL_SIZE);
+        assert_eq!(tree.rank(range.clone()), range.start);
+        let mut walker = tree.search(index);
+        assert_eq!(walker.index(), index);
+        walker.finger_search(range.start..range.start);
+        assert_eq!(walker.index(), range.start);
+        drop(walker);
+
+        let (a, b) = (rng.gen_range(0..MAX_KEY), rng.gen_range(0..MAX_KEY));
+        let smaller = |key: i32| entries.partition_point(|&(k, _)| k < key);
+        assert_eq!(tree.rank_of_key(&a), smaller(a));
+        let expected = if a <= b { smaller(b) - smaller(a) } else { 0 };
+        assert_eq!(tree.count_in_key_range(a..b), expected);
+        assert_eq!(tree.count_in_key_range(a..), INITIAL_SIZE - smaller(a));
+    }
+    tree.assert_correctness();
+}
+
+#[cfg(feature = "serde")]
+pub fn check_serde<T>(supports_reversals: bool)
+where
+    T: SomeTree<StdNum> + serde::Serialize + serde::de::DeserializeOwned,
+    for<'a> &'a mut T: ModifiableTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+    for _ in 0..NUM_ROUNDS / 100 {
+        let mut action = random_action(&mut rng);
+        action.to_reverse &= supports_reversals;
+        tree.act_segment(action, random_range(INITIAL_SIZE));
+    }
+
+    // serializing shouldn't require pushing the pending actions down first.
+    let json = serde_json::to_string(&tree).unwrap();
+    let values: Vec<i32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(values, tree.iter().cloned().collect::<Vec<_>>());
+
+    let mut copy: T = serde_json::from_str(&json).unwrap();
+    copy.assert_correctness();
+    assert_eq!(copy.segment_summary(..), tree.segment_summary(..));
+    assert!(copy.iter().eq(values.iter()));
+
+    let summary = tree.segment_summary(3..50);
+    let json = serde_json::to_string(&summary).unwrap();
+    assert_eq!(serde_json::from_str::<example_data::NumSummary>(&json).unwrap(), summary);
+    let action = random_action(&mut rng);
+    let json = serde_json::to_string(&action).unwrap();
+    assert_eq!(serde_json::from_str::<RevAffineAction>(&json).unwrap(), action);
+}
+
+/// A value that can't be cloned, to check that serializing doesn't clone values.
+#[cfg(feature = "serde")]
+#[derive(PartialEq, Eq, Debug, serde::Serialize)]
+pub struct NoClone(i32);
+
+#[cfg(feature = "serde")]
+pub fn check_serialize_ref<T>(
+    serialize_ref: fn(&T, &mut serde_json::Serializer<Vec<u8>>) -> serde_json::Result<()>,
+) where
+    T: SomeTree<example_data::SequenceData<NoClone>>,
+    for<'a> &'a mut T: ModifiableTreeRef<example_data::SequenceData<NoClone>>,
+{
+    let mut tree: T = (0..INITIAL_SIZE as i32).map(NoClone).collect();
+    let mut model: Vec<i32> = (0..INITIAL_SIZE as i32).collect();
+    for _ in 0..NUM_ROUNDS / 100 {
+        let range = random_range(INITIAL_SIZE);
+        tree.act_segment(example_data::ReverseOrder { to_reverse: true }, range.clone());
+        model[range].reverse();
+    }
+
+    let mut serializer = serde_json::Serializer::new(vec![]);
+    serialize_ref(&tree, &mut serializer).unwrap();
+    assert_eq!(serializer.into_inner(), serde_json::to_vec(&model).unwrap());
+}
+
+#[cfg(feature = "snapshot")]
+use grove::basic_tree::{snapshot, BasicTree};
+
+#[cfg(feature = "snapshot")]
+pub fn check_snapshot<T>(
+    to_snapshot: fn(&T) -> Vec<u8>,
+    from_snapshot: fn(&[u8]) -> Result<T, snapshot::SnapshotError>,
+    supports_reversals: bool,
+) where
+    T: SomeTree<StdNum>,
+    for<'a> &'a mut T: ModifiableTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+    for _ in 0..NUM_ROUNDS / 100 {
+        let mut action = random_action(&mut rng);
+        action.to_reverse &= supports_reversals;
+        tree.act_segment(action, random_range(INITIAL_SIZE));
+        let index = rng.gen_range(0..INITIAL_SIZE);
+        let value = tree.search(index).delete().unwrap();
+        tree.search(index..index).insert(value);
+    }
+
+    // the restored tree should be identical, and keep behaving identically.
+    let bytes = to_snapshot(&tree);
+    let mut copy = from_snapshot(&bytes).unwrap();
+    copy.assert_correctness();
+    assert_eq!(to_snapshot(&copy), bytes);
+    for _ in 0..NUM_ROUNDS / 100 {
+        let range = random_range(INITIAL_SIZE);
+        assert_eq!(copy.segment_summary(&range), tree.segment_summary(&range));
+        assert_eq!(to_snapshot(&copy), to_snapshot(&tree));
+    }
+    assert!(copy.iter().eq(tree.iter()));
+
+    let bytes = to_snapshot(&tree);
+    assert!(matches!(
+        from_snapshot(&bytes[..bytes.len() - 1]),
+        Err(snapshot::SnapshotError::Decode(_))
+    ));
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(matches!(
+        from_snapshot(&longer),
+        Err(snapshot::SnapshotError::TrailingBytes)
+    ));
+    let mut corrupted = bytes.clone();
+    corrupted[0] ^= 1;
+    assert!(matches!(
+        from_snapshot(&corrupted),
+        Err(snapshot::SnapshotError::BadMagic)
+    ));
+    // the tree kind is stored right after the magic number and the version.
+    let mut corrupted = bytes;
+    corrupted[5] ^= 1;
+    assert!(matches!(
+        from_snapshot(&corrupted),
+        Err(snapshot::SnapshotError::WrongTreeKind { .. })
+    ));
+}
+
+/// Same as `StdNum`, but computes the wrong sums.
+#[cfg(feature = "snapshot")]
+struct WrongSums {}
+
+#[cfg(feature = "snapshot")]
+impl Data for WrongSums {
+    type Value = i32;
+    type Summary = example_data::NumSummary;
+    type Action = RevAffineAction;
+
+    fn to_summary(val: &i32) -> Self::Summary {
+        let mut summary = StdNum::to_summary(val);
+        summary.sum += 1;
+        summary
+    }
+
+    fn segment_len(summary: Self::Summary) -> Option<usize> {
+        StdNum::segment_len(summary)
+    }
+}
+
+#[cfg(feature = "snapshot")]
+impl SizedData for WrongSums {}
+
+#[cfg(feature = "snapshot")]
+pub fn check_snapshot_validation() {
+    let tree: BasicTree<WrongSums> = (0..INITIAL_SIZE as i32).collect();
+    let bytes = snapshot::write(&tree, snapshot::TreeKind::Basic);
+    assert!(matches!(
+        BasicTree::<StdNum>::from_snapshot(&bytes),
+        Err(snapshot::SnapshotError::IncorrectSummary { node: 0 })
+    ));
+
+    // a single node should have rank 1.
+    let tree: BasicTree<StdNum, u8> = BasicTree::from_node(basic_tree::BasicNode::new_alg(7, 0));
+    let bytes = snapshot::write(&tree, snapshot::TreeKind::AVL);
+    assert!(matches!(
+        grove::avl::AVLTree::<StdNum>::from_snapshot(&bytes),
+        Err(snapshot::SnapshotError::InvalidAlgData { node: 0 })
+    ));
+    let tree: BasicTree<StdNum, u8> = BasicTree::from_node(basic_tree::BasicNode::new_alg(7, 1));
+    let bytes = snapshot::write(&tree, snapshot::TreeKind::AVL);
+    let mut avl = grove::avl::AVLTree::<StdNum>::from_snapshot(&bytes).unwrap();
+    assert_eq!(avl.iter().cloned().collect::<Vec<_>>(), vec![7]);
+}
+
+pub fn check_debug_output<T>()
+where
+    T: SomeTree<StdNum> + grove::debug::DebugTree<StdNum>,
+    for<'a> &'a mut T: SomeTreeRef<StdNum>,
+{
+    let mut rng = rand::thread_rng();
+    let mut tree: T = (0..INITIAL_SIZE as i32).collect();
+    for _ in 0..10 {
+        let action = RevAffineAction {
+            to_reverse: true,
+            ..random_action(&mut rng)
+        };
+        tree.act_segment(action, random_range(INITIAL_SIZE));
+    }
+    // some trees push reversals down immediately, so make sure the root has a pending reversal.
+    tree.act_subtree(RevAffineAction {
+        to_reverse: true,
+        mul: 1,
+        add: 0,
+    });
+
+    let ascii = grove::debug::to_ascii(&tree);
+    let dot = grove::debug::to_dot(&tree);
+    let node_lines: Vec<&str> = ascii.lines().filter(|line| !line.ends_with('*')).collect();
+    assert_eq!(node_lines.len(), INITIAL_SIZE);
+    assert_eq!(dot.matches("[label=\"L\"]").count(), INITIAL_SIZE);
+    assert_eq!(dot.matches("[label=\"R\"]").count(), INITIAL_SIZE);
+    let reversals = node_lines
+        .iter()
+        .filter(|line| line.ends_with("pending reversal"))
+        .count();
+    assert!(node_lines[0].ends_with("pending reversal"));
+    assert_eq!(dot.matches("style=dashed").count(), reversals);
+}
+
+#[cfg(feature = "testing")]
+fn random_std_num_summary(rng: &mut rand::prelude::ThreadRng) -> example_data::NumSummary {
+    let len = rng.gen_range(0..5);
+    (0..len)
+        .map(|_| StdNum::to_summary(&rng.gen_range(-1000..1000)))
+        .fold(Default::default(), |a, b| a + b)
+}
+
+#[cfg(feature = "testing")]
+pub fn check_example_data_laws() {
+    use example_data::{ReverseOrder, SequenceData, Size};
+    use grove::laws;
+
+    let rng = std::cell::RefCell::new(rand::thread_rng());
+    laws::check_random::<StdNum, _, _, _>(
+        || rng.borrow_mut().gen_range(-1000..1000),
+        || random_std_num_summary(&mut rng.borrow_mut()),
+        || random_action(&mut rng.borrow_mut()),
+        NUM_ROUNDS / 10,
+    )
+    .unwrap();
+
+    let sizes: Vec<Size> = (0..4).map(|size| Size { size }).collect();
+    let reversals = [false, true].map(|to_reverse| ReverseOrder { to_reverse });
+    laws::check_exhaustive::<SequenceData<i32>>(&[0, 1, 2], &sizes, &reversals).unwrap();
+
+    let summaries: Vec<SizeSum> = [(0, 0), (1, 3), (2, -5)]
+        .iter()
+        .map(|&(size, sum)| SizeSum { size, sum })
+        .collect();
+    let actions = [AddToValues(1), AddToValues(-7)];
+    laws::check_exhaustive::<SumValues>(&[(1, 2), (3, -4)], &summaries, &actions).unwrap();
+}
+
+/// Same as `StdNum`, but the sums of single values are off by one.
+#[cfg(feature = "testing")]
+struct ShiftedSums {}
+
+#[cfg(feature = "testing")]
+impl Data for ShiftedSums {
+    type Value = i32;
+    type Summary = example_data::NumSummary;
+    type Action = RevAffineAction;
+
+    fn to_summary(val: &i32) -> Self::Summary {
+        let mut summary = StdNum::to_summary(val);
+        summary.sum += 1;
+        summary
+    }
+
+    fn segment_len(summary: Self::Summary) -> Option<usize> {
+        StdNum::segment_len(summary)
+    }
+}
+
+#[cfg(feature = "testing")]
+impl SizedData for ShiftedSums {}
+
+#[cfg(feature = "testing")]
+pub fn check_law_violations() {
+    use grove::laws;
+
+    let rng = std::cell::RefCell::new(rand::thread_rng());
+    let violation = laws::check_random::<ShiftedSums, _, _, _>(
+        || rng.borrow_mut().gen_range(-1000..1000),
+        || random_std_num_summary(&mut rng.borrow_mut()),
+        || random_action(&mut rng.borrow_mut()),
+        NUM_ROUNDS / 10,
+    )
+    .unwrap_err();
+    assert_eq!(violation.law, "actions respect to_summary");
+    assert!(violation.to_string().contains("counterexample: action1 = RevAffineAction"));
+
+    // the off-by-one sums only show up when multiplying values.
+    let identity = RevAffineAction::default();
+    let summary = ShiftedSums::to_summary(&1);
+    assert!(laws::check_exhaustive::<ShiftedSums>(&[1], &[summary], &[identity]).is_ok());
+}
@@ -1,7 +1,7 @@
 mod common;
 use common::*;
 
-use grove::{avl::AVLTree, splay::SplayTree, treap::Treap, basic_tree::BasicTree};
+use grove::{avl::AVLTree, splay::SplayTree, treap::Treap, red_black::RBTree, scapegoat::ScapegoatTree, weight_balanced::WBTree, basic_tree::BasicTree};
 
 #[test]
 fn splay_and_treap_consistency() {
@@ -11,6 +11,97 @@ fn splay_and_treap_consistency() {
 fn splay_and_avl_consistency() {
     check_consistency::<SplayTree<_>, AVLTree<_>>();
 }
+#[test]
+fn splay_and_red_black_consistency() {
+    check_consistency::<SplayTree<_>, RBTree<_>>();
+}
+#[test]
+fn splay_and_scapegoat_consistency() {
+    check_consistency::<SplayTree<_>, ScapegoatTree<_>>();
+}
+#[test]
+fn splay_and_weight_balanced_consistency() {
+    check_consistency::<SplayTree<_>, WBTree<_>>();
+}
+#[test]
+fn splay_and_persistent_treap_consistency() {
+    check_persistent_consistency::<SplayTree<_>>();
+}
+
+#[test]
+fn basic_immutable_queries() {
+    check_immutable_queries::<BasicTree<_>>(false);
+}
+#[test]
+fn treap_immutable_queries() {
+    check_immutable_queries::<Treap<_>>(true);
+}
+#[test]
+fn avl_immutable_queries() {
+    check_immutable_queries::<AVLTree<_>>(true);
+}
+
+#[test]
+fn basic_concurrent_queries() {
+    check_concurrent_queries::<BasicTree<_>>(false);
+}
+#[test]
+fn treap_concurrent_queries() {
+    check_concurrent_queries::<Treap<_>>(true);
+}
+#[test]
+fn avl_concurrent_queries() {
+    check_concurrent_queries::<AVLTree<_>>(true);
+}
+
+#[test]
+fn treap_set_operations_trait() {
+    check_set_operations::<Treap<_>>();
+}
+#[test]
+fn avl_set_operations() {
+    check_set_operations::<AVLTree<_>>();
+}
+#[test]
+fn splay_set_operations() {
+    check_set_operations::<SplayTree<_>>();
+}
+#[test]
+fn red_black_set_operations() {
+    check_set_operations::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_set_operations() {
+    check_set_operations::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_set_operations() {
+    check_set_operations::<WBTree<_>>();
+}
+#[test]
+fn treap_union_order() {
+    check_union_order::<Treap<_>>();
+}
+#[test]
+fn avl_union_order() {
+    check_union_order::<AVLTree<_>>();
+}
+#[test]
+fn splay_union_order() {
+    check_union_order::<SplayTree<_>>();
+}
+#[test]
+fn red_black_union_order() {
+    check_union_order::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_union_order() {
+    check_union_order::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_union_order() {
+    check_union_order::<WBTree<_>>();
+}
 
 #[test]
 fn splay_insert() {
@@ -22,6 +113,21 @@ fn avl_insert() {
     check_insert::<AVLTree<_>>(false);
 }
 
+#[test]
+fn red_black_insert() {
+    check_insert::<RBTree<_>>(false);
+}
+
+#[test]
+fn scapegoat_insert() {
+    check_insert::<ScapegoatTree<_>>(false);
+}
+
+#[test]
+fn weight_balanced_insert() {
+    check_insert::<WBTree<_>>(false);
+}
+
 #[test]
 fn treap_insert() {
     check_insert::<Treap<_>>(true);
@@ -42,6 +148,21 @@ fn avl_delete() {
     check_delete::<AVLTree<_>>();
 }
 
+#[test]
+fn red_black_delete() {
+    check_delete::<RBTree<_>>();
+}
+
+#[test]
+fn scapegoat_delete() {
+    check_delete::<ScapegoatTree<_>>();
+}
+
+#[test]
+fn weight_balanced_delete() {
+    check_delete::<WBTree<_>>();
+}
+
 #[test]
 fn treap_delete() {
     check_delete::<Treap<_>>();
@@ -51,3 +172,438 @@ fn treap_delete() {
 fn basic_delete() {
     check_delete::<BasicTree<_>>();
 }
+
+#[cfg(feature = "rayon")]
+#[test]
+fn treap_parallel_set_operations() {
+    use grove::example_data::{NoAction, Ordered};
+    use grove::treap;
+    use grove::SomeTree;
+    use rand::Rng;
+    use rayon::prelude::*;
+
+    type D = NoAction<Ordered<i32>>;
+    let mut rng = rand::thread_rng();
+    let mut values1: Vec<i32> = (0..20_000).map(|_| rng.gen_range(0..30_000)).collect();
+    let mut values2: Vec<i32> = (0..20_000).map(|_| rng.gen_range(0..30_000)).collect();
+    values1.sort_unstable();
+    values2.sort_unstable();
+    let make_tree = |values: &Vec<i32>| -> Treap<D> { values.par_iter().map(|&x| Ordered(x)).collect() };
+
+    let tree = treap::par_union(make_tree(&values1), make_tree(&values2));
+    tree.assert_correctness();
+    let mut expected: Vec<i32> = values1.iter().chain(values2.iter()).cloned().collect();
+    expected.sort_unstable();
+    assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+    let tree = treap::par_intersection(make_tree(&values1), make_tree(&values2));
+    tree.assert_correctness();
+    let expected: Vec<i32> = values1.iter().filter(|x| values2.binary_search(x).is_ok()).cloned().collect();
+    assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+    let tree = treap::par_difference(make_tree(&values1), make_tree(&values2));
+    tree.assert_correctness();
+    let expected: Vec<i32> = values1.iter().filter(|x| values2.binary_search(x).is_err()).cloned().collect();
+    assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+    let tree: AVLTree<D> = values1.par_iter().map(|&x| Ordered(x)).collect();
+    tree.assert_correctness();
+    assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), values1);
+    let tree: BasicTree<D> = values1.par_iter().map(|&x| Ordered(x)).collect();
+    tree.assert_correctness();
+    assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), values1);
+}
+
+#[test]
+fn treap_set_operations() {
+    use grove::example_data::{NoAction, Ordered};
+    use grove::treap;
+    use grove::SomeTree;
+    use rand::Rng;
+
+    type D = NoAction<Ordered<i32>>;
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        // many duplicate keys
+        let mut values1: Vec<i32> = (0..1000).map(|_| rng.gen_range(0..500)).collect();
+        let mut values2: Vec<i32> = (0..1000).map(|_| rng.gen_range(0..500)).collect();
+        values1.sort_unstable();
+        values2.sort_unstable();
+        let make_tree = |values: &Vec<i32>| -> Treap<D> { values.iter().map(|&x| Ordered(x)).collect() };
+        let contains = |values: &Vec<i32>, x: &i32| values.binary_search(x).is_ok();
+
+        let tree = treap::intersection(make_tree(&values1), make_tree(&values2));
+        tree.assert_correctness();
+        let expected: Vec<i32> = values1.iter().filter(|x| contains(&values2, x)).cloned().collect();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+        let tree = treap::intersection_with(make_tree(&values1), make_tree(&values2), |x, _| x);
+        tree.assert_correctness();
+        let mut expected = expected;
+        expected.dedup();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+        let tree = treap::difference(make_tree(&values1), make_tree(&values2));
+        tree.assert_correctness();
+        let expected: Vec<i32> = values1.iter().filter(|x| !contains(&values2, x)).cloned().collect();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+
+        let tree = treap::symmetric_difference(make_tree(&values1), make_tree(&values2));
+        tree.assert_correctness();
+        let mut expected: Vec<i32> = values1
+            .iter()
+            .filter(|x| !contains(&values2, x))
+            .chain(values2.iter().filter(|x| !contains(&values1, x)))
+            .cloned()
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(tree.into_iter().map(|x| x.0).collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn treap_map() {
+    check_map::<Treap<_>>();
+}
+#[test]
+fn avl_map() {
+    check_map::<AVLTree<_>>();
+}
+#[test]
+fn splay_map() {
+    check_map::<SplayTree<_>>();
+}
+#[test]
+fn red_black_map() {
+    check_map::<RBTree<_>>();
+}
+
+#[test]
+fn treap_vec() {
+    check_vec::<Treap<_>>();
+}
+#[test]
+fn avl_vec() {
+    check_vec::<AVLTree<_>>();
+}
+#[test]
+fn splay_vec() {
+    check_vec::<SplayTree<_>>();
+}
+#[test]
+fn red_black_vec() {
+    check_vec::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_vec() {
+    check_vec::<ScapegoatTree<_>>();
+}
+
+#[test]
+fn treap_rope() {
+    check_rope::<Treap<_>>();
+}
+#[test]
+fn avl_rope() {
+    check_rope::<AVLTree<_>>();
+}
+#[test]
+fn splay_rope() {
+    check_rope::<SplayTree<_>>();
+}
+#[test]
+fn scapegoat_rope() {
+    check_rope::<ScapegoatTree<_>>();
+}
+
+#[test]
+fn treap_for_each_mut() {
+    check_for_each_mut::<Treap<_>>();
+}
+#[test]
+fn splay_for_each_mut() {
+    check_for_each_mut::<SplayTree<_>>();
+}
+#[test]
+fn avl_for_each_mut() {
+    check_for_each_mut::<AVLTree<_>>();
+}
+#[test]
+fn red_black_for_each_mut() {
+    check_for_each_mut::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_for_each_mut() {
+    check_for_each_mut::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_for_each_mut() {
+    check_for_each_mut::<WBTree<_>>();
+}
+
+#[test]
+fn treap_reverse_iteration() {
+    check_reverse_iteration::<Treap<_>>();
+}
+#[test]
+fn splay_reverse_iteration() {
+    check_reverse_iteration::<SplayTree<_>>();
+}
+#[test]
+fn avl_reverse_iteration() {
+    check_reverse_iteration::<AVLTree<_>>();
+}
+#[test]
+fn red_black_reverse_iteration() {
+    check_reverse_iteration::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_reverse_iteration() {
+    check_reverse_iteration::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_reverse_iteration() {
+    check_reverse_iteration::<WBTree<_>>();
+}
+
+#[test]
+fn treap_finger_search() {
+    check_finger_search::<Treap<_>>();
+}
+#[test]
+fn splay_finger_search() {
+    check_finger_search::<SplayTree<_>>();
+}
+#[test]
+fn avl_finger_search() {
+    check_finger_search::<AVLTree<_>>();
+}
+#[test]
+fn red_black_finger_search() {
+    check_finger_search::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_finger_search() {
+    check_finger_search::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_finger_search() {
+    check_finger_search::<WBTree<_>>();
+}
+#[test]
+fn treap_finger_search_steps() {
+    check_finger_search_steps::<Treap<_>>();
+}
+#[test]
+fn avl_finger_search_steps() {
+    check_finger_search_steps::<AVLTree<_>>();
+}
+#[test]
+fn red_black_finger_search_steps() {
+    check_finger_search_steps::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_finger_search_steps() {
+    check_finger_search_steps::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_finger_search_steps() {
+    check_finger_search_steps::<WBTree<_>>();
+}
+
+#[test]
+fn treap_splitters() {
+    check_splitters::<Treap<_>>();
+}
+#[test]
+fn avl_splitters() {
+    check_splitters::<AVLTree<_>>();
+}
+#[test]
+fn splay_splitters() {
+    check_splitters::<SplayTree<_>>();
+}
+#[test]
+fn red_black_splitters() {
+    check_splitters::<RBTree<_>>();
+}
+
+#[test]
+fn treap_summary_searches() {
+    check_summary_searches::<Treap<_>>();
+}
+#[test]
+fn splay_summary_searches() {
+    check_summary_searches::<SplayTree<_>>();
+}
+#[test]
+fn avl_summary_searches() {
+    check_summary_searches::<AVLTree<_>>();
+}
+#[test]
+fn basic_tree_summary_searches() {
+    check_summary_searches::<BasicTree<_>>();
+}
+
+#[test]
+fn treap_locator_combinators() {
+    check_locator_combinators::<Treap<_>>();
+}
+#[test]
+fn avl_locator_combinators() {
+    check_locator_combinators::<AVLTree<_>>();
+}
+
+#[test]
+fn treap_multi_segments() {
+    check_multi_segments::<Treap<_>>();
+}
+#[test]
+fn splay_multi_segments() {
+    check_multi_segments::<SplayTree<_>>();
+}
+#[test]
+fn avl_multi_segments() {
+    check_multi_segments::<AVLTree<_>>();
+}
+#[test]
+fn red_black_multi_segments() {
+    check_multi_segments::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_multi_segments() {
+    check_multi_segments::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_multi_segments() {
+    check_multi_segments::<WBTree<_>>();
+}
+
+#[test]
+fn treap_rank_select() {
+    check_rank_select::<Treap<_>>();
+}
+#[test]
+fn splay_rank_select() {
+    check_rank_select::<SplayTree<_>>();
+}
+#[test]
+fn avl_rank_select() {
+    check_rank_select::<AVLTree<_>>();
+}
+#[test]
+fn red_black_rank_select() {
+    check_rank_select::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_rank_select() {
+    check_rank_select::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_rank_select() {
+    check_rank_select::<WBTree<_>>();
+}
+#[test]
+fn basic_tree_rank_select() {
+    check_rank_select::<BasicTree<_>>();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn treap_serde() {
+    check_serde::<Treap<_>>(true);
+}
+#[cfg(feature = "serde")]
+#[test]
+fn splay_serde() {
+    check_serde::<SplayTree<_>>(true);
+}
+#[cfg(feature = "serde")]
+#[test]
+fn avl_serde() {
+    check_serde::<AVLTree<_>>(true);
+}
+#[cfg(feature = "serde")]
+#[test]
+fn basic_tree_serde() {
+    check_serde::<BasicTree<_>>(false);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn treap_serialize_ref() {
+    check_serialize_ref::<Treap<_>>(|tree, serializer| tree.serialize_ref(serializer));
+}
+#[cfg(feature = "serde")]
+#[test]
+fn splay_serialize_ref() {
+    check_serialize_ref::<SplayTree<_>>(|tree, serializer| tree.serialize_ref(serializer));
+}
+#[cfg(feature = "serde")]
+#[test]
+fn avl_serialize_ref() {
+    check_serialize_ref::<AVLTree<_>>(|tree, serializer| tree.serialize_ref(serializer));
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn treap_snapshot() {
+    check_snapshot::<Treap<_>>(Treap::to_snapshot, Treap::from_snapshot, true);
+}
+#[cfg(feature = "snapshot")]
+#[test]
+fn splay_snapshot() {
+    check_snapshot::<SplayTree<_>>(SplayTree::to_snapshot, SplayTree::from_snapshot, true);
+}
+#[cfg(feature = "snapshot")]
+#[test]
+fn avl_snapshot() {
+    check_snapshot::<AVLTree<_>>(AVLTree::to_snapshot, AVLTree::from_snapshot, true);
+}
+#[cfg(feature = "snapshot")]
+#[test]
+fn basic_tree_snapshot() {
+    check_snapshot::<BasicTree<_>>(BasicTree::to_snapshot, BasicTree::from_snapshot, false);
+}
+#[cfg(feature = "snapshot")]
+#[test]
+fn snapshot_validation() {
+    check_snapshot_validation();
+}
+
+#[test]
+fn treap_debug_output() {
+    check_debug_output::<Treap<_>>();
+}
+#[test]
+fn splay_debug_output() {
+    check_debug_output::<SplayTree<_>>();
+}
+#[test]
+fn avl_debug_output() {
+    check_debug_output::<AVLTree<_>>();
+}
+#[test]
+fn red_black_debug_output() {
+    check_debug_output::<RBTree<_>>();
+}
+#[test]
+fn scapegoat_debug_output() {
+    check_debug_output::<ScapegoatTree<_>>();
+}
+#[test]
+fn weight_balanced_debug_output() {
+    check_debug_output::<WBTree<_>>();
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn example_data_laws() {
+    check_example_data_laws();
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn law_violations() {
+    check_law_violations();
+}